@@ -0,0 +1,82 @@
+//! Renders the rurl(1) man page from the option table in `src/args/options.rs`,
+//! which `rurl --man` prints.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/args/options.rs"]
+mod options;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/args/options.rs");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(
+        Path::new(&out_dir).join("rurl.1"),
+        man_page(env!("CARGO_PKG_VERSION")),
+    )
+    .expect("failed to write the man page");
+}
+
+/// The man page in roff, with one subsection of OPTIONS per help topic.
+fn man_page(version: &str) -> String {
+    let mut page = format!(".TH RURL 1 \"\" \"rurl {}\" \"User Commands\"\n", version);
+    page.push_str(".SH NAME\nrurl \\- a minimal HTTP client\n");
+
+    page.push_str(".SH SYNOPSIS\n");
+    for usage in options::USAGE {
+        page.push_str(&format!(".B rurl\n{}\n.br\n", escape(usage)));
+    }
+
+    page.push_str(".SH DESCRIPTION\n");
+    page.push_str(".B rurl\nsends HTTP requests to the given URLs and writes the responses, much like curl.\n");
+    page.push_str(
+        "Run\n.B rurl \\-\\-help\n.I TOPIC\nto list one group of options; the topics are\n",
+    );
+    let names: Vec<&str> = options::TOPICS.iter().map(|topic| topic.name).collect();
+    page.push_str(&format!("{}.\n", names.join(", ")));
+
+    page.push_str(".SH OPTIONS\n");
+    for topic in options::TOPICS {
+        page.push_str(&format!(".SS {}\n", escape(topic.title)));
+        for option in options::OPTIONS
+            .iter()
+            .filter(|option| option.topic == topic.name)
+        {
+            page.push_str(&format!(
+                ".TP\n.B {}\n{}\n",
+                escape(option.usage),
+                escape(option.help)
+            ));
+        }
+    }
+
+    page.push_str(".SH FILES\n");
+    for (name, help) in options::CONFIG {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", escape(name), escape(help)));
+    }
+
+    page.push_str(".SH ENVIRONMENT\n");
+    for (name, help) in options::ENVIRONMENT {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", escape(name), escape(help)));
+    }
+
+    page.push_str(".SH EXAMPLES\n.nf\n");
+    for example in options::EXAMPLES {
+        page.push_str(&format!("{}\n", escape(example)));
+    }
+    page.push_str(".fi\n");
+    page
+}
+
+/// Escape `text` for roff: backslashes and hyphens, and a leading `.` or `'` that
+/// would start a request.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with(['.', '\'']) {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
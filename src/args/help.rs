@@ -0,0 +1,81 @@
+use super::options::{Topic, CONFIG, ENVIRONMENT, EXAMPLES, OPTIONS, TOPICS, USAGE};
+
+/// The man page that `build.rs` renders from the option table
+const MAN_PAGE: &str = include_str!(concat!(env!("OUT_DIR"), "/rurl.1"));
+
+/// Print the help for `--help`: every option grouped by topic, or with `topic` only
+/// the options of that group. `all` is the same as no topic.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once printed, or an error listing the topics if `topic` is not one of them.
+pub fn print_help(topic: Option<&str>) -> Result<(), String> {
+    if let Some(name) = topic.filter(|name| !name.eq_ignore_ascii_case("all")) {
+        let topic = TOPICS
+            .iter()
+            .find(|topic| topic.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown help topic '{}' (topics: {}, all)",
+                    name,
+                    topic_names()
+                )
+            })?;
+        println!("rurl - {}", topic.title);
+        println!();
+        print_options(topic);
+        return Ok(());
+    }
+
+    println!("rurl - A minimal HTTP client");
+    println!();
+    println!("Usage:");
+    for usage in USAGE {
+        println!("    rurl {}", usage);
+    }
+    for topic in TOPICS {
+        println!();
+        println!("{}:", topic.title);
+        print_options(topic);
+    }
+    println!();
+    println!("Config:");
+    for (name, help) in CONFIG {
+        println!("    {:<25} {}", name, help);
+    }
+    println!();
+    println!("Environment Variables:");
+    for (name, help) in ENVIRONMENT {
+        println!("    {:<25} {}", name, help);
+    }
+    println!();
+    println!("Examples:");
+    for example in EXAMPLES {
+        println!("    {}", example);
+    }
+    println!();
+    println!(
+        "Show one group with --help <TOPIC>, where TOPIC is one of: {}",
+        topic_names()
+    );
+    Ok(())
+}
+
+/// Print the man page for `--man`.
+pub fn print_man() {
+    print!("{}", MAN_PAGE);
+}
+
+fn print_options(topic: &Topic) {
+    for option in OPTIONS.iter().filter(|option| option.topic == topic.name) {
+        println!("    {:<25} {}", option.usage, option.help);
+    }
+}
+
+fn topic_names() -> String {
+    TOPICS
+        .iter()
+        .map(|topic| topic.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,51 +1,365 @@
+mod help;
+pub mod options;
+
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{self, Clock};
+use crate::config;
+use crate::cookies;
+use crate::http::multipart::{self, Part};
+use crate::http::paginate;
+use crate::http::proxy;
+use crate::http::range;
+use crate::http::reader::DEFAULT_BUFFER_SIZE;
+use crate::http::request;
+use crate::http::resolve;
+use crate::http::response;
+use crate::http::timeout::CONNECT_TIMEOUT;
+use crate::netrc;
+use crate::parallel;
+use crate::resume::ContinueAt;
+use crate::writeout;
+use rurl::method::Method;
+use rurl::request::Request;
+use rurl::url::Url;
+
+pub use help::{print_help, print_man};
 
 /// Represents command line arguments for the HTTP client
 #[derive(Clone)]
 pub struct Args {
+    /// The URL being fetched
     pub url: String,
+    /// Every URL given, fetched in order
+    pub urls: Vec<String>,
     pub output: Option<String>,
-    pub method: String,
+    /// Every `-o` given; see `for_url`
+    pub outputs: Vec<String>,
+    pub remote_name: bool,
+    /// Largest body written to an output file, from `--max-disk`
+    pub max_disk: Option<u64>,
+    /// Most body bytes downloaded by all transfers of the run together, from `--max-total-bytes`
+    pub max_total_bytes: Option<u64>,
+    /// Fetch the URLs concurrently, from `-Z/--parallel`
+    pub parallel: bool,
+    /// Most transfers running at once under `-Z`, from `--parallel-max`
+    pub parallel_max: usize,
+    /// Most bytes per second sent or received by a transfer, from `--limit-rate`
+    pub limit_rate: Option<u64>,
+    /// File receiving the arrival time and size of each body chunk, from `--waterfall`
+    pub waterfall: Option<String>,
+    /// File receiving a JSON summary of every transfer, kept current during the run, from `--summary-file`
+    pub summary_file: Option<String>,
+    /// File receiving a hex dump of every byte sent and received, from `--trace`
+    pub trace: Option<String>,
+    /// File receiving the exact bytes of every request sent, from `--request-dump`
+    pub request_dump: Option<String>,
+    /// Start each verbose line and trace header with the time of day, from `--trace-time`
+    pub trace_time: bool,
+    pub method: Method,
     pub headers: Vec<String>,
-    pub data: Option<String>,
+    /// The `-d` body, as raw bytes
+    pub data: Option<Vec<u8>>,
+    /// Encoded `--data-urlencode` fields, in order
+    pub data_urlencode: Vec<String>,
+    pub form: Vec<Part>,
+    pub upload_file: Option<String>,
+    /// The body is JSON, from `--json`
+    pub json: bool,
+    /// `Name: value` fields sent after a chunked body, from `--trailer`
+    pub trailers: Vec<String>,
     pub help: bool,
+    /// The group of options to show with `--help`, all of them if None
+    pub help_topic: Option<String>,
+    /// Print the man page, from `--man`
+    pub man: bool,
     pub verbose: bool,
     pub tls_version: Option<String>,
+    /// Accept any certificate and host name, from `-k/--insecure`
+    pub insecure: bool,
+    /// Verify the certificate chain but not the host name, from `--insecure-hostname`
+    pub insecure_hostname: bool,
+    /// PEM CA certificates trusted in addition to the default roots, from `--cacert`
+    pub cacert: Option<String>,
+    /// Client certificate for mutual TLS, from `--cert`
+    pub cert: Option<String>,
+    /// Private key of the client certificate, from `--key`
+    pub key: Option<String>,
+    /// Send -H headers unchecked, from `--allow-unsafe-headers`
+    pub allow_unsafe_headers: bool,
+    /// Method sent verbatim, from `--raw-method`
+    pub raw_method: Option<String>,
+    /// Request target sent verbatim, from `--raw-path`
+    pub raw_path: Option<String>,
+    /// Request target checked against the method, from `--request-target`
+    pub request_target: Option<String>,
     pub include_headers: bool,
     pub head_only: bool,
     pub follow_redirects: bool,
+    /// Most redirects followed with `-L`, from `--max-redirs`
+    pub max_redirs: usize,
     pub silent: bool,
     pub user_agent: Option<String>,
+    /// Send a `Date` header, from `--date-header`
+    pub date_header: bool,
+    /// Seconds added to the clock for the `Date` header, from `--clock-offset`
+    pub clock_offset: i64,
     pub user: Option<String>,
+    /// Bearer token from `--oauth2-bearer`, or else the `RURL_TOKEN` environment variable
+    pub oauth2_bearer: Option<String>,
+    /// Entries of the netrc file read with `-n` or `--netrc-file`
+    pub netrc: Vec<netrc::Machine>,
     pub fail_fast: bool,
+    pub hosts_file: Option<String>,
+    /// `host:port:addr` entries pinning hosts to addresses, from `--resolve`
+    pub resolve: Vec<String>,
+    /// Order in which resolved addresses are attempted, from `--ip-strategy`
+    pub ip_strategy: Option<resolve::Strategy>,
+    pub redirect_protocols: Vec<String>,
+    pub redirect_allow_hosts: Vec<String>,
+    pub redirect_deny_hosts: Vec<String>,
+    pub redirect_deny_private: bool,
+    pub http2: bool,
+    pub no_keepalive: bool,
+    pub buffer_size: usize,
+    pub audit_headers: bool,
+    /// Print the response as one JSON object, from `--json-output`
+    pub json_output: bool,
+    /// Report failures as JSON on stderr, from `--error-format json`
+    pub error_json: bool,
+    /// Golden file the body must match, from `--compare`
+    pub compare: Option<String>,
+    /// Directory the parts of a multipart response are saved to, from `--extract-parts`
+    pub extract_parts: Option<String>,
+    /// Compare as JSON rather than bytes, from `--compare-json`
+    pub compare_json: bool,
+    /// Template printed after each transfer, from `-w/--write-out`
+    pub write_out: Option<String>,
+    /// Print only the status code, from `--status`
+    pub status_only: bool,
+    /// `Name` or `Name: value` fields the response must have, from `--expect-header`
+    pub expect_headers: Vec<String>,
+    /// Names of the response headers whose values are printed, from `--header-value`
+    pub header_values: Vec<String>,
+    pub negotiate_report: bool,
+    /// Only connect to each URL's host and report the setup times, from `--warm`
+    pub warm: bool,
+    /// Link relation to follow from page to page, from `--follow-link`
+    pub follow_link: Option<String>,
+    /// Where to find the next page in JSON responses, from `--paginate`
+    pub paginate: Option<paginate::Spec>,
+    /// Most pages `--follow-link` fetches
+    pub max_pages: usize,
+    /// Print each response body as one line of compact JSON
+    pub ndjson: bool,
+    pub cookie: Option<String>,
+    pub cookie_file: Option<String>,
+    pub cookie_jar: Option<String>,
+    pub require_tls: bool,
+    pub connect_timeout: Duration,
+    pub dns_timeout: Option<Duration>,
+    pub tls_timeout: Option<Duration>,
+    pub proxy: Option<String>,
+    /// Proxies from `http_proxy`, `https_proxy`, and `all_proxy`, used without `-x`
+    pub env_proxy: proxy::Env,
+    /// Hosts reached without a proxy, from `--noproxy` or else `no_proxy`
+    pub noproxy: Vec<String>,
+    /// Route through the local Tor client, from `--tor`
+    pub tor: bool,
+    pub proxy_user: Option<String>,
+    pub proxy_cacert: Option<String>,
+    pub proxy_insecure: bool,
+    /// Unix domain socket to send requests over instead of connecting to the URL's host
+    pub unix_socket: Option<String>,
+    pub prompt_auth: bool,
+    pub hexdump: bool,
+    pub line_buffered: bool,
+    /// Print a leading UTF-8 byte order mark instead of removing it, from `--keep-bom`
+    pub keep_bom: bool,
+    pub tui: bool,
+    pub copy: bool,
+    /// Command the body is streamed into, from `--pipe-to`
+    pub pipe_to: Option<String>,
+    pub no_history: bool,
+    pub edit: bool,
+    /// The request as edited with `--edit`, sent in place of one built from the options
+    pub edited_request: Option<Request>,
+    pub resume_state: Option<String>,
+    pub resume_from: Option<u64>,
+    pub continue_at: Option<ContinueAt>,
+    /// Byte ranges requested with `-r`, e.g. `0-99,200-`
+    pub range: Option<String>,
+    pub retry: u32,
+    pub retry_delay: Option<Duration>,
+    pub retry_max_time: Option<Duration>,
+    pub honor_retry_after: bool,
+    /// Retries already made for this request
+    pub retry_attempt: u32,
+    /// When the command started on `clock`, for `--retry-max-time`
+    pub started: Duration,
+    /// The source of time for retries and cookie expiry
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Args {
     /// Parse command line arguments.
     ///
     /// This function parses command line arguments and returns an `Args` struct.
+    /// `~/.rurlrc`, when it exists, is read as if given with `--config` before any other option.
     ///
     /// # Returns
     ///
     /// * `Result<Self, &'static str>` - An `Args` struct if successful, or an error message if unsuccessful.
     pub fn parse() -> Result<Self, &'static str> {
-        let mut args = env::args().skip(1);
+        let rc = config::default_path()
+            .filter(|path| path.is_file())
+            .map(|path| ["--config".to_string(), path.to_string_lossy().to_string()]);
+        Self::parse_from(rc.into_iter().flatten().chain(env::args().skip(1)))
+    }
+
+    /// Parse the arguments in `args`, which do not include the program name.
+    ///
+    /// The option lines of `-K/--config` files are fed through the same option table,
+    /// in front of the command line and in the order the files are given. A later
+    /// single-valued option replaces an earlier one, so the command line wins, while
+    /// repeatable options such as `-H` accumulate.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, &'static str>` - An `Args` struct if successful, or an error message if unsuccessful.
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, &'static str> {
+        let args: Vec<String> = args.into_iter().collect();
+        let mut options = Vec::new();
+        let mut tokens = args.iter();
+        while let Some(arg) = tokens.next() {
+            if arg == "-K" || arg == "--config" {
+                if let Some(path) = tokens.next() {
+                    options.extend(config::load_options(path)?);
+                }
+            } else if takes_value(arg) {
+                // The value of another option, as in `-d -K`, is not a flag
+                tokens.next();
+            }
+        }
+        let mut args = options.into_iter().chain(args);
+        let clock = clock::system();
+        let mut url_query = Vec::new();
+        let (mut use_netrc, mut netrc_file) = (false, None);
+        let mut noproxy = None;
         let mut parsed = Args {
             url: String::new(),
+            urls: Vec::new(),
             output: None,
-            method: "GET".to_string(),
+            outputs: Vec::new(),
+            remote_name: false,
+            max_disk: None,
+            max_total_bytes: None,
+            parallel: false,
+            parallel_max: parallel::DEFAULT_MAX,
+            limit_rate: None,
+            waterfall: None,
+            summary_file: None,
+            trace: None,
+            trace_time: false,
+            request_dump: None,
+            method: Method::Get,
             headers: Vec::new(),
             data: None,
+            data_urlencode: Vec::new(),
+            form: Vec::new(),
+            upload_file: None,
+            json: false,
+            trailers: Vec::new(),
             help: false,
+            help_topic: None,
+            man: false,
             verbose: false,
             tls_version: None,
+            insecure: false,
+            insecure_hostname: false,
+            cacert: None,
+            cert: None,
+            key: None,
+            allow_unsafe_headers: false,
+            raw_method: None,
+            raw_path: None,
+            request_target: None,
             include_headers: false,
             head_only: false,
             follow_redirects: false,
+            max_redirs: 10,
             silent: false,
             user_agent: None,
+            date_header: false,
+            clock_offset: 0,
             user: None,
+            oauth2_bearer: None,
+            netrc: Vec::new(),
             fail_fast: false,
+            hosts_file: None,
+            resolve: Vec::new(),
+            ip_strategy: None,
+            redirect_protocols: vec!["http".to_string(), "https".to_string()],
+            redirect_allow_hosts: Vec::new(),
+            redirect_deny_hosts: Vec::new(),
+            redirect_deny_private: false,
+            http2: false,
+            no_keepalive: false,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            audit_headers: false,
+            json_output: false,
+            error_json: false,
+            compare: None,
+            extract_parts: None,
+            compare_json: false,
+            write_out: None,
+            status_only: false,
+            expect_headers: Vec::new(),
+            header_values: Vec::new(),
+            negotiate_report: false,
+            warm: false,
+            follow_link: None,
+            paginate: None,
+            max_pages: paginate::DEFAULT_MAX_PAGES,
+            ndjson: false,
+            cookie: None,
+            cookie_file: None,
+            cookie_jar: None,
+            require_tls: false,
+            connect_timeout: CONNECT_TIMEOUT,
+            dns_timeout: None,
+            tls_timeout: None,
+            proxy: None,
+            env_proxy: proxy::Env::read(),
+            noproxy: Vec::new(),
+            unix_socket: None,
+            tor: false,
+            proxy_user: None,
+            proxy_cacert: None,
+            proxy_insecure: false,
+            prompt_auth: false,
+            hexdump: false,
+            line_buffered: false,
+            keep_bom: false,
+            tui: false,
+            copy: false,
+            pipe_to: None,
+            no_history: false,
+            edit: false,
+            edited_request: None,
+            resume_state: None,
+            resume_from: None,
+            continue_at: None,
+            range: None,
+            retry: 0,
+            retry_delay: None,
+            retry_max_time: None,
+            honor_retry_after: false,
+            retry_attempt: 0,
+            started: clock.monotonic(),
+            clock,
         };
 
         // Check environment variable for TLS version
@@ -57,115 +371,764 @@ impl Args {
             match arg.as_str() {
                 "-h" | "--help" => {
                     parsed.help = true;
+                    parsed.help_topic = args.next().filter(|topic| !topic.starts_with('-'));
+                    return Ok(parsed);
+                }
+                "--man" => {
+                    parsed.man = true;
                     return Ok(parsed);
                 }
                 "-v" | "--verbose" => {
                     parsed.verbose = true;
                 }
                 "-o" | "--output" => {
-                    parsed.output = Some(args.next().ok_or("Missing output file")?);
+                    parsed
+                        .outputs
+                        .push(args.next().ok_or("Missing output file")?);
+                }
+                "-O" | "--remote-name" => {
+                    parsed.remote_name = true;
+                }
+                "--max-disk" => {
+                    let size = args.next().ok_or("Missing disk limit")?;
+                    parsed.max_disk = Some(parse_size(&size).ok_or("Invalid disk limit")? as u64);
+                }
+                "--max-total-bytes" => {
+                    let size = args.next().ok_or("Missing byte budget")?;
+                    parsed.max_total_bytes =
+                        Some(parse_size(&size).ok_or("Invalid byte budget")? as u64);
+                }
+                "-Z" | "--parallel" => parsed.parallel = true,
+                "--parallel-max" => {
+                    let count = args.next().ok_or("Missing parallel count")?;
+                    parsed.parallel_max = count
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or("Invalid parallel count")?;
+                }
+                "--limit-rate" => {
+                    let speed = args.next().ok_or("Missing rate limit")?;
+                    parsed.limit_rate = Some(
+                        parse_size(&speed)
+                            .filter(|speed| *speed > 0)
+                            .ok_or("Invalid rate limit")? as u64,
+                    );
+                }
+                "--waterfall" => {
+                    parsed.waterfall = Some(args.next().ok_or("Missing waterfall file")?);
+                }
+                "--trace-time" => parsed.trace_time = true,
+                "--request-dump" => {
+                    parsed.request_dump = Some(args.next().ok_or("Missing request dump file")?);
+                }
+                "--trace" => {
+                    parsed.trace = Some(args.next().ok_or("Missing trace file")?);
+                }
+                "--summary-file" => {
+                    parsed.summary_file = Some(args.next().ok_or("Missing summary file")?);
                 }
                 "-m" | "--method" | "-X" | "--request" => {
-                    parsed.method = args.next().ok_or("Missing HTTP method")?.to_uppercase();
+                    parsed.method = Method::from(
+                        args.next()
+                            .ok_or("Missing HTTP method")?
+                            .to_uppercase()
+                            .as_str(),
+                    );
                 }
                 "-H" | "--header" => {
                     parsed.headers.push(args.next().ok_or("Missing header")?);
                 }
-                "-d" | "--data" => {
+                "-d" | "--data" | "--data-urlencode" if parsed.json => {
+                    return Err("Cannot combine --json with -d or --data-urlencode");
+                }
+                "--json" if parsed.data.is_some() || !parsed.data_urlencode.is_empty() => {
+                    return Err("Cannot combine --json with -d or --data-urlencode");
+                }
+                "--data-urlencode" => {
+                    let spec = args.next().ok_or("Missing data")?;
+                    parsed
+                        .data_urlencode
+                        .push(request::encode_form_field(&spec)?);
+                    if parsed.method == "GET" {
+                        parsed.method = Method::Post;
+                    }
+                }
+                "-d" | "--data" | "--json" => {
+                    parsed.json = arg == "--json";
                     let data_arg = args.next().ok_or("Missing data")?;
-                    // Check if data starts with @ to read from file
+                    // Check if data starts with @ to read from file (or stdin for @-), byte for byte
                     if let Some(filename) = data_arg.strip_prefix('@') {
-                        let file_content = std::fs::read_to_string(filename)
-                            .map_err(|_| "Failed to read data file")?;
-                        parsed.data = Some(file_content);
+                        parsed.data = Some(request::read_data_file(filename)?);
                     } else {
-                        parsed.data = Some(data_arg);
+                        parsed.data = Some(data_arg.into_bytes());
                     }
                     // If data is provided without explicit method, default to POST
                     if parsed.method == "GET" {
-                        parsed.method = "POST".to_string();
+                        parsed.method = Method::Post;
+                    }
+                }
+                "-F" | "--form" => {
+                    let field = args.next().ok_or("Missing form field")?;
+                    parsed.form.push(multipart::parse_part(&field)?);
+                    if parsed.method == "GET" {
+                        parsed.method = Method::Post;
+                    }
+                }
+                "-r" | "--range" => {
+                    let spec = args.next().ok_or("Missing range")?;
+                    parsed.range = Some(range::parse_spec(&spec)?);
+                }
+                "-C" | "--continue-at" => {
+                    let value = args.next().ok_or("Missing -C offset")?;
+                    parsed.continue_at = Some(ContinueAt::parse(&value)?);
+                }
+                "--resume-state" => {
+                    parsed.resume_state = Some(args.next().ok_or("Missing resume state file")?);
+                }
+                "-T" | "--upload-file" => {
+                    parsed.upload_file = Some(args.next().ok_or("Missing upload file")?);
+                    if parsed.method == "GET" {
+                        parsed.method = Method::Put;
+                    }
+                }
+                "--trailer" => {
+                    let trailer = args.next().ok_or("Missing trailer")?;
+                    if !trailer.contains(':') {
+                        return Err("Invalid trailer format");
+                    }
+                    parsed.trailers.push(trailer);
+                }
+                "-b" | "--cookie" => {
+                    let value = args.next().ok_or("Missing cookie")?;
+                    if value.contains('=') {
+                        let literal = cookies::parse_literal(&value)?;
+                        parsed.cookie = Some(match parsed.cookie.take() {
+                            Some(existing) => format!("{}; {}", existing, literal),
+                            None => literal,
+                        });
+                    } else {
+                        // Without '=' the value names a Netscape cookie file to read
+                        parsed.cookie_file = Some(value);
                     }
                 }
+                "-c" | "--cookie-jar" => {
+                    parsed.cookie_jar = Some(args.next().ok_or("Missing cookie jar file")?);
+                }
+                "-k" | "--insecure" => {
+                    parsed.insecure = true;
+                }
+                "--insecure-hostname" => {
+                    parsed.insecure_hostname = true;
+                }
+                "--cacert" => {
+                    parsed.cacert = Some(args.next().ok_or("Missing CA file")?);
+                }
+                "-E" | "--cert" => {
+                    parsed.cert = Some(args.next().ok_or("Missing certificate file")?);
+                }
+                "--key" => {
+                    parsed.key = Some(args.next().ok_or("Missing key file")?);
+                }
+                "--allow-unsafe-headers" => {
+                    parsed.allow_unsafe_headers = true;
+                }
+                "--raw-method" => {
+                    parsed.raw_method = Some(args.next().ok_or("Missing HTTP method")?);
+                }
+                "--raw-path" => {
+                    parsed.raw_path = Some(args.next().ok_or("Missing request target")?);
+                }
+                "--url-query" => {
+                    let spec = args.next().ok_or("Missing query parameter")?;
+                    // Like curl, a leading + means the parameter is already encoded
+                    url_query.push(match spec.strip_prefix('+') {
+                        Some(encoded) => encoded.to_string(),
+                        None => request::encode_form_field(&spec)?,
+                    });
+                }
+                "--request-target" => {
+                    parsed.request_target = Some(args.next().ok_or("Missing request target")?);
+                }
                 "--tls-version" => {
                     parsed.tls_version = Some(args.next().ok_or("Missing TLS version")?);
                 }
                 "-i" | "--include" => {
                     parsed.include_headers = true;
                 }
+                "--hexdump" => {
+                    parsed.hexdump = true;
+                }
+                "--line-buffered" => {
+                    parsed.line_buffered = true;
+                }
+                "--keep-bom" => {
+                    parsed.keep_bom = true;
+                }
+                "--tui" => {
+                    parsed.tui = true;
+                }
+                "--copy" => {
+                    parsed.copy = true;
+                }
+                "--pipe-to" => {
+                    parsed.pipe_to = Some(args.next().ok_or("Missing --pipe-to command")?);
+                }
+                "--no-history" => {
+                    parsed.no_history = true;
+                }
+                "--edit" => {
+                    parsed.edit = true;
+                }
                 "-I" | "--head" => {
                     parsed.head_only = true;
-                    parsed.method = "HEAD".to_string();
+                    parsed.method = Method::Head;
                 }
                 "-L" | "--location" => {
                     parsed.follow_redirects = true;
                 }
+                "--max-redirs" => {
+                    let count = args.next().ok_or("Missing redirect limit")?;
+                    // -1 means no limit, like curl
+                    parsed.max_redirs = if count == "-1" {
+                        usize::MAX
+                    } else {
+                        count.parse().map_err(|_| "Invalid redirect limit")?
+                    };
+                }
                 "-s" | "--silent" => {
                     parsed.silent = true;
                 }
                 "-A" | "--user-agent" => {
                     parsed.user_agent = Some(args.next().ok_or("Missing user agent")?);
                 }
+                "--date-header" => parsed.date_header = true,
+                "--clock-offset" => {
+                    let offset = args.next().ok_or("Missing clock offset")?;
+                    parsed.clock_offset = offset
+                        .trim_start_matches('+')
+                        .parse::<i64>()
+                        .map_err(|_| "Invalid clock offset")?;
+                }
                 "-u" | "--user" => {
                     parsed.user = Some(args.next().ok_or("Missing user credentials")?);
                 }
+                "-n" | "--netrc" => {
+                    use_netrc = true;
+                }
+                "--netrc-file" => {
+                    netrc_file = Some(args.next().ok_or("Missing netrc file")?);
+                }
+                "--oauth2-bearer" => {
+                    parsed.oauth2_bearer = Some(args.next().ok_or("Missing bearer token")?);
+                }
                 "-f" | "--fail" => {
                     parsed.fail_fast = true;
                 }
+                "-x" | "--proxy" => {
+                    parsed.proxy = Some(args.next().ok_or("Missing proxy")?);
+                }
+                "--noproxy" => {
+                    noproxy = Some(args.next().ok_or("Missing no-proxy list")?);
+                }
+                "--unix-socket" => {
+                    if !cfg!(unix) {
+                        return Err("--unix-socket is only supported on Unix");
+                    }
+                    parsed.unix_socket = Some(args.next().ok_or("Missing socket path")?);
+                }
+                "--tor" => {
+                    parsed.tor = true;
+                    parsed.proxy = Some(proxy::TOR_PROXY.to_string());
+                }
+                "--proxy-user" => {
+                    parsed.proxy_user = Some(args.next().ok_or("Missing proxy credentials")?);
+                }
+                "--proxy-cacert" => {
+                    parsed.proxy_cacert = Some(args.next().ok_or("Missing proxy CA file")?);
+                }
+                "--proxy-insecure" => {
+                    parsed.proxy_insecure = true;
+                }
+                "--prompt-auth" => {
+                    parsed.prompt_auth = true;
+                }
+                "--resolve" => {
+                    let entry = args.next().ok_or("Missing --resolve entry")?;
+                    resolve::parse_resolve(&entry)?;
+                    parsed.resolve.push(entry);
+                }
+                "-K" | "--config" => {
+                    // Already read in front of the command line
+                    args.next().ok_or("Missing config file")?;
+                }
+                "--ip-strategy" => {
+                    let strategy = args.next().ok_or("Missing IP strategy")?;
+                    parsed.ip_strategy = Some(resolve::Strategy::parse(&strategy)?);
+                }
+                "--hosts-file" => {
+                    parsed.hosts_file = Some(args.next().ok_or("Missing hosts file")?);
+                }
+                "--proto-redir" => {
+                    let protocols = args.next().ok_or("Missing redirect protocols")?;
+                    parsed.redirect_protocols = protocols
+                        .split(',')
+                        .map(|p| p.trim().to_lowercase())
+                        .filter(|p| !p.is_empty())
+                        .collect();
+                }
+                "--redir-allow-host" => {
+                    parsed
+                        .redirect_allow_hosts
+                        .push(args.next().ok_or("Missing redirect host")?);
+                }
+                "--redir-deny-host" => {
+                    parsed
+                        .redirect_deny_hosts
+                        .push(args.next().ok_or("Missing redirect host")?);
+                }
+                "--redir-deny-private" => {
+                    parsed.redirect_deny_private = true;
+                }
+                "--require-tls" => {
+                    parsed.require_tls = true;
+                }
+                "--http2" => {
+                    if !cfg!(feature = "http2") {
+                        return Err(
+                            "HTTP/2 is not available: rurl was built without the http2 feature",
+                        );
+                    }
+                    parsed.http2 = true;
+                }
+                "--no-keepalive" => {
+                    parsed.no_keepalive = true;
+                }
+                "--audit-headers" => {
+                    parsed.audit_headers = true;
+                }
+                "--json-output" => {
+                    parsed.json_output = true;
+                }
+                "--error-format" => {
+                    parsed.error_json = match args.next().ok_or("Missing error format")?.as_str() {
+                        "json" => true,
+                        "text" => false,
+                        _ => return Err("Invalid error format, expected text or json"),
+                    };
+                }
+                "--compare" => {
+                    parsed.compare = Some(args.next().ok_or("Missing golden file")?);
+                }
+                "--extract-parts" => {
+                    parsed.extract_parts = Some(args.next().ok_or("Missing directory")?);
+                }
+                "--compare-json" => {
+                    parsed.compare_json = true;
+                }
+                "-w" | "--write-out" => {
+                    let template = args.next().ok_or("Missing write-out format")?;
+                    writeout::validate(&template)?;
+                    parsed.write_out = Some(template);
+                }
+                "--status" => {
+                    parsed.status_only = true;
+                }
+                "--expect-header" => {
+                    let expected = args.next().ok_or("Missing expected header")?;
+                    let name = expected.split(':').next().unwrap_or("").trim();
+                    if name.is_empty() {
+                        return Err("Invalid expected header (expected NAME or NAME: VALUE)");
+                    }
+                    parsed.expect_headers.push(expected);
+                }
+                "--header-value" => {
+                    let name = args.next().ok_or("Missing header name")?;
+                    if name.trim().is_empty() || name.contains(':') {
+                        return Err("Invalid header name");
+                    }
+                    parsed.header_values.push(name.trim().to_string());
+                }
+                "--negotiate-report" => {
+                    parsed.negotiate_report = true;
+                }
+                "--warm" => parsed.warm = true,
+                "--follow-link" => {
+                    let rel = args.next().ok_or("Missing link relation")?;
+                    if rel.is_empty() || rel.contains(|c: char| c.is_whitespace() || c == '"') {
+                        return Err("Invalid link relation");
+                    }
+                    parsed.follow_link = Some(rel);
+                }
+                "--paginate" => {
+                    let spec = args.next().ok_or("Missing --paginate specification")?;
+                    parsed.paginate = Some(paginate::Spec::parse(&spec)?);
+                }
+                "--max-pages" => {
+                    let pages = args.next().ok_or("Missing page count")?;
+                    parsed.max_pages = match pages.parse::<usize>() {
+                        Ok(pages) if pages > 0 => pages,
+                        _ => return Err("Invalid page count"),
+                    };
+                }
+                "--ndjson" => {
+                    parsed.ndjson = true;
+                }
+                "--connect-timeout" => {
+                    let seconds = args.next().ok_or("Missing timeout")?;
+                    parsed.connect_timeout = parse_seconds(&seconds).ok_or("Invalid timeout")?;
+                }
+                "--dns-timeout" => {
+                    let seconds = args.next().ok_or("Missing timeout")?;
+                    parsed.dns_timeout = Some(parse_seconds(&seconds).ok_or("Invalid timeout")?);
+                }
+                "--tls-timeout" => {
+                    let seconds = args.next().ok_or("Missing timeout")?;
+                    parsed.tls_timeout = Some(parse_seconds(&seconds).ok_or("Invalid timeout")?);
+                }
+                "--retry" => {
+                    let count = args.next().ok_or("Missing retry count")?;
+                    parsed.retry = count.parse().map_err(|_| "Invalid retry count")?;
+                }
+                "--retry-delay" => {
+                    let seconds = args.next().ok_or("Missing retry delay")?;
+                    parsed.retry_delay =
+                        Some(parse_seconds(&seconds).ok_or("Invalid retry delay")?);
+                }
+                "--retry-max-time" => {
+                    let seconds = args.next().ok_or("Missing retry max time")?;
+                    parsed.retry_max_time =
+                        Some(parse_seconds(&seconds).ok_or("Invalid retry max time")?);
+                }
+                "--honor-retry-after" => {
+                    parsed.honor_retry_after = true;
+                }
+                "--buffer-size" => {
+                    let size = args.next().ok_or("Missing buffer size")?;
+                    parsed.buffer_size = parse_size(&size).ok_or("Invalid buffer size")?;
+                    if parsed.buffer_size == 0 {
+                        return Err("Invalid buffer size");
+                    }
+                }
                 _ if arg.starts_with('-') => {
                     return Err("Unknown option");
                 }
                 _ => {
-                    parsed.url = arg;
+                    parsed.urls.push(arg);
                 }
             }
         }
 
-        if parsed.url.is_empty() && !parsed.help {
+        // Like curl, uploading to a directory URL appends the file name
+        if let Some(file) = parsed.upload_file.as_ref().filter(|file| *file != "-") {
+            if let Some(name) = std::path::Path::new(file).file_name() {
+                for url in parsed.urls.iter_mut().filter(|url| url.ends_with('/')) {
+                    url.push_str(&name.to_string_lossy());
+                }
+            }
+        }
+        if !url_query.is_empty() {
+            for url in parsed.urls.iter_mut() {
+                let mut with_query = Url::parse(url)?;
+                for pair in &url_query {
+                    with_query.append_query(pair);
+                }
+                *url = with_query.to_string();
+            }
+        }
+        if !parsed.urls.is_empty() {
+            parsed = parsed.for_url(0);
+        }
+
+        let has_data = parsed.data.is_some() || !parsed.data_urlencode.is_empty();
+        if has_data && !parsed.form.is_empty() {
+            return Err("Cannot combine -d, --data-urlencode, or --json with -F");
+        }
+        if parsed.upload_file.is_some() && (has_data || !parsed.form.is_empty()) {
+            return Err("Cannot combine -T with -d, --data-urlencode, --json, or -F");
+        }
+        if parsed.json && request::has_header(&parsed, "content-type") {
+            return Err("Cannot combine --json with -H Content-Type");
+        }
+        if parsed.copy && (parsed.output.is_some() || parsed.tui || parsed.hexdump) {
+            return Err("Cannot combine --copy with -o, --tui, or --hexdump");
+        }
+        if parsed.pipe_to.is_some()
+            && (parsed.output.is_some()
+                || parsed.include_headers
+                || parsed.tui
+                || parsed.copy
+                || parsed.hexdump
+                || parsed.ndjson
+                || parsed.json_output
+                || parsed.compare.is_some()
+                || parsed.status_only
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some())
+        {
+            return Err("Cannot combine --pipe-to with -o, -O, -i, --tui, --copy, --hexdump, --ndjson, --json-output, --compare, --status, --negotiate-report, --follow-link, or --paginate");
+        }
+        if parsed.edit && parsed.upload_file.is_some() {
+            return Err("Cannot combine --edit with -T");
+        }
+        if parsed.clock_offset != 0 && !parsed.date_header {
+            return Err("--clock-offset only applies to --date-header");
+        }
+        if parsed.negotiate_report
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.tui
+                || parsed.copy
+                || parsed.audit_headers)
+        {
+            return Err(
+                "Cannot combine --negotiate-report with -o, -O, --tui, --copy, or --audit-headers",
+            );
+        }
+        if parsed.warm
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.tui
+                || parsed.copy
+                || parsed.pipe_to.is_some()
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some())
+        {
+            return Err("Cannot combine --warm with -o, -O, --tui, --copy, --pipe-to, --negotiate-report, --follow-link, or --paginate");
+        }
+        if parsed.extract_parts.is_some()
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.head_only
+                || parsed.tui
+                || parsed.copy
+                || parsed.pipe_to.is_some()
+                || parsed.json_output
+                || parsed.compare.is_some()
+                || parsed.range.is_some())
+        {
+            return Err("Cannot combine --extract-parts with -o, -O, --head, --tui, --copy, --pipe-to, --json-output, --compare, or -r");
+        }
+        if parsed.parallel
+            && (parsed.tui
+                || parsed.copy
+                || parsed.pipe_to.is_some()
+                || parsed.edit
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some()
+                || parsed.extract_parts.is_some()
+                || parsed.summary_file.is_some()
+                || parsed.waterfall.is_some()
+                || parsed.trace.is_some()
+                || parsed.request_dump.is_some()
+                || parsed.resume_state.is_some())
+        {
+            return Err("Cannot combine -Z with --tui, --copy, --pipe-to, --edit, --negotiate-report, --follow-link, --paginate, --extract-parts, --summary-file, --waterfall, --trace, --request-dump, or --resume-state");
+        }
+        if parsed.follow_link.is_some() && parsed.paginate.is_some() {
+            return Err("Cannot combine --follow-link with --paginate");
+        }
+        if parsed.paginate.is_some() && parsed.head_only {
+            return Err("Cannot combine --paginate with --head");
+        }
+        if (parsed.follow_link.is_some() || parsed.paginate.is_some())
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.tui
+                || parsed.copy
+                || parsed.audit_headers
+                || parsed.negotiate_report
+                || parsed.edit
+                || parsed.continue_at.is_some()
+                || parsed.resume_state.is_some())
+        {
+            return Err("Cannot combine --follow-link or --paginate with -o, -O, --tui, --copy, --audit-headers, --negotiate-report, --edit, -C, or --resume-state");
+        }
+        if parsed.ndjson && (parsed.include_headers || parsed.hexdump || parsed.head_only) {
+            return Err("Cannot combine --ndjson with -i, --hexdump, or --head");
+        }
+        if (parsed.allow_unsafe_headers || parsed.raw_method.is_some() || parsed.raw_path.is_some())
+            && (parsed.http2 || parsed.edit)
+        {
+            return Err("Cannot combine --allow-unsafe-headers, --raw-method, or --raw-path with --http2 or --edit");
+        }
+        if parsed.request_target.is_some()
+            && (parsed.raw_path.is_some() || parsed.http2 || parsed.edit)
+        {
+            return Err("Cannot combine --request-target with --raw-path, --http2, or --edit");
+        }
+        if (parsed.status_only
+            || !parsed.expect_headers.is_empty()
+            || !parsed.header_values.is_empty())
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.tui
+                || parsed.copy
+                || parsed.hexdump
+                || parsed.audit_headers
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some())
+        {
+            return Err("Cannot combine --status, --expect-header, or --header-value with -o, -O, --tui, --copy, --hexdump, --audit-headers, --negotiate-report, --follow-link, or --paginate");
+        }
+        if parsed.json_output
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.include_headers
+                || parsed.tui
+                || parsed.copy
+                || parsed.hexdump
+                || parsed.ndjson
+                || parsed.status_only
+                || !parsed.expect_headers.is_empty()
+                || !parsed.header_values.is_empty()
+                || parsed.compare.is_some()
+                || parsed.audit_headers
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some())
+        {
+            return Err("Cannot combine --json-output with -o, -O, -i, --tui, --copy, --hexdump, --ndjson, --status, --expect-header, --header-value, --compare, --audit-headers, --negotiate-report, --follow-link, or --paginate");
+        }
+        if parsed.compare_json && parsed.compare.is_none() {
+            return Err("--compare-json requires --compare");
+        }
+        if parsed.compare.is_some()
+            && (!parsed.outputs.is_empty()
+                || parsed.remote_name
+                || parsed.head_only
+                || parsed.tui
+                || parsed.copy
+                || parsed.hexdump
+                || parsed.ndjson
+                || parsed.status_only
+                || !parsed.expect_headers.is_empty()
+                || !parsed.header_values.is_empty()
+                || parsed.audit_headers
+                || parsed.negotiate_report
+                || parsed.follow_link.is_some()
+                || parsed.paginate.is_some())
+        {
+            return Err("Cannot combine --compare with -o, -O, --head, --tui, --copy, --hexdump, --ndjson, --status, --expect-header, --header-value, --audit-headers, --negotiate-report, --follow-link, or --paginate");
+        }
+        if parsed.max_disk.is_some() && parsed.outputs.is_empty() && !parsed.remote_name {
+            return Err("--max-disk requires -o or -O");
+        }
+        if parsed.key.is_some() && parsed.cert.is_none() {
+            return Err("--key requires --cert");
+        }
+        let has_authorization = request::has_header(&parsed, "authorization");
+        if parsed.oauth2_bearer.is_some() && (parsed.user.is_some() || has_authorization) {
+            return Err("Cannot combine --oauth2-bearer with -u or -H Authorization");
+        }
+        if let Some(path) = &netrc_file {
+            parsed.netrc = netrc::load(path)?;
+        } else if use_netrc {
+            // Without a ~/.netrc there are simply no credentials to add
+            if let Some(path) = netrc::default_path().filter(|path| path.exists()) {
+                parsed.netrc = netrc::load(&path.to_string_lossy())?;
+            }
+        }
+        // RURL_TOKEN only fills in when no other credentials were given
+        let url_credentials = Url::parse(&parsed.url).is_ok_and(|url| url.username().is_some());
+        if parsed.oauth2_bearer.is_none()
+            && parsed.user.is_none()
+            && !has_authorization
+            && !url_credentials
+            && !use_netrc
+            && netrc_file.is_none()
+        {
+            parsed.oauth2_bearer = env::var("RURL_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty());
+        }
+        // --noproxy replaces the environment's list, even when empty
+        let noproxy =
+            noproxy.or_else(|| env::var("no_proxy").or_else(|_| env::var("NO_PROXY")).ok());
+        parsed.noproxy = proxy::parse_noproxy(noproxy.as_deref().unwrap_or(""));
+        if parsed.tor && parsed.proxy.as_deref() != Some(proxy::TOR_PROXY) {
+            return Err("Cannot combine --tor with -x");
+        }
+        if parsed.unix_socket.is_some() && parsed.proxy.is_some() {
+            return Err("Cannot combine --unix-socket with -x");
+        }
+        if !parsed.trailers.is_empty() && (parsed.edit || parsed.http2) {
+            return Err("Cannot combine --trailer with --edit or --http2");
+        }
+        if parsed.continue_at.is_some() && parsed.resume_state.is_some() {
+            return Err("Cannot combine -C with --resume-state");
+        }
+        if parsed.range.is_some() && (parsed.continue_at.is_some() || parsed.resume_state.is_some())
+        {
+            return Err("Cannot combine -r with -C or --resume-state");
+        }
+        if parsed.copy && parsed.urls.len() > 1 {
+            return Err("Cannot combine --copy with multiple URLs");
+        }
+
+        if parsed.urls.is_empty() && !parsed.help {
             return Err("Missing URL");
         }
 
         Ok(parsed)
     }
+
+    /// The arguments for fetching the URL at `index` in `urls`.
+    ///
+    /// Like curl, the n-th `-o` names the file for the n-th URL, and URLs without
+    /// one are written to stdout, or with `-O` to a file named after the URL. A
+    /// single `-o` containing `#1` is a template for every URL instead, with `#1`
+    /// replaced by the URL's number, counting from 1.
+    pub fn for_url(&self, index: usize) -> Args {
+        let mut args = self.clone();
+        args.url = self.urls[index].clone();
+        args.output = match self.outputs.as_slice() {
+            [template] if template.contains("#1") => {
+                Some(template.replace("#1", &(index + 1).to_string()))
+            }
+            outputs => outputs.get(index).cloned(),
+        };
+        if args.output.is_none() && self.remote_name {
+            args.output = Url::parse(&args.url)
+                .ok()
+                .map(|url| response::remote_file_name(&url));
+        }
+        args
+    }
 }
 
-/// Print usage information
-pub fn print_help() {
-    println!("rurl - A minimal HTTP client");
-    println!();
-    println!("Usage:");
-    println!("    rurl [OPTIONS] <URL>");
-    println!();
-    println!("Options:");
-    println!("    -o, --output <FILE>       Save the response body to a file");
-    println!("    -m, --method <METHOD>     HTTP method to use (default: GET)");
-    println!("    -X, --request <METHOD>    HTTP method to use (alias for -m)");
-    println!("    -H, --header <HEADER>     Add a header to the request");
-    println!("    -d, --data <DATA>         Add data to the request body");
-    println!("                              Use @filename to read from file");
-    println!("    -i, --include             Include response headers in output");
-    println!("    -I, --head                Fetch headers only (HEAD request)");
-    println!("    -L, --location            Follow redirects");
-    println!("    -s, --silent              Silent mode (no progress output)");
-    println!("    -f, --fail                Fail silently on HTTP errors");
-    println!("    -A, --user-agent <NAME>   Custom User-Agent string");
-    println!("    -u, --user <USER:PASS>    Server authentication credentials");
-    println!("    -v, --verbose             Enable verbose output");
-    println!("    -h, --help                Display this help message");
-    println!("    --tls-version <VERSION>   Set TLS version (1.0, 1.1, 1.2, 1.3)");
-    println!();
-    println!("Environment Variables:");
-    println!("    RURL_TLS_VERSION          Set TLS version (overridden by --tls-version)");
-    println!();
-    println!("Examples:");
-    println!("    rurl https://example.com");
-    println!("    rurl -i https://example.com");
-    println!("    rurl -I https://example.com");
-    println!("    rurl -L https://example.com/redirect");
-    println!("    rurl -A \"Mozilla/5.0\" https://example.com");
-    println!("    rurl -u user:pass https://api.example.com");
-    println!("    rurl -X POST -H \"Content-Type: application/json\" -d '{{\"key\":\"value\"}}' https://api.example.com");
-    println!("    rurl -d @data.json https://api.example.com");
-    println!("    rurl -o response.html https://example.com");
-    println!("    rurl --tls-version 1.2 https://example.com");
-    println!("    RURL_TLS_VERSION=1.3 rurl https://example.com");
+/// Whether the option `flag` is followed by a value, as its usage in `options::OPTIONS`
+/// says, like `-o, --output <FILE>`.
+fn takes_value(flag: &str) -> bool {
+    options::OPTIONS.iter().any(|option| {
+        option
+            .usage
+            .split_once(" <")
+            .is_some_and(|(names, _)| names.split(", ").any(|name| name == flag))
+    })
+}
+
+/// Parse a byte size such as `65536`, `64k`, or `1M` (binary multiples).
+pub fn parse_size(value: &str) -> Option<usize> {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last()? {
+        'k' | 'K' => (&value[..value.len() - 1], 1024),
+        'm' | 'M' => (&value[..value.len() - 1], 1024 * 1024),
+        'g' | 'G' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+/// Parse a timeout given in (possibly fractional) seconds, such as `5` or `0.25`.
+pub fn parse_seconds(value: &str) -> Option<Duration> {
+    let seconds = value.trim().parse::<f64>().ok()?;
+    if seconds <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(seconds).ok()
 }
@@ -0,0 +1,684 @@
+//! The documented command-line surface: every option with its help line and topic,
+//! plus the subcommands, config files, environment variables, and examples.
+//!
+//! `--help` and `--help <topic>` print from these tables, and `build.rs` renders them
+//! into the man page, so this file is plain data that both can include.
+
+/// A group of related options, shown by `--help <name>`.
+pub struct Topic {
+    pub name: &'static str,
+    pub title: &'static str,
+}
+
+/// One option as documented.
+pub struct OptionHelp {
+    /// Its spellings and value, e.g. `-o, --output <FILE>`
+    pub usage: &'static str,
+    /// The name of its `Topic`
+    pub topic: &'static str,
+    pub help: &'static str,
+}
+
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        name: "output",
+        title: "Output",
+    },
+    Topic {
+        name: "request",
+        title: "Request",
+    },
+    Topic {
+        name: "auth",
+        title: "Authentication and cookies",
+    },
+    Topic {
+        name: "tls",
+        title: "TLS",
+    },
+    Topic {
+        name: "http2",
+        title: "HTTP/2 and connection reuse",
+    },
+    Topic {
+        name: "network",
+        title: "Proxies, name resolution, and timeouts",
+    },
+    Topic {
+        name: "transfer",
+        title: "Redirects, retries, and transfers",
+    },
+    Topic {
+        name: "debug",
+        title: "Inspection and debugging",
+    },
+];
+
+/// The ways to run rurl, after `rurl`
+pub const USAGE: &[&str] = &[
+    "[OPTIONS] <URL>...",
+    "cookies list|import|export --jar <FILE> [--domain <DOMAIN>] [--delete-expired]",
+    "history [--limit N] [--clear]",
+    "rerun <N>[-<M>] [--edit] [--respect-timing [--speedup X]]",
+    "hold [--interval SECS] [--ramp] [--max-probes N] [OPTIONS] <URL>",
+];
+
+pub const OPTIONS: &[OptionHelp] = &[
+    // Output
+    OptionHelp {
+        usage: "-o, --output <FILE>",
+        topic: "output",
+        help: "Save the response body to a file (one per URL, or '#1' for its number)",
+    },
+    OptionHelp {
+        usage: "-O, --remote-name",
+        topic: "output",
+        help: "Save the body to a file named after the URL's last path segment",
+    },
+    OptionHelp {
+        usage: "--max-disk <SIZE>",
+        topic: "output",
+        help: "Refuse to save a body larger than SIZE, e.g. 500M",
+    },
+    OptionHelp {
+        usage: "-i, --include",
+        topic: "output",
+        help: "Include response headers in output",
+    },
+    OptionHelp {
+        usage: "-s, --silent",
+        topic: "output",
+        help: "Silent mode (no progress output)",
+    },
+    OptionHelp {
+        usage: "-f, --fail",
+        topic: "output",
+        help: "Fail silently on HTTP errors",
+    },
+    OptionHelp {
+        usage: "--hexdump",
+        topic: "output",
+        help: "Show the body (and headers with -i) as a hex dump",
+    },
+    OptionHelp {
+        usage: "--line-buffered",
+        topic: "output",
+        help: "Flush the body to stdout or -o after every line as it arrives",
+    },
+    OptionHelp {
+        usage: "--keep-bom",
+        topic: "output",
+        help: "Print a leading UTF-8 byte order mark instead of removing it",
+    },
+    OptionHelp {
+        usage: "--tui",
+        topic: "output",
+        help: "Browse the response in an interactive terminal viewer",
+    },
+    OptionHelp {
+        usage: "--copy",
+        topic: "output",
+        help: "Put the response body on the clipboard instead of printing it",
+    },
+    OptionHelp {
+        usage: "--pipe-to <COMMAND>",
+        topic: "output",
+        help: "Stream the body into COMMAND's stdin and exit with its status",
+    },
+    OptionHelp {
+        usage: "--json-output",
+        topic: "output",
+        help: "Print status, headers, body, timing, and URL as one JSON object",
+    },
+    OptionHelp {
+        usage: "--ndjson",
+        topic: "output",
+        help: "Print each response body as one line of compact JSON",
+    },
+    OptionHelp {
+        usage: "-w, --write-out <FORMAT>",
+        topic: "output",
+        help: "Print FORMAT after the transfer, e.g. '%{http_code} %{time_total}\\n'",
+    },
+    OptionHelp {
+        usage: "--status",
+        topic: "output",
+        help: "Print only the status code; the body is not downloaded",
+    },
+    OptionHelp {
+        usage: "--expect-header <H>",
+        topic: "output",
+        help: "Fail unless the response has NAME or NAME: VALUE (body not downloaded)",
+    },
+    OptionHelp {
+        usage: "--header-value <NAME>",
+        topic: "output",
+        help: "Print only the values of header NAME, one per line (body not downloaded)",
+    },
+    OptionHelp {
+        usage: "--compare <FILE>",
+        topic: "output",
+        help: "Exit non-zero unless the body matches the golden FILE",
+    },
+    OptionHelp {
+        usage: "--compare-json",
+        topic: "output",
+        help: "With --compare, compare as JSON, ignoring formatting and key order",
+    },
+    OptionHelp {
+        usage: "--extract-parts <DIR>",
+        topic: "output",
+        help: "Save each part of a multipart response as a file in DIR, with an index.json",
+    },
+    OptionHelp {
+        usage: "--error-format <FORMAT>",
+        topic: "output",
+        help: "Report failures as text (default) or as one JSON object on stderr",
+    },
+    OptionHelp {
+        usage: "--summary-file <FILE>",
+        topic: "output",
+        help: "Keep a JSON summary of every transfer in FILE, even if rurl is killed",
+    },
+    // Request
+    OptionHelp {
+        usage: "-m, --method <METHOD>",
+        topic: "request",
+        help: "HTTP method to use (default: GET)",
+    },
+    OptionHelp {
+        usage: "-X, --request <METHOD>",
+        topic: "request",
+        help: "HTTP method to use (alias for -m)",
+    },
+    OptionHelp {
+        usage: "-H, --header <HEADER>",
+        topic: "request",
+        help: "Add a header to the request",
+    },
+    OptionHelp {
+        usage: "-A, --user-agent <NAME>",
+        topic: "request",
+        help: "Custom User-Agent string",
+    },
+    OptionHelp {
+        usage: "--date-header",
+        topic: "request",
+        help: "Send a Date header with the current time",
+    },
+    OptionHelp {
+        usage: "--clock-offset <SECS>",
+        topic: "request",
+        help: "Add SECS (negative to subtract) to the time in the --date-header Date",
+    },
+    OptionHelp {
+        usage: "-I, --head",
+        topic: "request",
+        help: "Fetch headers only (HEAD request)",
+    },
+    OptionHelp {
+        usage: "-d, --data <DATA>",
+        topic: "request",
+        help: "Add data to the request body; @FILE reads a file, @- stdin",
+    },
+    OptionHelp {
+        usage: "--json <DATA>",
+        topic: "request",
+        help: "Send DATA as JSON: sets Content-Type and Accept, POST by default; @FILE and @- as for -d",
+    },
+    OptionHelp {
+        usage: "--data-urlencode <DATA>",
+        topic: "request",
+        help: "Add a form field, percent-encoding the value: NAME=VALUE, NAME@FILE, VALUE, or @FILE",
+    },
+    OptionHelp {
+        usage: "--url-query <DATA>",
+        topic: "request",
+        help: "Add a query parameter to every URL, encoded like --data-urlencode; +DATA is sent as is",
+    },
+    OptionHelp {
+        usage: "-F, --form <NAME=VALUE>",
+        topic: "request",
+        help: "Add a multipart/form-data field; NAME=@FILE uploads a file",
+    },
+    OptionHelp {
+        usage: "-T, --upload-file <FILE>",
+        topic: "request",
+        help: "PUT FILE, streamed from disk; - streams stdin with chunked encoding",
+    },
+    OptionHelp {
+        usage: "--trailer <HEADER>",
+        topic: "request",
+        help: "Send the body chunked, followed by this trailer field",
+    },
+    OptionHelp {
+        usage: "--edit",
+        topic: "request",
+        help: "Edit the raw request in $EDITOR before sending it",
+    },
+    OptionHelp {
+        usage: "--allow-unsafe-headers",
+        topic: "request",
+        help: "Send -H headers unchecked, e.g. for request smuggling tests",
+    },
+    OptionHelp {
+        usage: "--raw-method <METHOD>",
+        topic: "request",
+        help: "Send METHOD verbatim in the request line",
+    },
+    OptionHelp {
+        usage: "--raw-path <TARGET>",
+        topic: "request",
+        help: "Send TARGET verbatim as the request target",
+    },
+    OptionHelp {
+        usage: "--request-target <TARGET>",
+        topic: "request",
+        help: "Send TARGET as the request target: *, host:port, a URL, or a path",
+    },
+    // Authentication and cookies
+    OptionHelp {
+        usage: "-u, --user <USER:PASS>",
+        topic: "auth",
+        help: "Server authentication credentials",
+    },
+    OptionHelp {
+        usage: "-n, --netrc",
+        topic: "auth",
+        help: "Take credentials for the host from ~/.netrc",
+    },
+    OptionHelp {
+        usage: "--netrc-file <FILE>",
+        topic: "auth",
+        help: "Take credentials for the host from FILE",
+    },
+    OptionHelp {
+        usage: "--oauth2-bearer <TOKEN>",
+        topic: "auth",
+        help: "Send 'Authorization: Bearer TOKEN' (default: $RURL_TOKEN)",
+    },
+    OptionHelp {
+        usage: "--prompt-auth",
+        topic: "auth",
+        help: "Ask for a username and password on a 401 or 407 Basic challenge, then retry once",
+    },
+    OptionHelp {
+        usage: "-b, --cookie <DATA|FILE>",
+        topic: "auth",
+        help: "Send cookies ('name=value; n2=v2') or read a cookie file",
+    },
+    OptionHelp {
+        usage: "-c, --cookie-jar <FILE>",
+        topic: "auth",
+        help: "Write received cookies to a Netscape cookie file",
+    },
+    // TLS
+    OptionHelp {
+        usage: "--tls-version <VERSION>",
+        topic: "tls",
+        help: "Set TLS version (1.0, 1.1, 1.2, 1.3)",
+    },
+    OptionHelp {
+        usage: "-k, --insecure",
+        topic: "tls",
+        help: "Skip TLS certificate and host name verification",
+    },
+    OptionHelp {
+        usage: "--insecure-hostname",
+        topic: "tls",
+        help: "Verify the certificate chain but accept any host name",
+    },
+    OptionHelp {
+        usage: "--cacert <FILE>",
+        topic: "tls",
+        help: "Also trust the PEM CA certificates in FILE",
+    },
+    OptionHelp {
+        usage: "-E, --cert <FILE[:PASS]>",
+        topic: "tls",
+        help: "Client certificate for mutual TLS (PEM, or PKCS#12 with its password)",
+    },
+    OptionHelp {
+        usage: "--key <FILE>",
+        topic: "tls",
+        help: "PEM private key for --cert",
+    },
+    OptionHelp {
+        usage: "--require-tls",
+        topic: "tls",
+        help: "Refuse to send any request over plain HTTP",
+    },
+    // HTTP/2 and connection reuse
+    OptionHelp {
+        usage: "--http2",
+        topic: "http2",
+        help: "Use HTTP/2 with prior knowledge",
+    },
+    OptionHelp {
+        usage: "--no-keepalive",
+        topic: "http2",
+        help: "Close each connection after its response instead of reusing it",
+    },
+    // Proxies, name resolution, and timeouts
+    OptionHelp {
+        usage: "-x, --proxy <URL>",
+        topic: "network",
+        help: "Use an HTTP, socks5://, or socks5h:// proxy",
+    },
+    OptionHelp {
+        usage: "--proxy-user <USER:PASS>",
+        topic: "network",
+        help: "Proxy authentication credentials",
+    },
+    OptionHelp {
+        usage: "--noproxy <LIST>",
+        topic: "network",
+        help: "Hosts, domains, or CIDR blocks reached without a proxy",
+    },
+    OptionHelp {
+        usage: "--tor",
+        topic: "network",
+        help: "Use the local Tor client (-x socks5h://127.0.0.1:9050)",
+    },
+    OptionHelp {
+        usage: "--proxy-cacert <FILE>",
+        topic: "network",
+        help: "Also trust these PEM CA certificates through the -x proxy",
+    },
+    OptionHelp {
+        usage: "--proxy-insecure",
+        topic: "network",
+        help: "Skip certificate checks for TLS through the -x proxy",
+    },
+    OptionHelp {
+        usage: "--unix-socket <PATH>",
+        topic: "network",
+        help: "Connect to this Unix domain socket instead of the URL's host",
+    },
+    OptionHelp {
+        usage: "--hosts-file <FILE>",
+        topic: "network",
+        help: "Resolve hosts from an /etc/hosts-style file before DNS",
+    },
+    OptionHelp {
+        usage: "--resolve <HOST:PORT:ADDR>",
+        topic: "network",
+        help: "Connect to ADDR for HOST:PORT instead of asking DNS",
+    },
+    OptionHelp {
+        usage: "--ip-strategy <STRATEGY>",
+        topic: "network",
+        help: "Address order: v4-first, v6-first, round-robin, random",
+    },
+    OptionHelp {
+        usage: "--connect-timeout <SECS>",
+        topic: "network",
+        help: "Limit for establishing the TCP connection (default: 10)",
+    },
+    OptionHelp {
+        usage: "--dns-timeout <SECS>",
+        topic: "network",
+        help: "Limit for DNS resolution",
+    },
+    OptionHelp {
+        usage: "--tls-timeout <SECS>",
+        topic: "network",
+        help: "Limit for the TLS handshake",
+    },
+    OptionHelp {
+        usage: "--buffer-size <SIZE>",
+        topic: "network",
+        help: "Initial read buffer size, e.g. 64k (default: 128k)",
+    },
+    // Redirects, retries, and transfers
+    OptionHelp {
+        usage: "-L, --location",
+        topic: "transfer",
+        help: "Follow redirects",
+    },
+    OptionHelp {
+        usage: "--max-redirs <N>",
+        topic: "transfer",
+        help: "Follow at most N redirects (default: 10, -1 for no limit)",
+    },
+    OptionHelp {
+        usage: "--proto-redir <LIST>",
+        topic: "transfer",
+        help: "Protocols redirects may switch to (default: http,https)",
+    },
+    OptionHelp {
+        usage: "--redir-allow-host <HOST>",
+        topic: "transfer",
+        help: "Only follow redirects to this host or its subdomains (repeatable)",
+    },
+    OptionHelp {
+        usage: "--redir-deny-host <HOST>",
+        topic: "transfer",
+        help: "Never follow redirects to this host or its subdomains (repeatable)",
+    },
+    OptionHelp {
+        usage: "--redir-deny-private",
+        topic: "transfer",
+        help: "Refuse redirects to private, loopback, or link-local addresses",
+    },
+    OptionHelp {
+        usage: "--retry <N>",
+        topic: "transfer",
+        help: "Retry N times on connection errors, timeouts, and 5xx/429",
+    },
+    OptionHelp {
+        usage: "--retry-delay <SECS>",
+        topic: "transfer",
+        help: "Fixed delay between retries (default: backoff from 1s)",
+    },
+    OptionHelp {
+        usage: "--retry-max-time <SECS>",
+        topic: "transfer",
+        help: "Give up retrying once this much time has passed",
+    },
+    OptionHelp {
+        usage: "--honor-retry-after",
+        topic: "transfer",
+        help: "Wait as long as a 429/5xx Retry-After header asks",
+    },
+    OptionHelp {
+        usage: "-r, --range <RANGES>",
+        topic: "transfer",
+        help: "Request byte ranges, e.g. 0-1023 or 0-99,-100",
+    },
+    OptionHelp {
+        usage: "-C, --continue-at <N|->",
+        topic: "transfer",
+        help: "Resume at byte N, or with '-' at the end of the -o file",
+    },
+    OptionHelp {
+        usage: "--resume-state <FILE>",
+        topic: "transfer",
+        help: "Record downloads in FILE; skip finished ones, resume partial ones",
+    },
+    OptionHelp {
+        usage: "-Z, --parallel",
+        topic: "transfer",
+        help: "Fetch the URLs concurrently, each into its own file",
+    },
+    OptionHelp {
+        usage: "--parallel-max <N>",
+        topic: "transfer",
+        help: "Run at most N transfers at once with -Z (default: 50)",
+    },
+    OptionHelp {
+        usage: "--limit-rate <SPEED>",
+        topic: "transfer",
+        help: "Send and receive at most SPEED bytes per second, e.g. 500k",
+    },
+    OptionHelp {
+        usage: "--max-total-bytes <SIZE>",
+        topic: "transfer",
+        help: "Stop the run once all transfers together download more than SIZE",
+    },
+    OptionHelp {
+        usage: "--follow-link <REL>",
+        topic: "transfer",
+        help: "Keep fetching the Link header target with this relation, e.g. next",
+    },
+    OptionHelp {
+        usage: "--paginate <SPEC>",
+        topic: "transfer",
+        help: "Find the next page in JSON responses: next=$.PATH, cursor=$.PATH,param=NAME, or offset=NAME; add items=$.PATH[*] to merge arrays",
+    },
+    OptionHelp {
+        usage: "--max-pages <N>",
+        topic: "transfer",
+        help: "Stop --follow-link or --paginate after N pages (default: 100)",
+    },
+    // Inspection and debugging
+    OptionHelp {
+        usage: "-v, --verbose",
+        topic: "debug",
+        help: "Enable verbose output",
+    },
+    OptionHelp {
+        usage: "--trace <FILE>",
+        topic: "debug",
+        help: "Write a hex dump of all bytes sent and received to FILE",
+    },
+    OptionHelp {
+        usage: "--request-dump <FILE>",
+        topic: "debug",
+        help: "Write the exact bytes of every request sent to FILE",
+    },
+    OptionHelp {
+        usage: "--trace-time",
+        topic: "debug",
+        help: "Prefix verbose output and trace headers with the time of day",
+    },
+    OptionHelp {
+        usage: "--waterfall <FILE>",
+        topic: "debug",
+        help: "Write the arrival time and size of each body chunk (CSV, or JSON for .json)",
+    },
+    OptionHelp {
+        usage: "--audit-headers",
+        topic: "debug",
+        help: "Report security header posture (exit 0/1/2 = pass/warn/fail)",
+    },
+    OptionHelp {
+        usage: "--negotiate-report",
+        topic: "debug",
+        help: "Vary Accept, Accept-Encoding, and Accept-Language and report how the response changes",
+    },
+    OptionHelp {
+        usage: "--warm",
+        topic: "debug",
+        help: "Resolve and connect to each URL's host (with TLS) without sending a request, and report the timings",
+    },
+    OptionHelp {
+        usage: "--no-history",
+        topic: "debug",
+        help: "Do not record this request in the history file",
+    },
+    OptionHelp {
+        usage: "-h, --help [TOPIC]",
+        topic: "debug",
+        help: "Display this help message, or only the options of TOPIC",
+    },
+    OptionHelp {
+        usage: "--man",
+        topic: "debug",
+        help: "Print the rurl(1) man page, e.g. rurl --man > rurl.1",
+    },
+];
+
+/// Where default options come from, as (option or file, description)
+pub const CONFIG: &[(&str, &str)] = &[
+    (
+        "-K, --config <FILE>",
+        "Read default options from FILE, one per line",
+    ),
+    (
+        "~/.rurlrc",
+        "Options before the first section apply to every request",
+    ),
+    (
+        "~/.rurlrc",
+        "[host \"PATTERN\"] sections set default options per host",
+    ),
+];
+
+pub const ENVIRONMENT: &[(&str, &str)] = &[
+    (
+        "RURL_TLS_VERSION",
+        "Set TLS version (overridden by --tls-version)",
+    ),
+    ("RURL_CLIPBOARD", "Command that --copy pipes the body to"),
+    (
+        "RURL_HISTORY_FILE",
+        "History file (default ~/.rurl_history; empty disables)",
+    ),
+    (
+        "http_proxy, https_proxy",
+        "Proxy for http:// or https:// URLs when -x is not given",
+    ),
+    (
+        "all_proxy",
+        "Proxy for URLs whose scheme has none of its own",
+    ),
+    ("no_proxy", "Default for --noproxy"),
+];
+
+pub const EXAMPLES: &[&str] = &[
+    "rurl https://example.com",
+    "rurl -i https://example.com",
+    "rurl -I https://example.com",
+    "rurl -L https://example.com/redirect",
+    "rurl -A \"Mozilla/5.0\" https://example.com",
+    "rurl --date-header --clock-offset -90 https://api.example.com",
+    "rurl -u user:pass https://api.example.com",
+    "rurl -X POST -H \"Content-Type: application/json\" -d '{\"key\":\"value\"}' https://api.example.com",
+    "rurl -d @data.json https://api.example.com",
+    "rurl --pipe-to 'tar -xz -C release' https://example.com/release.tar.gz",
+    "rurl --data-urlencode 'q=rust & curl' --data-urlencode note@note.txt https://example.com/search",
+    "rurl --url-query 'q=rust & curl' --url-query page=2 https://example.com/search",
+    "rurl -T backup.tar.gz https://files.example.com/uploads/",
+    "tar -cz data/ | rurl -T - https://files.example.com/uploads/data.tar.gz",
+    "rurl --unix-socket /var/run/docker.sock http://localhost/v1.43/containers/json",
+    "rurl -T backup.tar.gz --trailer 'X-Checksum: 5d41402a' https://files.example.com/uploads/",
+    "rurl -F name=alice -F avatar=@me.png https://api.example.com/upload",
+    "rurl -o response.html https://example.com",
+    "rurl -o 'page-#1.html' https://example.com/a https://example.com/b",
+    "rurl -Z --parallel-max 4 https://example.com/a.iso https://example.com/b.iso",
+    "rurl -O --limit-rate 500k https://example.com/big.iso",
+    "rurl --resume-state state.json -o big.iso https://example.com/big.iso",
+    "rurl --hexdump -i https://example.com/favicon.ico",
+    "rurl --retry 5 --retry-max-time 60 --honor-retry-after https://api.example.com",
+    "rurl --tui https://api.example.com/items",
+    "rurl --copy -d grant_type=client_credentials https://auth.example.com/token",
+    "rurl --edit -d '{\"draft\":true}' https://api.example.com/items",
+    "rurl --tls-version 1.2 https://example.com",
+    "rurl -k https://localhost:8443/health",
+    "rurl --cacert ca.pem --cert client.pem --key client.key https://internal.example.com",
+    "rurl -x http://proxy.local:3128 https://example.com",
+    "rurl --hosts-file ./hosts https://staging.example.com",
+    "rurl --resolve example.com:443:127.0.0.1 https://example.com",
+    "rurl --ip-strategy v6-first https://example.com",
+    "rurl --config ./ci.rurlrc https://example.com",
+    "rurl --audit-headers https://example.com",
+    "rurl --json-output https://api.example.com/items | jq .status",
+    "rurl --compare tests/users.json --compare-json https://api.example.com/users",
+    "rurl --extract-parts ./batch https://api.example.com/documents/batch",
+    "rurl -s -w '%{http_code} %{size_download} %{time_total}\\n' -o /dev/null https://example.com",
+    "rurl --status --expect-header 'Cache-Control: no-store' https://example.com/large.iso",
+    "rurl --header-value ETag --header-value Location https://example.com",
+    "rurl --negotiate-report https://api.example.com/items",
+    "rurl --warm https://api.example.com https://cdn.example.com",
+    "rurl --follow-link next --max-pages 5 --ndjson https://api.example.com/items",
+    "rurl --paginate 'cursor=$.meta.next_cursor,param=after,items=$.items[*]' https://api.example.com/items",
+    "rurl -b 'session=abc; theme=dark' https://example.com",
+    "rurl -L -b cookies.txt -c cookies.txt https://example.com/login",
+    "rurl cookies list --jar cookies.txt --domain example.com",
+    "rurl history --limit 10",
+    "rurl rerun 42 --edit",
+    "rurl rerun 10-25 --respect-timing --speedup 4",
+    "rurl hold --interval 30 --ramp https://example.com",
+    "RURL_TLS_VERSION=1.3 rurl https://example.com",
+];
@@ -0,0 +1,294 @@
+//! The async `Client`, on tokio.
+//!
+//! It behaves like `client::Client` and shares its builder, errors, and HTTP/1.1
+//! parsing; only the I/O differs. TLS is supplied by an async `Connector`.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+use std::io::{self, ErrorKind};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::client::http1::{self, Failed, Key, ResponseParser, Settings, MAX_IDLE, READ_SIZE};
+use crate::client::Policy;
+use crate::method::Method;
+use crate::request::{Request, TlsOptions};
+use crate::response::Response;
+use crate::url::Url;
+
+pub use crate::client::Error;
+
+/// Configures an async `Client`.
+pub type ClientBuilder = crate::client::ClientBuilder<Box<dyn Connector>>;
+
+/// A connection requests can be sent over: a TCP stream or TLS on top of one.
+pub trait Stream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Stream for T {}
+
+/// The future a `Connector` returns.
+pub type Connecting<'a> = Pin<Box<dyn Future<Output = io::Result<Box<dyn Stream>>> + Send + 'a>>;
+
+/// Starts TLS for `https` URLs, so the library needs no TLS implementation of its own.
+pub trait Connector: Send + Sync {
+    /// Start TLS on `tcp`, which is connected to `host` or tunnelled to it through a proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host name to verify the certificate against, without IPv6 brackets.
+    /// * `tcp` - The connected stream.
+    /// * `tls` - The TLS settings of the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Connecting` - A future of the encrypted stream, once the handshake succeeded.
+    fn connect<'a>(&'a self, host: &'a str, tcp: TcpStream, tls: &'a TlsOptions) -> Connecting<'a>;
+}
+
+/// A reusable async HTTP/1.1 client.
+///
+/// The same settings, connection reuse, retries, and circuit breaker as the
+/// blocking `client::Client` apply. Timeouts need a tokio runtime with the time
+/// driver enabled.
+///
+/// Build one with `Client::builder`; it can be shared between tasks.
+pub struct Client {
+    connector: Option<Box<dyn Connector>>,
+    settings: Settings,
+    started: Instant,
+    policy: Mutex<Policy>,
+    idle: Mutex<Vec<(Key, Box<dyn Stream>)>>,
+}
+
+impl Client {
+    /// Start configuring a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Send a GET request to `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub async fn get(&self, url: &str) -> Result<Response, Error> {
+        self.execute(Request::builder("GET", url).keep_alive(true).build()?)
+            .await
+    }
+
+    /// Send a POST request with `body` to `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub async fn post(&self, url: &str, body: impl Into<Vec<u8>>) -> Result<Response, Error> {
+        self.execute(
+            Request::builder("POST", url)
+                .keep_alive(true)
+                .body(body)
+                .build()?,
+        )
+        .await
+    }
+
+    /// Send `request` and read the whole response.
+    ///
+    /// Default headers are added unless the request sets a header of the same name,
+    /// and the timeouts and TLS version of the request take precedence over the
+    /// client's. Redirects are not followed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let (request, tls) = self.settings.prepare(request)?;
+        // Refuse before asking the policy, so an unsendable request never takes the probe
+        if request.url().is_https() && self.connector.is_none() {
+            return Err(Error::Invalid("HTTPS needs a TLS connector"));
+        }
+        let host = request.url().host_str();
+        let mut attempt = 0;
+        loop {
+            self.policy().allow_request(&host, self.started.elapsed())?;
+            let result = self.send(&request, &tls).await;
+            let now = self.started.elapsed();
+            let delay = self
+                .settings
+                .retry(&mut self.policy(), &request, &result, attempt, now);
+            let Some(delay) = delay else {
+                return result;
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn policy(&self) -> MutexGuard<'_, Policy> {
+        self.policy.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send `request` once, on an idle connection if one is kept for its origin.
+    async fn send(&self, request: &Request, tls: &TlsOptions) -> Result<Response, Error> {
+        let key = Key::new(request.url());
+        let bytes = http1::request_bytes(request);
+        let read_timeout = request.read_timeout().or(self.settings.read_timeout);
+        if let Some(stream) = self.checkout(&key) {
+            // The server may have closed the idle connection; then use a new one
+            let exchange = self.exchange(stream, &key, request, &bytes, read_timeout);
+            match exchange.await {
+                Err(failed) if failed.resend(request) => {}
+                result => return result.map_err(|failed| failed.error),
+            }
+        }
+        let stream = self.open(&key, request, tls).await?;
+        self.exchange(stream, &key, request, &bytes, read_timeout)
+            .await
+            .map_err(|failed| failed.error)
+    }
+
+    /// Write the request bytes and read the response, keeping the connection if it can be reused.
+    async fn exchange(
+        &self,
+        mut stream: Box<dyn Stream>,
+        key: &Key,
+        request: &Request,
+        bytes: &[u8],
+        timeout: Option<Duration>,
+    ) -> Result<Response, Failed> {
+        let unanswered = |err: io::Error| Failed {
+            error: err.into(),
+            unanswered: true,
+        };
+        within(timeout, stream.write_all(bytes))
+            .await
+            .map_err(unanswered)?;
+        within(timeout, stream.flush()).await.map_err(unanswered)?;
+        let mut parser = ResponseParser::new(request.method(), request.url());
+        read_response(&mut stream, &mut parser, timeout)
+            .await
+            .map_err(|err| parser.failed(err))?;
+        let (response, reusable) = parser.finish()?;
+        if reusable && request.keep_alive() {
+            let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+            if idle.len() < MAX_IDLE {
+                idle.push((key.clone(), stream));
+            }
+        }
+        Ok(response)
+    }
+
+    /// Take an idle connection to the origin of `key`.
+    fn checkout(&self, key: &Key) -> Option<Box<dyn Stream>> {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = idle.iter().position(|(idle_key, _)| idle_key == key)?;
+        Some(idle.remove(index).1)
+    }
+
+    /// Open a new connection to the origin of `key`, through the proxy and TLS as configured.
+    async fn open(
+        &self,
+        key: &Key,
+        request: &Request,
+        tls: &TlsOptions,
+    ) -> Result<Box<dyn Stream>, Error> {
+        let connect_timeout = request.connect_timeout().or(self.settings.connect_timeout);
+        let read_timeout = request.read_timeout().or(self.settings.read_timeout);
+        let (host, port) = self.settings.address(key);
+        let mut tcp = within(connect_timeout, TcpStream::connect((host.as_str(), port))).await?;
+        if !key.https {
+            return Ok(Box::new(tcp));
+        }
+        if let Some(proxy) = &self.settings.proxy {
+            tunnel(&mut tcp, key, proxy, read_timeout).await?;
+        }
+        match &self.connector {
+            Some(connector) => {
+                Ok(within(read_timeout, connector.connect(&key.host, tcp, tls)).await?)
+            }
+            None => Err(Error::Invalid("HTTPS needs a TLS connector")),
+        }
+    }
+}
+
+impl ClientBuilder {
+    /// Start TLS for `https` URLs with `connector`.
+    pub fn connector(mut self, connector: impl Connector + 'static) -> Self {
+        self.connector = Some(Box::new(connector));
+        self
+    }
+
+    /// Finish the client.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Client, &'static str>` - The client if successful, or the first invalid setting.
+    pub fn build(self) -> Result<Client, &'static str> {
+        let (connector, settings) = self.finish()?;
+        Ok(Client {
+            connector,
+            started: Instant::now(),
+            policy: Mutex::new(Policy::new(settings.config)),
+            idle: Mutex::new(Vec::new()),
+            settings,
+        })
+    }
+}
+
+/// Run the I/O in `future`, failing with `TimedOut` if it takes longer than `timeout`.
+async fn within<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| Err(io::Error::from(ErrorKind::TimedOut))),
+        None => future.await,
+    }
+}
+
+/// Ask the proxy for a tunnel to the origin of `key` with CONNECT.
+async fn tunnel(
+    tcp: &mut TcpStream,
+    key: &Key,
+    proxy: &Url,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    within(timeout, tcp.write_all(&http1::connect_request(key, proxy))).await?;
+    let mut parser = ResponseParser::new(&Method::Connect, proxy);
+    read_response(tcp, &mut parser, timeout).await?;
+    match parser.into_head() {
+        Some(response) => http1::check_tunnel(&response),
+        None => Err(Error::Protocol("Proxy CONNECT failed".into())),
+    }
+}
+
+/// Read from `stream` into `parser` until the response is complete, waiting at most
+/// `timeout` for each read.
+async fn read_response(
+    stream: &mut (impl AsyncRead + Unpin),
+    parser: &mut ResponseParser,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    let mut chunk = vec![0u8; READ_SIZE];
+    loop {
+        let n = match within(timeout, stream.read(&mut chunk)).await {
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if n == 0 {
+            return parser.end();
+        }
+        if parser.feed(&chunk[..n])? {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! The `Transfer-Encoding: chunked` body framing.
+//!
+//! The decoder is fed whatever bytes have arrived and writes the body data into a
+//! caller-provided buffer, so it works with any transport and never needs the whole
+//! body in memory. The encoder functions frame a body that is sent in pieces.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Upper bound for a chunk-size or trailer line
+pub const MAX_LINE_SIZE: usize = 8 * 1024;
+
+/// A violation of the chunked encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A chunk-size line that is not a hex number
+    InvalidSize(String),
+    /// Chunk data not followed by the line break ending it
+    DataTooLong,
+    /// A trailer line that is not a `name: value` field
+    InvalidTrailer(String),
+    /// A chunk-size or trailer line longer than `MAX_LINE_SIZE`
+    LineTooLong,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidSize(line) => write!(f, "invalid chunk size line '{}'", line),
+            Error::DataTooLong => write!(f, "chunk data longer than its size"),
+            Error::InvalidTrailer(line) => write!(f, "invalid trailer field '{}'", line),
+            Error::LineTooLong => write!(f, "chunked encoding line too long"),
+        }
+    }
+}
+
+/// Position inside a chunked body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Size,
+    Data(u64),
+    DataEnd,
+    Trailers,
+    Done,
+}
+
+/// Decoder for one chunked body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoder {
+    state: State,
+    /// A line split across calls to `decode`
+    line: Vec<u8>,
+    trailers: Vec<(String, String)>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder {
+            state: State::Size,
+            line: Vec::new(),
+            trailers: Vec::new(),
+        }
+    }
+
+    /// Check whether the last chunk and the trailer section have been read.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Decode body data from `input` into `out`.
+    ///
+    /// Decoding stops when `out` is full, `input` is used up, or the body ends; bytes
+    /// after the end of the body are left unconsumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The next bytes of the encoded body.
+    /// * `out` - The buffer receiving decoded data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(usize, usize), Error>` - The number of input bytes consumed and of data bytes written if successful, or the encoding error.
+    pub fn decode(&mut self, input: &[u8], out: &mut [u8]) -> Result<(usize, usize), Error> {
+        let mut consumed = 0;
+        let mut written = 0;
+
+        while consumed < input.len() && self.state != State::Done {
+            if let State::Data(remaining) = self.state {
+                if written == out.len() {
+                    break;
+                }
+                let n = (input.len() - consumed)
+                    .min(out.len() - written)
+                    .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+                out[written..written + n].copy_from_slice(&input[consumed..consumed + n]);
+                consumed += n;
+                written += n;
+                let left = remaining - n as u64;
+                self.state = if left == 0 {
+                    State::DataEnd
+                } else {
+                    State::Data(left)
+                };
+                continue;
+            }
+
+            let rest = &input[consumed..];
+            let Some(end) = rest.iter().position(|&b| b == b'\n') else {
+                self.line.extend_from_slice(rest);
+                consumed = input.len();
+                if self.line.len() > MAX_LINE_SIZE {
+                    return Err(Error::LineTooLong);
+                }
+                break;
+            };
+            self.line.extend_from_slice(&rest[..end]);
+            consumed += end + 1;
+            if self.line.len() > MAX_LINE_SIZE {
+                return Err(Error::LineTooLong);
+            }
+
+            let mut line = core::mem::take(&mut self.line);
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            self.state = self.next_state(&line)?;
+        }
+
+        Ok((consumed, written))
+    }
+
+    /// Take the trailer fields read so far.
+    pub fn take_trailers(&mut self) -> Vec<(String, String)> {
+        core::mem::take(&mut self.trailers)
+    }
+
+    /// Describe what is still expected, for reporting a body that ends early.
+    pub fn expected(&self) -> String {
+        match self.state {
+            State::Size => "the last chunk".to_string(),
+            State::Data(remaining) => format!("{} bytes of the chunk", remaining),
+            State::DataEnd => "the end of the chunk data".to_string(),
+            State::Trailers => "the end of the trailer section".to_string(),
+            State::Done => "nothing".to_string(),
+        }
+    }
+
+    /// Handle a complete line, without its terminator, in the current state.
+    fn next_state(&mut self, line: &[u8]) -> Result<State, Error> {
+        match self.state {
+            State::Size => match parse_size(line) {
+                Some(0) => Ok(State::Trailers),
+                Some(size) => Ok(State::Data(size)),
+                None => Err(Error::InvalidSize(
+                    String::from_utf8_lossy(line).into_owned(),
+                )),
+            },
+            // The chunk data must be followed by exactly CRLF
+            State::DataEnd if line.is_empty() => Ok(State::Size),
+            State::DataEnd => Err(Error::DataTooLong),
+            State::Trailers if line.is_empty() => Ok(State::Done),
+            State::Trailers => {
+                let line = String::from_utf8_lossy(line);
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| Error::InvalidTrailer(line.to_string()))?;
+                self.trailers
+                    .push((name.trim().to_string(), value.trim().to_string()));
+                Ok(State::Trailers)
+            }
+            State::Data(_) | State::Done => Ok(self.state),
+        }
+    }
+}
+
+/// Parse a chunk-size line: hex digits, optionally followed by `;` and chunk extensions,
+/// which are ignored.
+fn parse_size(line: &[u8]) -> Option<u64> {
+    let line = core::str::from_utf8(line).ok()?;
+    let size = line.split(';').next()?.trim();
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(size, 16).ok()
+}
+
+/// Frame `data` as one chunk.
+///
+/// Empty data yields no bytes, since an empty chunk would end the body.
+pub fn encode_chunk(data: &[u8]) -> Vec<u8> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+    let mut out = format!("{:x}\r\n", data.len()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\r\n");
+    out
+}
+
+/// The last chunk followed by the trailer section with `trailers`.
+pub fn encode_end(trailers: &[(String, String)]) -> Vec<u8> {
+    let mut out = String::from("0\r\n");
+    for (name, value) in trailers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    out.into_bytes()
+}
@@ -0,0 +1,265 @@
+//! The blocking `Client`.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Instant;
+
+use super::builder::ClientBuilder;
+use super::http1::{self, Error, Failed, Key, ResponseParser, Settings, MAX_IDLE, READ_SIZE};
+use super::policy::Policy;
+use crate::method::Method;
+use crate::request::{Request, TlsOptions};
+use crate::response::Response;
+use crate::url::Url;
+
+/// A connection requests can be sent over: a TCP stream or TLS on top of one.
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Starts TLS for `https` URLs, so the library needs no TLS implementation of its own.
+pub trait Connector: Send + Sync {
+    /// Start TLS on `tcp`, which is connected to `host` or tunnelled to it through a proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host name to verify the certificate against, without IPv6 brackets.
+    /// * `tcp` - The connected stream.
+    /// * `tls` - The TLS settings of the request.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Box<dyn Stream>>` - The encrypted stream if the handshake succeeded.
+    fn connect(&self, host: &str, tcp: TcpStream, tls: &TlsOptions) -> io::Result<Box<dyn Stream>>;
+}
+
+/// A reusable blocking HTTP/1.1 client.
+///
+/// It holds the settings shared by its requests (TLS, timeouts, proxy, and default
+/// headers), keeps idle keep-alive connections for the next request to the same
+/// origin, and applies a `Policy`: idempotent requests that fail with a connection
+/// error, 429, or 5xx are retried up to `retries` times within the retry budget, and
+/// requests to a host whose circuit is open fail at once.
+///
+/// Build one with `Client::builder`; it can be shared between threads.
+pub struct Client {
+    connector: Option<Box<dyn Connector>>,
+    settings: Settings,
+    started: Instant,
+    policy: Mutex<Policy>,
+    idle: Mutex<Vec<(Key, Box<dyn Stream>)>>,
+}
+
+impl Client {
+    /// Start configuring a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Send a GET request to `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub fn get(&self, url: &str) -> Result<Response, Error> {
+        self.execute(Request::builder("GET", url).keep_alive(true).build()?)
+    }
+
+    /// Send a POST request with `body` to `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub fn post(&self, url: &str, body: impl Into<Vec<u8>>) -> Result<Response, Error> {
+        self.execute(
+            Request::builder("POST", url)
+                .keep_alive(true)
+                .body(body)
+                .build()?,
+        )
+    }
+
+    /// Send `request` and read the whole response.
+    ///
+    /// Default headers are added unless the request sets a header of the same name,
+    /// and the timeouts and TLS version of the request take precedence over the
+    /// client's. Redirects are not followed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, Error>` - The response with its body if successful, or why no response was received.
+    pub fn execute(&self, request: Request) -> Result<Response, Error> {
+        let (request, tls) = self.settings.prepare(request)?;
+        // Refuse before asking the policy, so an unsendable request never takes the probe
+        if request.url().is_https() && self.connector.is_none() {
+            return Err(Error::Invalid("HTTPS needs a TLS connector"));
+        }
+        let host = request.url().host_str();
+        let mut attempt = 0;
+        loop {
+            self.policy().allow_request(&host, self.started.elapsed())?;
+            let result = self.send(&request, &tls);
+            let now = self.started.elapsed();
+            let delay = self
+                .settings
+                .retry(&mut self.policy(), &request, &result, attempt, now);
+            let Some(delay) = delay else {
+                return result;
+            };
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    fn policy(&self) -> MutexGuard<'_, Policy> {
+        self.policy.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Send `request` once, on an idle connection if one is kept for its origin.
+    fn send(&self, request: &Request, tls: &TlsOptions) -> Result<Response, Error> {
+        let key = Key::new(request.url());
+        let bytes = http1::request_bytes(request);
+        if let Some(stream) = self.checkout(&key) {
+            // The server may have closed the idle connection; then use a new one
+            match self.exchange(stream, &key, request, &bytes) {
+                Err(failed) if failed.resend(request) => {}
+                result => return result.map_err(|failed| failed.error),
+            }
+        }
+        let stream = self.open(&key, request, tls)?;
+        self.exchange(stream, &key, request, &bytes)
+            .map_err(|failed| failed.error)
+    }
+
+    /// Write the request bytes and read the response, keeping the connection if it can be reused.
+    fn exchange(
+        &self,
+        mut stream: Box<dyn Stream>,
+        key: &Key,
+        request: &Request,
+        bytes: &[u8],
+    ) -> Result<Response, Failed> {
+        let unanswered = |err: io::Error| Failed {
+            error: err.into(),
+            unanswered: true,
+        };
+        stream.write_all(bytes).map_err(unanswered)?;
+        stream.flush().map_err(unanswered)?;
+        let mut parser = ResponseParser::new(request.method(), request.url());
+        read_response(&mut stream, &mut parser).map_err(|err| parser.failed(err))?;
+        let (response, reusable) = parser.finish()?;
+        if reusable && request.keep_alive() {
+            let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+            if idle.len() < MAX_IDLE {
+                idle.push((key.clone(), stream));
+            }
+        }
+        Ok(response)
+    }
+
+    /// Take an idle connection to the origin of `key`.
+    fn checkout(&self, key: &Key) -> Option<Box<dyn Stream>> {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        let index = idle.iter().position(|(idle_key, _)| idle_key == key)?;
+        Some(idle.remove(index).1)
+    }
+
+    /// Open a new connection to the origin of `key`, through the proxy and TLS as configured.
+    fn open(
+        &self,
+        key: &Key,
+        request: &Request,
+        tls: &TlsOptions,
+    ) -> Result<Box<dyn Stream>, Error> {
+        let connect_timeout = request.connect_timeout().or(self.settings.connect_timeout);
+        let read_timeout = request.read_timeout().or(self.settings.read_timeout);
+        let (host, port) = self.settings.address(key);
+        let mut tcp = connect(&host, port, connect_timeout)?;
+        tcp.set_read_timeout(read_timeout)?;
+        tcp.set_write_timeout(read_timeout)?;
+        if !key.https {
+            return Ok(Box::new(tcp));
+        }
+        if let Some(proxy) = &self.settings.proxy {
+            tunnel(&mut tcp, key, proxy)?;
+        }
+        match &self.connector {
+            Some(connector) => Ok(connector.connect(&key.host, tcp, tls)?),
+            None => Err(Error::Invalid("HTTPS needs a TLS connector")),
+        }
+    }
+}
+
+impl ClientBuilder<Box<dyn Connector>> {
+    /// Start TLS for `https` URLs with `connector`.
+    pub fn connector(mut self, connector: impl Connector + 'static) -> Self {
+        self.connector = Some(Box::new(connector));
+        self
+    }
+
+    /// Finish the client.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Client, &'static str>` - The client if successful, or the first invalid setting.
+    pub fn build(self) -> Result<Client, &'static str> {
+        let (connector, settings) = self.finish()?;
+        Ok(Client {
+            connector,
+            started: Instant::now(),
+            policy: Mutex::new(Policy::new(settings.config)),
+            idle: Mutex::new(Vec::new()),
+            settings,
+        })
+    }
+}
+
+/// Connect to the first address of `host` that accepts.
+fn connect(host: &str, port: u16, timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(ErrorKind::NotFound, "host has no addresses");
+    for addr in (host, port).to_socket_addrs()? {
+        let result = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        };
+        match result {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last = err,
+        }
+    }
+    Err(last)
+}
+
+/// Ask the proxy for a tunnel to the origin of `key` with CONNECT.
+fn tunnel(tcp: &mut TcpStream, key: &Key, proxy: &Url) -> Result<(), Error> {
+    tcp.write_all(&http1::connect_request(key, proxy))?;
+    let mut parser = ResponseParser::new(&Method::Connect, proxy);
+    read_response(tcp, &mut parser)?;
+    match parser.into_head() {
+        Some(response) => http1::check_tunnel(&response),
+        None => Err(Error::Protocol("Proxy CONNECT failed".into())),
+    }
+}
+
+/// Read from `stream` into `parser` until the response is complete.
+fn read_response(stream: &mut impl Read, parser: &mut ResponseParser) -> Result<(), Error> {
+    let mut chunk = [0u8; READ_SIZE];
+    loop {
+        let n = match stream.read(&mut chunk) {
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if n == 0 {
+            return parser.end();
+        }
+        if parser.feed(&chunk[..n])? {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! `ClientBuilder`, shared by the blocking and async clients.
+
+use alloc::string::ToString;
+use core::time::Duration;
+
+use super::http1::Settings;
+use super::policy::ClientConfig;
+use crate::url::Url;
+
+/// Configures a `Client`; `C` is the kind of TLS connector it takes.
+///
+/// `client::Client::builder` returns the builder of the blocking client and
+/// `r#async::Client::builder` that of the async one. Like `RequestBuilder`, an
+/// invalid setting is reported by `build`.
+pub struct ClientBuilder<C = alloc::boxed::Box<dyn super::Connector>> {
+    pub(crate) connector: Option<C>,
+    pub(crate) settings: Settings,
+    pub(crate) error: Option<&'static str>,
+}
+
+impl<C> Default for ClientBuilder<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> ClientBuilder<C> {
+    /// Start with no TLS connector, proxy, default headers, or retries, and the default `ClientConfig`.
+    pub fn new() -> Self {
+        ClientBuilder {
+            connector: None,
+            settings: Settings::default(),
+            error: None,
+        }
+    }
+
+    /// Require at least TLS `version`: `1.0`, `1.1`, `1.2`, or `1.3`.
+    pub fn tls_version(mut self, version: &str) -> Self {
+        if !matches!(version, "1.0" | "1.1" | "1.2" | "1.3") {
+            self.error.get_or_insert("Invalid TLS version");
+        }
+        self.settings.tls.min_version = Some(version.to_string());
+        self
+    }
+
+    /// Give up on connecting after `timeout`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Give up on a read or write that makes no progress for `timeout`.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request through the HTTP proxy at `url`; credentials in it are sent as Basic auth.
+    pub fn proxy(mut self, url: &str) -> Self {
+        match Url::parse(url) {
+            Ok(proxy) if !proxy.is_https() => self.settings.proxy = Some(proxy),
+            Ok(_) => {
+                self.error
+                    .get_or_insert("Only http:// proxies are supported");
+            }
+            Err(err) => {
+                self.error.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    /// Send `name: value` with every request that does not set `name` itself.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        let invalid = |text: &str| text.contains(['\r', '\n']);
+        if name.is_empty() || name.contains(':') || invalid(name) || invalid(value) {
+            self.error.get_or_insert("Invalid header format");
+        }
+        self.settings
+            .headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Retry a failed idempotent request up to `retries` times, within the retry budget.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.settings.retries = retries;
+        self
+    }
+
+    /// Wait `delay` between retries instead of backing off exponentially from one second.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.settings.retry_delay = Some(delay);
+        self
+    }
+
+    /// Set the retry budget and circuit breaker settings.
+    pub fn config(mut self, config: ClientConfig) -> Self {
+        self.settings.config = config;
+        self
+    }
+
+    /// The connector and settings to build a client from, or the first invalid setting.
+    pub(crate) fn finish(self) -> Result<(Option<C>, Settings), &'static str> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok((self.connector, self.settings)),
+        }
+    }
+}
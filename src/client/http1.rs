@@ -0,0 +1,442 @@
+//! The parts of an HTTP/1.1 exchange shared by the blocking and async clients.
+//!
+//! Everything here is I/O-free: requests are prepared and serialized, and responses
+//! are parsed from bytes as the caller reads them, whatever kind of stream it has.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+use std::io;
+
+use super::policy::{ClientConfig, Policy, Rejected};
+use crate::chunked;
+use crate::method::Method;
+use crate::request::{base64_encode, Request, TlsOptions};
+use crate::response::{self, Response};
+use crate::url::Url;
+
+/// Upper bound for a response head, so a misbehaving server cannot exhaust memory
+const MAX_HEAD_SIZE: usize = 1024 * 1024;
+
+/// Idle connections kept for reuse by one client
+pub(crate) const MAX_IDLE: usize = 8;
+
+/// First delay of the exponential backoff between retries
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for a single delay between retries
+const MAX_DELAY: Duration = Duration::from_secs(600);
+
+/// Size of the buffer each read from a connection goes into
+pub(crate) const READ_SIZE: usize = 16 * 1024;
+
+/// Why a request through a client failed.
+#[derive(Debug)]
+pub enum Error {
+    /// The request cannot be sent as given
+    Invalid(&'static str),
+    /// Connecting, sending, or receiving failed or timed out
+    Io(io::Error),
+    /// The server or proxy did not answer with valid HTTP/1.1
+    Protocol(String),
+    /// The circuit breaker holds requests to the host back
+    Rejected(Rejected),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Invalid(message) => write!(f, "{}", message),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Protocol(message) => write!(f, "{}", message),
+            Error::Rejected(rejected) => write!(f, "{}", rejected),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<&'static str> for Error {
+    fn from(message: &'static str) -> Self {
+        Error::Invalid(message)
+    }
+}
+
+impl From<Rejected> for Error {
+    fn from(rejected: Rejected) -> Self {
+        Error::Rejected(rejected)
+    }
+}
+
+/// Why one exchange on a connection failed.
+pub(crate) struct Failed {
+    pub(crate) error: Error,
+    /// The request could not be written, or the connection ended before any of the
+    /// response arrived, as when the server had closed an idle connection
+    pub(crate) unanswered: bool,
+}
+
+impl Failed {
+    /// Whether `request` may be sent again on a new connection after this failure on
+    /// a reused one.
+    ///
+    /// Only an idempotent request that the server cannot have answered qualifies; any
+    /// other failure is the outcome of the attempt, left to the retry policy.
+    pub(crate) fn resend(&self, request: &Request) -> bool {
+        self.unanswered && request.method().is_idempotent()
+    }
+}
+
+impl From<Error> for Failed {
+    fn from(error: Error) -> Self {
+        Failed {
+            error,
+            unanswered: false,
+        }
+    }
+}
+
+/// The origin a connection leads to, for reusing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Key {
+    pub(crate) https: bool,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+impl Key {
+    pub(crate) fn new(url: &Url) -> Key {
+        Key {
+            https: url.is_https(),
+            host: url.host_str(),
+            port: url.port(),
+        }
+    }
+}
+
+/// The settings a client applies to each of its requests.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Settings {
+    pub(crate) tls: TlsOptions,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) proxy: Option<Url>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) retries: u32,
+    pub(crate) retry_delay: Option<Duration>,
+    pub(crate) config: ClientConfig,
+}
+
+impl Settings {
+    /// Apply the client settings to `request`.
+    ///
+    /// Default headers are added unless the request sets a header of the same name,
+    /// and a request through the proxy gets the absolute target and proxy credentials.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(Request, TlsOptions), Error>` - The request to send and its TLS settings, or why it cannot be sent.
+    pub(crate) fn prepare(&self, mut request: Request) -> Result<(Request, TlsOptions), Error> {
+        if request.tls().http2 {
+            return Err(Error::Invalid("HTTP/2 is not supported by Client"));
+        }
+        for (name, value) in &self.headers {
+            request.set_default_header(name, value);
+        }
+        if let Some(proxy) = &self.proxy {
+            if !request.url().is_https() {
+                request.use_absolute_target();
+                if let Some(credentials) = proxy_credentials(proxy) {
+                    request.set_default_header("Proxy-Authorization", &credentials);
+                }
+            }
+        }
+        let tls = TlsOptions {
+            min_version: request
+                .tls()
+                .min_version
+                .clone()
+                .or_else(|| self.tls.min_version.clone()),
+            http2: false,
+        };
+        Ok((request, tls))
+    }
+
+    /// Where a connection to the origin of `key` is opened: the proxy, or the origin itself.
+    pub(crate) fn address(&self, key: &Key) -> (String, u16) {
+        match &self.proxy {
+            Some(proxy) => (proxy.host_str(), proxy.port()),
+            None => (key.host.clone(), key.port),
+        }
+    }
+
+    /// Record the outcome of one attempt at `request` and decide whether to try again.
+    ///
+    /// A connection error, 429, or 5xx counts as a failure of the host; only
+    /// idempotent requests are retried, up to `retries` times within the budget.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Duration>` - The delay to wait before the next attempt, or None to return `result`.
+    pub(crate) fn retry(
+        &self,
+        policy: &mut Policy,
+        request: &Request,
+        result: &Result<Response, Error>,
+        attempt: u32,
+        now: Duration,
+    ) -> Option<Duration> {
+        let host = request.url().host_str();
+        let failed = match result {
+            Ok(response) => {
+                response.status().as_u16() == 429 || response.status().is_server_error()
+            }
+            // A request that cannot be sent says nothing about the host; `execute`
+            // refuses those before the policy lets the attempt through
+            Err(Error::Invalid(_)) => return None,
+            Err(_) => true,
+        };
+        if !failed {
+            policy.record_success(&host);
+            return None;
+        }
+        policy.record_failure(&host, now);
+        let retry =
+            attempt < self.retries && request.method().is_idempotent() && policy.allow_retry();
+        retry.then(|| self.backoff(result, attempt))
+    }
+
+    /// The delay before retry number `attempt` (0 for the first retry).
+    ///
+    /// A `Retry-After` in seconds on the failed response is honoured, then a fixed
+    /// `retry_delay`; otherwise the delay starts at one second and doubles with every
+    /// attempt. It is never longer than ten minutes.
+    fn backoff(&self, result: &Result<Response, Error>, attempt: u32) -> Duration {
+        let requested = result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.header("retry-after"))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        requested
+            .or(self.retry_delay)
+            .unwrap_or_else(|| {
+                INITIAL_DELAY
+                    .checked_mul(1 << attempt.min(16))
+                    .unwrap_or(MAX_DELAY)
+            })
+            .min(MAX_DELAY)
+    }
+}
+
+/// The `Proxy-Authorization` value for the credentials in a proxy URL, if it has any.
+fn proxy_credentials(proxy: &Url) -> Option<String> {
+    let user = proxy.username()?;
+    let password = proxy.password().unwrap_or_default();
+    Some(format!(
+        "Basic {}",
+        base64_encode(format!("{}:{}", user, password).as_bytes())
+    ))
+}
+
+/// The bytes of `request`, ending a chunked body.
+pub(crate) fn request_bytes(request: &Request) -> Vec<u8> {
+    let mut bytes = request.to_http1();
+    if request.chunked() {
+        bytes.extend(request.end_chunked(|_| None));
+    }
+    bytes
+}
+
+/// The CONNECT request asking `proxy` for a tunnel to the origin of `key`.
+pub(crate) fn connect_request(key: &Key, proxy: &Url) -> Vec<u8> {
+    let authority = if key.host.contains(':') {
+        format!("[{}]:{}", key.host, key.port)
+    } else {
+        format!("{}:{}", key.host, key.port)
+    };
+    let mut head = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some(credentials) = proxy_credentials(proxy) {
+        head.push_str(&format!("Proxy-Authorization: {}\r\n", credentials));
+    }
+    head.push_str("\r\n");
+    head.into_bytes()
+}
+
+/// Check the proxy's answer to a CONNECT request.
+pub(crate) fn check_tunnel(response: &Response) -> Result<(), Error> {
+    if response.status().is_success() {
+        return Ok(());
+    }
+    Err(Error::Protocol(format!(
+        "Proxy CONNECT failed: HTTP {}",
+        response.status().as_u16()
+    )))
+}
+
+/// How the end of a response body is found.
+enum Framing {
+    Empty,
+    Length(usize),
+    Chunked(chunked::Decoder),
+    UntilClose,
+}
+
+/// Parses one response from the bytes of a connection as they are read.
+///
+/// Interim 1xx responses are skipped. A response to HEAD, a 204 or 304 status, and a
+/// successful CONNECT have no body whatever their Content-Length says.
+pub(crate) struct ResponseParser {
+    method: Method,
+    url: Url,
+    buffer: Vec<u8>,
+    head: Option<(Response, Framing, bool)>,
+    body: Vec<u8>,
+    done: bool,
+    received: bool,
+}
+
+impl ResponseParser {
+    /// Start parsing the response to a `method` request for `url`.
+    pub(crate) fn new(method: &Method, url: &Url) -> Self {
+        ResponseParser {
+            method: method.clone(),
+            url: url.clone(),
+            buffer: Vec::new(),
+            head: None,
+            body: Vec::new(),
+            done: false,
+            received: false,
+        }
+    }
+
+    /// The failure for `error` from reading the response: unanswered if the
+    /// connection ended before any byte of it.
+    pub(crate) fn failed(&self, error: Error) -> Failed {
+        let unanswered = !self.received
+            && matches!(&error, Error::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof);
+        Failed { error, unanswered }
+    }
+
+    /// Take the next bytes read from the connection.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - true once the response is complete, or the protocol error.
+    pub(crate) fn feed(&mut self, input: &[u8]) -> Result<bool, Error> {
+        self.received |= !input.is_empty();
+        self.buffer.extend_from_slice(input);
+        while self.head.is_none() {
+            let Some(end) = response::find_header_end(&self.buffer) else {
+                if self.buffer.len() > MAX_HEAD_SIZE {
+                    return Err(Error::Protocol("Response headers too large".to_string()));
+                }
+                return Ok(false);
+            };
+            let head: Vec<u8> = self.buffer.drain(..end).collect();
+            self.parse_head(&head)?;
+        }
+        let Some((_, framing, _)) = self.head.as_mut() else {
+            return Ok(false);
+        };
+        self.done = match framing {
+            Framing::Empty => true,
+            Framing::Length(remaining) => {
+                let n = (*remaining).min(self.buffer.len());
+                self.body.extend(self.buffer.drain(..n));
+                *remaining -= n;
+                *remaining == 0
+            }
+            Framing::Chunked(decoder) => {
+                let mut out = [0u8; READ_SIZE];
+                let mut pos = 0;
+                while pos < self.buffer.len() && !decoder.is_done() {
+                    let (used, written) = decoder
+                        .decode(&self.buffer[pos..], &mut out)
+                        .map_err(|err| Error::Protocol(err.to_string()))?;
+                    pos += used;
+                    self.body.extend_from_slice(&out[..written]);
+                }
+                self.buffer.drain(..pos);
+                decoder.is_done()
+            }
+            Framing::UntilClose => {
+                self.body.append(&mut self.buffer);
+                false
+            }
+        };
+        Ok(self.done)
+    }
+
+    /// Parse a response head; an interim response is dropped.
+    fn parse_head(&mut self, head: &[u8]) -> Result<(), Error> {
+        let protocol = |err: &str| Error::Protocol(err.to_string());
+        let status = response::parse_status(head).map_err(protocol)?;
+        let code = status.as_u16();
+        if status.is_informational() && code != 101 {
+            return Ok(());
+        }
+        let response = Response::parse(head, self.url.clone(), 0).map_err(protocol)?;
+        let framing = if self.method == Method::Head
+            || code == 101
+            || code == 204
+            || code == 304
+            || (self.method == Method::Connect && status.is_success())
+        {
+            Framing::Empty
+        } else if response::is_chunked_transfer(head) {
+            Framing::Chunked(chunked::Decoder::new())
+        } else if let Some(length) = response::get_content_length(head) {
+            Framing::Length(length)
+        } else {
+            Framing::UntilClose
+        };
+        let close = code == 101
+            || response.header("connection").is_some_and(|value| {
+                value
+                    .split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case("close"))
+            });
+        self.head = Some((response, framing, !close));
+        Ok(())
+    }
+
+    /// Note that the connection was closed; this ends a body that runs until then.
+    pub(crate) fn end(&mut self) -> Result<(), Error> {
+        match &self.head {
+            None => Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed before the response head",
+            ))),
+            Some((_, Framing::UntilClose, _)) => {
+                self.done = true;
+                Ok(())
+            }
+            Some(_) if self.done => Ok(()),
+            Some(_) => Err(Error::Protocol("Response body ended early".to_string())),
+        }
+    }
+
+    /// The parsed response with its body, and whether the connection can carry another
+    /// request; call it once `feed` returned true or `end` succeeded.
+    pub(crate) fn finish(self) -> Result<(Response, bool), Error> {
+        let Some((response, framing, keep)) = self.head.filter(|_| self.done) else {
+            return Err(Error::Protocol("Response incomplete".to_string()));
+        };
+        let reusable = keep && !matches!(framing, Framing::UntilClose) && self.buffer.is_empty();
+        Ok((response.with_body(self.body), reusable))
+    }
+
+    /// The head of the final response once it is parsed, for a CONNECT answer.
+    pub(crate) fn into_head(self) -> Option<Response> {
+        self.head.map(|(response, _, _)| response)
+    }
+}
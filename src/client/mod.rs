@@ -0,0 +1,24 @@
+//! HTTP/1.1 clients for embedding, and the policy they apply.
+//!
+//! `Policy` is I/O-free and available without `std`. `Client`, behind the default
+//! `std` feature, sends `request::Request`s over `std::net`, keeping idle
+//! connections for reuse; TLS is supplied by a `Connector`. The async client in
+//! `r#async` shares its builder, errors, and HTTP/1.1 parsing. The `rurl` binary
+//! does not go through these clients yet: it streams bodies and speaks HTTP/2
+//! over its own transport.
+
+#[cfg(feature = "std")]
+mod blocking;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+pub(crate) mod http1;
+mod policy;
+
+#[cfg(feature = "std")]
+pub use blocking::{Client, Connector, Stream};
+#[cfg(feature = "std")]
+pub use builder::ClientBuilder;
+#[cfg(feature = "std")]
+pub use http1::Error;
+pub use policy::{Circuit, ClientConfig, Policy, Rejected};
@@ -0,0 +1,198 @@
+//! The retry budget and circuit breakers shared by the requests of one client.
+//!
+//! A `Policy` keeps a retry budget, so retries stay a small share of the requests
+//! made, and a circuit breaker per host, so a host that keeps failing is left alone
+//! for a while instead of receiving a retry storm. It does no I/O and reads no clock:
+//! the caller passes the current monotonic time and reports each outcome.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::fmt;
+use core::time::Duration;
+
+/// Settings of the retry budget and the circuit breakers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Retries earned by each request, in percent of a retry
+    pub retry_percent: u32,
+    /// Retries available before any are earned, and the most that can be saved up (at least one)
+    pub retry_reserve: u32,
+    /// Consecutive failures to a host that open its circuit; 0 disables the breaker
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests before letting one probe through, and
+    /// how long a probe may take before another is let through in its place; a duration
+    /// too large to add to the current time, such as `Duration::MAX`, means for good
+    pub open_for: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            retry_percent: 20,
+            retry_reserve: 10,
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of the circuit of one host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Circuit {
+    /// Requests go through; counts the failures since the last success
+    Closed(u32),
+    /// Requests are rejected until the time given
+    Open(Duration),
+    /// One probe is in flight since the time given; its outcome closes or reopens the circuit
+    HalfOpen(Duration),
+}
+
+/// Why a request was not allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejected {
+    /// The circuit of the host is open; the duration is the time until a probe is allowed
+    CircuitOpen(String, Duration),
+    /// The circuit is half-open and its probe has not finished
+    ProbeInFlight(String),
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejected::CircuitOpen(host, wait) => write!(
+                f,
+                "circuit open for {} after repeated failures, retry in {}s",
+                host,
+                wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+            ),
+            Rejected::ProbeInFlight(host) => {
+                write!(f, "circuit half-open for {}, waiting for its probe", host)
+            }
+        }
+    }
+}
+
+/// The retry budget and circuit breakers of one client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    config: ClientConfig,
+    /// Retries available, in hundredths of a retry
+    balance: u64,
+    circuits: BTreeMap<String, Circuit>,
+}
+
+impl Policy {
+    /// Create a policy with a full retry reserve and every circuit closed.
+    pub fn new(config: ClientConfig) -> Self {
+        Policy {
+            config,
+            balance: u64::from(config.retry_reserve) * 100,
+            circuits: BTreeMap::new(),
+        }
+    }
+
+    /// The settings this policy was created with.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// The state of the circuit of `host`; hosts never seen are closed.
+    pub fn circuit(&self, host: &str) -> Circuit {
+        self.circuits
+            .get(&host.to_ascii_lowercase())
+            .copied()
+            .unwrap_or(Circuit::Closed(0))
+    }
+
+    /// Check whether a new request to `host` may be sent at `now`, and earn its share
+    /// of the retry budget if so.
+    ///
+    /// Once an open circuit has waited `open_for`, the next request is let through as
+    /// the probe and the circuit is half-open until its outcome is recorded. A probe
+    /// whose outcome is not recorded within `open_for`, because its caller gave up on
+    /// it, is replaced by the next request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Rejected>` - `Ok(())` if the request may be sent, or why it may not.
+    pub fn allow_request(&mut self, host: &str, now: Duration) -> Result<(), Rejected> {
+        let host = host.to_ascii_lowercase();
+        match self.circuits.get(&host).copied() {
+            Some(Circuit::Open(until)) if now < until => {
+                return Err(Rejected::CircuitOpen(host, until - now));
+            }
+            Some(Circuit::HalfOpen(since))
+                if since
+                    .checked_add(self.config.open_for)
+                    .is_none_or(|expiry| now < expiry) =>
+            {
+                return Err(Rejected::ProbeInFlight(host));
+            }
+            Some(Circuit::Open(_)) | Some(Circuit::HalfOpen(_)) => {
+                self.circuits.insert(host, Circuit::HalfOpen(now));
+            }
+            Some(Circuit::Closed(_)) | None => {}
+        }
+        let cap = u64::from(self.config.retry_reserve.max(1)) * 100;
+        self.balance = (self.balance + u64::from(self.config.retry_percent)).min(cap);
+        Ok(())
+    }
+
+    /// Take one retry from the budget.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if a retry was available and is now spent, false if the budget is exhausted.
+    pub fn allow_retry(&mut self) -> bool {
+        if self.balance < 100 {
+            return false;
+        }
+        self.balance -= 100;
+        true
+    }
+
+    /// Record that a request to `host` succeeded, closing its circuit.
+    pub fn record_success(&mut self, host: &str) {
+        self.circuits.remove(&host.to_ascii_lowercase());
+    }
+
+    /// Record that a request to `host` failed at `now`.
+    ///
+    /// The circuit opens when the failures in a row reach `failure_threshold`, or at
+    /// once when the probe of a half-open circuit fails.
+    pub fn record_failure(&mut self, host: &str, now: Duration) {
+        if self.config.failure_threshold == 0 {
+            return;
+        }
+        let open = Circuit::Open(
+            now.checked_add(self.config.open_for)
+                .unwrap_or(Duration::MAX),
+        );
+        let circuit = self
+            .circuits
+            .entry(host.to_ascii_lowercase())
+            .or_insert(Circuit::Closed(0));
+        *circuit = match *circuit {
+            Circuit::Closed(failures) if failures + 1 < self.config.failure_threshold => {
+                Circuit::Closed(failures + 1)
+            }
+            _ => open,
+        };
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::new(ClientConfig::default())
+    }
+}
+
+impl fmt::Display for Circuit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Circuit::Closed(_) => write!(f, "closed"),
+            Circuit::Open(_) => write!(f, "open"),
+            Circuit::HalfOpen(_) => write!(f, "half-open"),
+        }
+    }
+}
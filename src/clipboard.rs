@@ -0,0 +1,93 @@
+use std::env;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use crate::history::split_words;
+
+/// Largest body put on the clipboard
+pub const MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Clipboard commands tried in order, each reading the data from stdin.
+#[cfg(target_os = "macos")]
+const COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+
+#[cfg(windows)]
+const COMMANDS: &[&[&str]] = &[&["clip.exe"]];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    // WSL can reach the Windows clipboard
+    &["clip.exe"],
+];
+
+/// Put `data` on the system clipboard.
+///
+/// `$RURL_CLIPBOARD` names a command to use instead, e.g. `tmux load-buffer -`;
+/// otherwise the platform's clipboard tool is used (`pbcopy`, `clip.exe`, or
+/// `wl-copy`, `xclip`, `xsel` on Linux and BSD).
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The name of the command used if successful, or an error message if no clipboard command worked.
+pub fn copy(data: &[u8]) -> Result<String, String> {
+    if let Ok(custom) = env::var("RURL_CLIPBOARD") {
+        let words = split_words(&custom)?;
+        if words.is_empty() {
+            return Err("Empty $RURL_CLIPBOARD".to_string());
+        }
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        return match run(&words, data) {
+            Ok(()) => Ok(words[0].to_string()),
+            Err(err) => Err(err.unwrap_or_else(|| format!("Command '{}' not found", words[0]))),
+        };
+    }
+
+    for command in COMMANDS {
+        match run(command, data) {
+            Ok(()) => return Ok(command[0].to_string()),
+            // Not installed: try the next one
+            Err(None) => continue,
+            Err(Some(err)) => return Err(err),
+        }
+    }
+    Err(format!(
+        "No clipboard command found (tried {}); set RURL_CLIPBOARD",
+        COMMANDS
+            .iter()
+            .map(|command| command[0])
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// Run a clipboard command with `data` on stdin.
+///
+/// The error is None when the command does not exist.
+fn run(command: &[&str], data: &[u8]) -> Result<(), Option<String>> {
+    let mut child = match Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Err(None),
+        Err(err) => return Err(Some(format!("Failed to run '{}': {}", command[0], err))),
+    };
+
+    let written = child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut stdin| stdin.write_all(data));
+    let status = child
+        .wait()
+        .map_err(|err| Some(format!("Failed to run '{}': {}", command[0], err)))?;
+    written.map_err(|err| Some(format!("Failed to write to '{}': {}", command[0], err)))?;
+    if !status.success() {
+        return Err(Some(format!("'{}' exited with {}", command[0], status)));
+    }
+    Ok(())
+}
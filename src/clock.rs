@@ -0,0 +1,167 @@
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The source of time for retries, `--retry-max-time`, and cookie expiry.
+///
+/// Requests carry their clock in `Args::clock`, so tests can swap in a `MockClock`
+/// and check backoff and expiry without waiting. Socket timeouts are enforced by the
+/// operating system and always follow real time.
+pub trait Clock: Send + Sync {
+    /// Monotonic time since an arbitrary fixed point; only differences are meaningful.
+    fn monotonic(&self) -> Duration;
+
+    /// Wall-clock time in seconds since the Unix epoch.
+    fn unix_time(&self) -> u64;
+
+    /// Wait for `duration`, e.g. before a retry.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock.
+#[derive(Debug)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn monotonic(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn unix_time(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when told to: sleeping advances it instantly.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MockClock {
+    unix_start: u64,
+    state: Mutex<MockState>,
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+struct MockState {
+    elapsed: Duration,
+    sleeps: Vec<Duration>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    /// A clock starting at `unix_start` seconds since the Unix epoch.
+    pub fn new(unix_start: u64) -> Self {
+        MockClock {
+            unix_start,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.lock().elapsed += duration;
+    }
+
+    /// Every `sleep` requested so far, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.lock().sleeps.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        // A panicking test cannot leave the state half-updated
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn monotonic(&self) -> Duration {
+        self.lock().elapsed
+    }
+
+    fn unix_time(&self) -> u64 {
+        self.unix_start + self.lock().elapsed.as_secs()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut state = self.lock();
+        state.elapsed += duration;
+        state.sleeps.push(duration);
+    }
+}
+
+/// The wall-clock time to stamp on requests: `clock`'s time moved by `offset` seconds,
+/// from `--clock-offset`, for a machine whose clock is known to drift.
+pub fn request_time(clock: &dyn Clock, offset: i64) -> u64 {
+    clock.unix_time().saturating_add_signed(offset)
+}
+
+/// Format seconds since the Unix epoch as an IMF-fixdate (RFC 7231), e.g.
+/// `Wed, 21 Oct 2015 07:28:00 GMT`.
+pub fn http_date(secs: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let days = secs / 86_400;
+    let rest = secs % 86_400;
+    let (year, month, day) = civil_date(days);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60
+    )
+}
+
+/// The (year, month, day) of a number of days since the Unix epoch (Howard Hinnant's
+/// algorithm).
+pub fn civil_date(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (year_of_era + era * 400 + u64::from(month <= 2), month, day)
+}
+
+/// The clock used unless a test injects another.
+pub fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock::new())
+}
@@ -0,0 +1,280 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::args::Args;
+use crate::http::request;
+use rurl::url::Url;
+
+/// Default options for requests to one host, from a `[host "pattern"]` section.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub pattern: String,
+    pub headers: Vec<String>,
+    pub user: Option<String>,
+    pub user_agent: Option<String>,
+    pub proxy: Option<String>,
+    pub proxy_user: Option<String>,
+    pub proxy_cacert: Option<String>,
+    pub tls_version: Option<String>,
+    pub http2: bool,
+    pub require_tls: bool,
+}
+
+impl Profile {
+    /// Check whether the profile applies to `host` on `port`.
+    ///
+    /// A pattern is a host name, optionally with a port (`localhost:8080`), or
+    /// `*.example.com` to match every subdomain.
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let pattern = self.pattern.to_ascii_lowercase();
+        let (pattern, pattern_port) = match pattern.rsplit_once(':') {
+            Some((name, port)) if !name.contains(':') || name.ends_with(']') => {
+                (name.to_string(), port.parse::<u16>().ok())
+            }
+            _ => (pattern, None),
+        };
+        if pattern_port.is_some_and(|p| p != port) {
+            return false;
+        }
+
+        let pattern = pattern.trim_start_matches('[').trim_end_matches(']');
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{}", domain)),
+            None => host == pattern,
+        }
+    }
+}
+
+/// Location of the user's config file, `~/.rurlrc`.
+pub fn default_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".rurlrc"))
+}
+
+/// Turn the options before the first section of a config file into command-line
+/// arguments, so they go through the same option table as the command line.
+///
+/// Lines are curl-style: an option name with or without its dashes, then its value
+/// (if it takes one) after whitespace, `=`, or `:`. So `silent`, `--silent`,
+/// `header = "Accept: text/plain"`, `header: Accept: text/plain`, and
+/// `-H "Accept: text/plain"` all work. Quoted values may use `\"`, `\\`, `\n`, and `\t`.
+/// `#` and `;` start comment lines.
+///
+/// # Arguments
+///
+/// * `contents` - The config file contents.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>, &'static str>` - The arguments in file order if successful, or an error message if unsuccessful.
+pub fn parse_options(contents: &str) -> Result<Vec<String>, &'static str> {
+    let mut options = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        // Host sections and everything after them are profiles
+        if line.starts_with('[') {
+            break;
+        }
+
+        let end = line
+            .find(|c: char| c.is_whitespace() || c == '=' || c == ':')
+            .unwrap_or(line.len());
+        let (name, value) = line.split_at(end);
+        let value = value
+            .trim_start()
+            .trim_start_matches(['=', ':'])
+            .trim_start();
+        if name.starts_with('-') {
+            options.push(name.to_string());
+        } else {
+            options.push(format!("--{}", name));
+        }
+        if !value.is_empty() {
+            options.push(unescape(value)?);
+        }
+    }
+    Ok(options)
+}
+
+/// Strip the quotes of a quoted value and resolve its escapes; other values are kept as-is.
+fn unescape(value: &str) -> Result<String, &'static str> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return Ok(value.to_string());
+    };
+    let mut unescaped = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Ok(unescaped),
+            '\\' => match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(c) => unescaped.push(c),
+                None => break,
+            },
+            _ => unescaped.push(c),
+        }
+    }
+    Err("Unterminated quote in config file")
+}
+
+/// Read the options of the config file at `path`.
+pub fn load_options(path: &str) -> Result<Vec<String>, &'static str> {
+    let contents = fs::read_to_string(path).map_err(|_| "Failed to read config file")?;
+    parse_options(&contents)
+}
+
+/// Parse the host profiles in a config file.
+///
+/// Sections look like `[host "api.example.com"]`, followed by `name = value`
+/// lines using the long option names (`header`, `user`, `user-agent`, `proxy`,
+/// `proxy-user`, `proxy-cacert`, `tls-version`, `http2`, `require-tls`). Values may be quoted;
+/// `#` and `;` start comment lines.
+///
+/// # Arguments
+///
+/// * `contents` - The config file contents.
+///
+/// # Returns
+///
+/// * `Result<Vec<Profile>, String>` - The profiles in file order if successful, or an error message naming the line if unsuccessful.
+pub fn parse_profiles(contents: &str) -> Result<Vec<Profile>, String> {
+    let mut profiles: Vec<Profile> = Vec::new();
+    let mut in_host_section = false;
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| format!("Config line {}: {}", number + 1, message);
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[') {
+            let section = section
+                .strip_suffix(']')
+                .ok_or_else(|| error("unclosed section"))?;
+            in_host_section = false;
+            let (kind, pattern) = section.trim().split_once(' ').unwrap_or((section, ""));
+            if kind == "host" {
+                let pattern = unquote(pattern.trim());
+                if pattern.is_empty() {
+                    return Err(error("missing host pattern"));
+                }
+                profiles.push(Profile {
+                    pattern: pattern.to_string(),
+                    ..Profile::default()
+                });
+                in_host_section = true;
+            }
+            continue;
+        }
+
+        // Only host sections are understood; other sections are skipped
+        let Some(profile) = profiles.last_mut().filter(|_| in_host_section) else {
+            continue;
+        };
+
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), unquote(value.trim())),
+            None => (line, ""),
+        };
+        let value = value.to_string();
+        match name {
+            "header" => profile.headers.push(value),
+            "user" => profile.user = Some(value),
+            "user-agent" => profile.user_agent = Some(value),
+            "proxy" => profile.proxy = Some(value),
+            "proxy-user" => profile.proxy_user = Some(value),
+            "proxy-cacert" => profile.proxy_cacert = Some(value),
+            "tls-version" => profile.tls_version = Some(value),
+            "http2" => profile.http2 = parse_bool(&value).ok_or_else(|| error("invalid http2"))?,
+            "require-tls" => {
+                profile.require_tls =
+                    parse_bool(&value).ok_or_else(|| error("invalid require-tls"))?
+            }
+            _ => return Err(error(&format!("unknown option '{}'", name))),
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// Strip one pair of surrounding double quotes.
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Parse a boolean option; a bare option name means true.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Fill in defaults from every profile matching the request URL.
+///
+/// Options given on the command line always win: a profile header is skipped when
+/// a header of the same name was given with `-H`, and single-valued options are
+/// only set if still unset, so the first matching profile takes precedence.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The patterns of the profiles that were applied.
+pub fn apply(args: &mut Args, profiles: &[Profile]) -> Vec<String> {
+    let Ok(url) = Url::parse(&args.url) else {
+        return Vec::new();
+    };
+    let host = url.host_str();
+
+    let mut applied = Vec::new();
+    for profile in profiles.iter().filter(|p| p.matches(&host, url.port())) {
+        for header in &profile.headers {
+            let name = header.split(':').next().unwrap_or("").trim();
+            if !request::has_header(args, name) {
+                args.headers.push(header.clone());
+            }
+        }
+        fill(&mut args.user, &profile.user);
+        fill(&mut args.user_agent, &profile.user_agent);
+        fill(&mut args.proxy, &profile.proxy);
+        fill(&mut args.proxy_user, &profile.proxy_user);
+        fill(&mut args.proxy_cacert, &profile.proxy_cacert);
+        fill(&mut args.tls_version, &profile.tls_version);
+        args.http2 |= profile.http2;
+        args.require_tls |= profile.require_tls;
+        applied.push(profile.pattern.clone());
+    }
+    applied
+}
+
+/// Set `target` from `default` unless it already has a value.
+fn fill(target: &mut Option<String>, default: &Option<String>) {
+    if target.is_none() {
+        target.clone_from(default);
+    }
+}
+
+/// Load the profiles from `~/.rurlrc`, if it exists.
+pub fn load_profiles() -> Result<Vec<Profile>, String> {
+    let Some(path) = default_path() else {
+        return Ok(Vec::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            parse_profiles(&contents).map_err(|err| format!("{} in '{}'", err, path.display()))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(format!("Failed to read '{}': {}", path.display(), err)),
+    }
+}
@@ -0,0 +1,599 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::args::Args;
+use crate::http::response;
+use rurl::url::Url;
+
+/// Prefix curl uses to mark HttpOnly cookies in Netscape cookie files.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// A single stored cookie.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// Expiry as seconds since the Unix epoch, 0 for a session cookie.
+    pub expires: u64,
+    pub name: String,
+    pub value: String,
+}
+
+impl Cookie {
+    /// Whether the cookie has expired at `now` (session cookies never do).
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires != 0 && self.expires <= now
+    }
+
+    /// Whether the cookie belongs to `domain` or one of its subdomains.
+    pub fn matches_domain(&self, domain: &str) -> bool {
+        let cookie_domain = self.domain.trim_start_matches('.').to_lowercase();
+        let domain = domain.trim_start_matches('.').to_lowercase();
+        cookie_domain == domain || cookie_domain.ends_with(&format!(".{}", domain))
+    }
+
+    /// Whether the cookie should be sent to `host` (host-only cookies need an exact match).
+    fn matches_host(&self, host: &str) -> bool {
+        let domain = self.domain.trim_start_matches('.');
+        if self.include_subdomains {
+            domain_matches(host, domain)
+        } else {
+            host.eq_ignore_ascii_case(domain)
+        }
+    }
+
+    /// Parse a `Set-Cookie` header value received from `host` for `request_path`.
+    ///
+    /// Cookies whose Domain attribute does not cover `host` are rejected (RFC 6265, Section 5.3).
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The Set-Cookie value.
+    /// * `host` - The host the response came from.
+    /// * `request_path` - The path of the request, used for the default cookie path.
+    /// * `now` - The current time in seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Cookie>` - The cookie, or None if it is malformed or not allowed for `host`.
+    pub fn parse_set_cookie(
+        header: &str,
+        host: &str,
+        request_path: &str,
+        now: u64,
+    ) -> Option<Self> {
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let host = host.to_lowercase();
+        let mut cookie = Cookie {
+            domain: host.clone(),
+            include_subdomains: false,
+            path: default_path(request_path),
+            secure: false,
+            http_only: false,
+            expires: 0,
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+        };
+        let mut max_age = None;
+
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = format!(".{}", domain);
+                    cookie.include_subdomains = true;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "expires" => {
+                    if let Some(expires) = parse_http_date(value) {
+                        // 0 means session, so an expiry at the epoch becomes 1
+                        cookie.expires = expires.max(1);
+                    }
+                }
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+
+        // Max-Age takes precedence over Expires
+        if let Some(max_age) = max_age {
+            cookie.expires = if max_age <= 0 {
+                1
+            } else {
+                now.saturating_add(max_age as u64)
+            };
+        }
+
+        Some(cookie)
+    }
+
+    /// Parse one line of a Netscape cookie file.
+    fn from_netscape_line(line: &str) -> Result<Self, String> {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(format!("Invalid cookie line: {}", line));
+        }
+
+        let flag = |value: &str| value.eq_ignore_ascii_case("TRUE");
+        let expires = fields[4]
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid cookie expiry: {}", fields[4]))?;
+
+        Ok(Cookie {
+            domain: fields[0].to_string(),
+            include_subdomains: flag(fields[1]),
+            path: fields[2].to_string(),
+            secure: flag(fields[3]),
+            http_only,
+            expires,
+            name: fields[5].to_string(),
+            value: fields[6].to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Cookie {
+    /// Format the cookie as a Netscape cookie file line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        write!(
+            f,
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            if self.http_only { HTTP_ONLY_PREFIX } else { "" },
+            self.domain,
+            flag(self.include_subdomains),
+            self.path,
+            flag(self.secure),
+            self.expires,
+            self.name,
+            self.value
+        )
+    }
+}
+
+/// An in-memory cookie jar backed by a Netscape cookie file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Parse the contents of a Netscape cookie file.
+    ///
+    /// Blank lines and `#` comments are skipped; `#HttpOnly_` lines are cookies.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut jar = CookieJar::default();
+        for line in content.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty()
+                || (line.starts_with('#') && !line.starts_with(HTTP_ONLY_PREFIX))
+            {
+                continue;
+            }
+            jar.insert(Cookie::from_netscape_line(line)?);
+        }
+        Ok(jar)
+    }
+
+    /// Load a jar from `path`, or an empty jar if the file does not exist.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(CookieJar::default()),
+            Err(err) => Err(format!("Failed to read cookie jar {}: {}", path, err)),
+        }
+    }
+
+    /// Write the jar to `path` in Netscape format.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_netscape())
+            .map_err(|err| format!("Failed to write cookie jar {}: {}", path, err))
+    }
+
+    /// Serialize the jar as a Netscape cookie file.
+    pub fn to_netscape(&self) -> String {
+        let mut out = String::from("# Netscape HTTP Cookie File\n");
+        for cookie in &self.cookies {
+            out.push_str(&format!("{}\n", cookie));
+        }
+        out
+    }
+
+    /// Add a cookie, replacing any existing one with the same domain, path, and name.
+    pub fn insert(&mut self, cookie: Cookie) {
+        match self.cookies.iter_mut().find(|c| {
+            c.domain.eq_ignore_ascii_case(&cookie.domain)
+                && c.path == cookie.path
+                && c.name == cookie.name
+        }) {
+            Some(existing) => *existing = cookie,
+            None => self.cookies.push(cookie),
+        }
+    }
+
+    /// Merge every cookie of `other` into this jar.
+    pub fn merge(&mut self, other: CookieJar) {
+        for cookie in other.cookies {
+            self.insert(cookie);
+        }
+    }
+
+    /// Drop cookies that have expired at `now`, returning how many were removed.
+    pub fn remove_expired(&mut self, now: u64) -> usize {
+        let before = self.cookies.len();
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+        before - self.cookies.len()
+    }
+
+    /// Keep only the cookies belonging to `domain`.
+    pub fn retain_domain(&mut self, domain: &str) {
+        self.cookies.retain(|cookie| cookie.matches_domain(domain));
+    }
+
+    /// Store the cookies set by a response, dropping any the server expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The response header block.
+    /// * `host` - The host the response came from.
+    /// * `request_path` - The path of the request.
+    /// * `now` - The current time in seconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of Set-Cookie headers accepted.
+    pub fn store_response(
+        &mut self,
+        headers: &[u8],
+        host: &str,
+        request_path: &str,
+        now: u64,
+    ) -> usize {
+        let mut stored = 0;
+        for (name, value) in response::header_fields(headers) {
+            if !name.eq_ignore_ascii_case(b"set-cookie") {
+                continue;
+            }
+            let value = String::from_utf8_lossy(value);
+            if let Some(cookie) = Cookie::parse_set_cookie(&value, host, request_path, now) {
+                self.insert(cookie);
+                stored += 1;
+            }
+        }
+        self.remove_expired(now);
+        stored
+    }
+
+    /// Build the `Cookie` header value for a request, most specific paths first.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The `name=value` pairs joined with `; `, or None if no cookie matches.
+    pub fn header_for(&self, host: &str, path: &str, is_https: bool, now: u64) -> Option<String> {
+        let mut matching: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| {
+                cookie.matches_host(host)
+                    && path_matches(path, &cookie.path)
+                    && (is_https || !cookie.secure)
+                    && !cookie.is_expired(now)
+            })
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    /// Iterate over the stored cookies.
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie> {
+        self.cookies.iter()
+    }
+}
+
+/// Check whether `host` equals `domain` or is a subdomain of it (IP addresses must match exactly).
+fn domain_matches(host: &str, domain: &str) -> bool {
+    let host = host.to_lowercase();
+    let domain = domain.to_lowercase();
+    host == domain || (host.parse::<IpAddr>().is_err() && host.ends_with(&format!(".{}", domain)))
+}
+
+/// Check whether a request path is within a cookie path (RFC 6265, Section 5.1.4).
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    let request_path = request_path.split(['?', '#']).next().unwrap_or("/");
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || request_path.as_bytes().get(cookie_path.len()) == Some(&b'/')))
+}
+
+/// The default cookie path: the request path up to, but not including, its last `/`.
+fn default_path(request_path: &str) -> String {
+    let path = request_path.split(['?', '#']).next().unwrap_or("");
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(pos) => path[..pos].to_string(),
+    }
+}
+
+/// Parse an HTTP date as used by the `Expires` attribute.
+///
+/// Accepts the IMF-fixdate, RFC 850, and asctime forms, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+///
+/// # Returns
+///
+/// * `Option<u64>` - Seconds since the Unix epoch, or None if the date cannot be parsed.
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+    for token in value
+        .split([' ', ',', '-'])
+        .filter(|token| !token.is_empty())
+    {
+        if token.contains(':') {
+            let mut fields = token.split(':').map(|field| field.parse::<u64>().ok());
+            time = Some((fields.next()??, fields.next()??, fields.next()??));
+        } else if let Ok(number) = token.parse::<u64>() {
+            if day.is_none() && token.len() <= 2 {
+                day = Some(number);
+            } else {
+                year = Some(match number {
+                    0..=69 => number + 2000,
+                    70..=99 => number + 1900,
+                    _ => number,
+                });
+            }
+        } else if let Some(index) = MONTHS
+            .iter()
+            .position(|name| token.to_lowercase().starts_with(name))
+        {
+            month = Some(index as u64 + 1);
+        }
+    }
+
+    let (day, month, year, (hour, minute, second)) = (day?, month?, year?, time?);
+    if !(1..=31).contains(&day) || year < 1970 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Days since the epoch for a proleptic Gregorian date (Howard Hinnant's algorithm)
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Build the `Cookie` header for a request: the `-b` literal followed by matching jar cookies.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookies collected so far.
+///
+/// # Returns
+///
+/// * `Option<String>` - The header value, or None if there are no cookies to send.
+pub fn request_header(args: &Args, jar: &CookieJar) -> Option<String> {
+    let from_jar = Url::parse(&args.url).ok().and_then(|url| {
+        jar.header_for(
+            &url.host_str(),
+            url.path(),
+            url.is_https(),
+            args.clock.unix_time(),
+        )
+    });
+
+    match (&args.cookie, from_jar) {
+        (Some(literal), Some(jar)) => Some(format!("{}; {}", literal, jar)),
+        (Some(literal), None) => Some(literal.clone()),
+        (None, jar) => jar,
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Validate a literal `name=value; name2=value2` cookie string and normalize its spacing.
+///
+/// # Returns
+///
+/// * `Result<String, &'static str>` - The value for a `Cookie` header if successful, or an error message if unsuccessful.
+pub fn parse_literal(value: &str) -> Result<String, &'static str> {
+    let mut pairs = Vec::new();
+    for pair in value.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, value) = pair.split_once('=').ok_or("Invalid cookie string")?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err("Invalid cookie string");
+        }
+        pairs.push(format!("{}={}", name, value.trim()));
+    }
+    if pairs.is_empty() {
+        return Err("Invalid cookie string");
+    }
+    Ok(pairs.join("; "))
+}
+
+/// Options for the `rurl cookies` subcommand.
+struct CommandArgs {
+    action: String,
+    jar: String,
+    domain: Option<String>,
+    delete_expired: bool,
+    input: Option<String>,
+    output: Option<String>,
+}
+
+impl CommandArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, &'static str> {
+        let mut parsed = CommandArgs {
+            action: args.next().ok_or("Missing cookies action")?,
+            jar: String::new(),
+            domain: None,
+            delete_expired: false,
+            input: None,
+            output: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--jar" => {
+                    parsed.jar = args.next().ok_or("Missing cookie jar file")?;
+                }
+                "--domain" => {
+                    parsed.domain = Some(args.next().ok_or("Missing domain")?);
+                }
+                "--delete-expired" => {
+                    parsed.delete_expired = true;
+                }
+                "-o" | "--output" => {
+                    parsed.output = Some(args.next().ok_or("Missing output file")?);
+                }
+                _ if arg.starts_with('-') => {
+                    return Err("Unknown option");
+                }
+                _ => {
+                    parsed.input = Some(arg);
+                }
+            }
+        }
+
+        if parsed.jar.is_empty() {
+            return Err("Missing --jar");
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Run `rurl cookies list|import|export --jar <file>`.
+///
+/// * `list` prints the cookies, optionally filtered with `--domain`.
+/// * `import <file>` merges a Netscape cookie file into the jar.
+/// * `export [-o <file>]` writes the jar (or the `--domain` subset) in Netscape format.
+///
+/// `--delete-expired` prunes expired cookies from the jar before the action runs.
+///
+/// # Arguments
+///
+/// * `args` - The arguments following `cookies` on the command line.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if successful, or an error message if unsuccessful.
+pub fn run_command(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let args = CommandArgs::parse(args)?;
+    let jar_path = args.jar.as_str();
+    let mut jar = CookieJar::load(jar_path)?;
+
+    if args.delete_expired {
+        let removed = jar.remove_expired(now());
+        jar.save(jar_path)?;
+        eprintln!("Removed {} expired cookie(s)", removed);
+    }
+
+    match args.action.as_str() {
+        "list" => {
+            if let Some(domain) = &args.domain {
+                jar.retain_domain(domain);
+            }
+            let now = now();
+            for cookie in jar.iter() {
+                let expires = match cookie.expires {
+                    0 => "session".to_string(),
+                    _ if cookie.is_expired(now) => format!("{} (expired)", cookie.expires),
+                    expires => expires.to_string(),
+                };
+                let mut flags = Vec::new();
+                if cookie.secure {
+                    flags.push("Secure");
+                }
+                if cookie.http_only {
+                    flags.push("HttpOnly");
+                }
+                println!(
+                    "{}\t{}\t{}={}\texpires={}\t{}",
+                    cookie.domain,
+                    cookie.path,
+                    cookie.name,
+                    cookie.value,
+                    expires,
+                    flags.join(",")
+                );
+            }
+        }
+        "import" => {
+            let input = args
+                .input
+                .as_deref()
+                .ok_or("Missing cookie file to import")?;
+            let content = std::fs::read_to_string(input)
+                .map_err(|err| format!("Failed to read {}: {}", input, err))?;
+            let mut imported = CookieJar::parse(&content)?;
+            if let Some(domain) = &args.domain {
+                imported.retain_domain(domain);
+            }
+            let count = imported.iter().count();
+            jar.merge(imported);
+            jar.save(jar_path)?;
+            eprintln!("Imported {} cookie(s) into {}", count, jar_path);
+        }
+        "export" => {
+            if let Some(domain) = &args.domain {
+                jar.retain_domain(domain);
+            }
+            match &args.output {
+                Some(path) => jar.save(path)?,
+                None => print!("{}", jar.to_netscape()),
+            }
+        }
+        _ => return Err("Unknown cookies action (expected list, import, or export)".to_string()),
+    }
+
+    Ok(())
+}
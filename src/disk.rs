@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::Path;
+
+use crate::args::Args;
+
+/// Fail before an `-o` or `-O` file is written when the body is known not to fit.
+///
+/// The body size comes from the response (Content-Length or the buffered range parts);
+/// a body of unknown size is never refused. A file that is about to be truncated counts
+/// as free space.
+///
+/// # Arguments
+///
+/// * `path` - The output file.
+/// * `needed` - The number of bytes that will be written.
+/// * `append` - Whether the file is continued rather than replaced.
+/// * `args` - A reference to an `Args` struct holding `--max-disk`.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if the body fits, or an error message naming the limit it exceeds.
+pub fn check(path: &str, needed: u64, append: bool, args: &Args) -> Result<(), String> {
+    if let Some(limit) = args.max_disk {
+        if needed > limit {
+            return Err(format!(
+                "Refusing to write '{}': the response body is {} bytes, more than --max-disk {}",
+                path, needed, limit
+            ));
+        }
+    }
+
+    let path = Path::new(path);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let Some(free) = free_space(directory) else {
+        return Ok(());
+    };
+    let replaced = match fs::metadata(path) {
+        Ok(metadata) if !append => metadata.len(),
+        _ => 0,
+    };
+    if needed > free.saturating_add(replaced) {
+        return Err(format!(
+            "Not enough disk space for '{}': the response body is {} bytes but only {} are free",
+            path.display(),
+            needed,
+            free.saturating_add(replaced)
+        ));
+    }
+    Ok(())
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The free space, or None if it cannot be determined on this platform.
+#[cfg(any(
+    all(target_os = "linux", target_pointer_width = "64"),
+    target_os = "macos"
+))]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::{c_char, c_int, c_ulong, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    // fsblkcnt_t is 64 bits on 64-bit Linux but 32 bits on macOS
+    #[cfg(target_os = "linux")]
+    type Blocks = u64;
+    #[cfg(target_os = "macos")]
+    type Blocks = u32;
+
+    // Only the leading fields are read; the padding covers the rest of the C struct
+    #[repr(C)]
+    struct StatVfs {
+        _bsize: c_ulong,
+        frsize: c_ulong,
+        _blocks: Blocks,
+        _bfree: Blocks,
+        bavail: Blocks,
+        _rest: [u64; 16],
+    }
+
+    extern "C" {
+        fn statvfs(path: *const c_char, buf: *mut StatVfs) -> c_int;
+    }
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = StatVfs {
+        _bsize: 0,
+        frsize: 0,
+        _blocks: 0,
+        _bfree: 0,
+        bavail: 0,
+        _rest: [0; 16],
+    };
+    // SAFETY: `path` is NUL-terminated and `stats` is at least as large as `struct statvfs`
+    if unsafe { statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    // Both widths differ by platform
+    #[allow(clippy::useless_conversion, clippy::unnecessary_cast)]
+    u64::from(stats.bavail).checked_mul(stats.frsize as u64)
+}
+
+/// Bytes available to the current user on the volume holding `path`.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The free space, or None if it cannot be determined.
+#[cfg(windows)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            available: *mut u64,
+            total: *mut u64,
+            free: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated; the total and free outputs are optional
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+/// Free space cannot be queried on this platform, so no download is refused for it.
+#[cfg(not(any(
+    all(target_os = "linux", target_pointer_width = "64"),
+    target_os = "macos",
+    windows
+)))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,47 @@
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+use crate::history::split_words;
+
+/// Let the user edit `contents` in `$VISUAL` or `$EDITOR` (default `vi`).
+///
+/// The text is written to a temporary file named after `name`, so editors can
+/// pick syntax highlighting from its extension, and read back once the editor exits.
+///
+/// # Arguments
+///
+/// * `contents` - The text to edit.
+/// * `name` - The file name suffix, such as `request.http`.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The edited text if the editor exited successfully, or an error message if unsuccessful.
+pub fn edit(contents: &str, name: &str) -> Result<String, String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = env::temp_dir().join(format!("rurl-{}-{}", process::id(), name));
+    fs::write(&path, contents)
+        .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+
+    // The editor setting may carry its own arguments, e.g. "code --wait"
+    let mut editor_words = split_words(&editor)?;
+    if editor_words.is_empty() {
+        let _ = fs::remove_file(&path);
+        return Err("Empty $EDITOR".to_string());
+    }
+    let program = editor_words.remove(0);
+    let status = Command::new(&program)
+        .args(editor_words)
+        .arg(&path)
+        .status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status.map_err(|err| format!("Failed to run editor '{}': {}", program, err))?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", program, status));
+    }
+    edited.map_err(|err| format!("Failed to read the edited file: {}", err))
+}
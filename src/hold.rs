@@ -0,0 +1,239 @@
+use std::io::{Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+use crate::cookies::CookieJar;
+use crate::http::client;
+use crate::http::pool::Connection;
+use crate::http::reader::ResponseReader;
+use crate::http::response;
+use crate::http::timeout::{self, READ_TIMEOUT, WRITE_TIMEOUT};
+#[cfg(feature = "http2")]
+use crate::http2::connection::Http2Connection;
+use crate::output::log::Logger;
+use crate::tls;
+use rurl::method::Method;
+use rurl::url::Url;
+
+/// Gap between probes unless `--interval` is given
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The open connection being held, and how it is probed.
+enum Probe {
+    /// A HEAD request (or the `-X` method) per probe; None once the server stopped reusing it
+    Http1(Option<Connection>),
+    /// A PING frame per probe
+    #[cfg(feature = "http2")]
+    Http2(Box<Http2Connection<Connection>>),
+}
+
+impl Probe {
+    /// Send one probe and wait for the answer.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - What the server answered if successful, or how the connection failed.
+    fn send(&mut self, args: &Args, jar: &CookieJar) -> Result<String, String> {
+        match self {
+            Probe::Http1(slot) => {
+                let mut connection = slot.take().ok_or("Connection not reusable")?;
+                let request = crate::http::request::build(args, jar)?;
+                connection
+                    .write_all(&request)
+                    .map_err(|err| format!("Write error: {}", err))?;
+                let mut reader =
+                    ResponseReader::new(connection, args.buffer_size, Logger::new(args));
+                let head = reader.read_head(&args.method)?;
+                let status = response::parse_status(&head)?;
+                std::io::copy(&mut reader, &mut std::io::sink())
+                    .map_err(|err| format!("Read error: {}", err))?;
+                *slot = reader.into_reusable();
+                if slot.is_none() {
+                    return Err(format!(
+                        "Server answered {} but ended the connection",
+                        status
+                    ));
+                }
+                Ok(status.to_string())
+            }
+            #[cfg(feature = "http2")]
+            Probe::Http2(connection) => connection.ping().map(|()| "PING acknowledged".to_string()),
+        }
+    }
+
+    /// Wait `gap` before the next probe.
+    ///
+    /// An HTTP/1.1 connection is watched while idle, so a close or reset is reported
+    /// as soon as it arrives rather than at the next probe.
+    fn idle(&mut self, gap: Duration) -> Result<(), String> {
+        let connection = match self {
+            Probe::Http1(Some(connection)) => connection,
+            _ => {
+                thread::sleep(gap);
+                return Ok(());
+            }
+        };
+        let deadline = Instant::now() + gap;
+        let mut byte = [0u8; 1];
+        let result = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break Ok(());
+            }
+            connection
+                .set_timeouts(Some(remaining), Some(WRITE_TIMEOUT))
+                .map_err(|err| format!("Failed to set socket timeout: {}", err))?;
+            match connection.read(&mut byte) {
+                Ok(0) => break Err("Connection closed by the server while idle".to_string()),
+                Ok(_) => break Err("Server sent data on an idle connection".to_string()),
+                Err(err) if timeout::is_timeout(&err) => continue,
+                Err(err) => break Err(format!("Connection failed while idle: {}", err)),
+            }
+        };
+        connection
+            .set_timeouts(Some(READ_TIMEOUT), Some(WRITE_TIMEOUT))
+            .map_err(|err| format!("Failed to set socket timeout: {}", err))?;
+        result
+    }
+}
+
+/// Run `rurl hold [--interval SECS] [--ramp] [--max-probes N] [OPTIONS] <URL>`.
+///
+/// Opens one connection to the URL (through TLS, `-x`, and `--http2` as for a
+/// request) and probes it every `--interval`: with a HEAD request on HTTP/1.1 or a
+/// PING on HTTP/2. Each probe and the moment the connection is dropped are reported
+/// with the time since connecting, which shows the idle timeout of the server or of
+/// a load balancer in between. `--ramp` doubles the gap after each probe to find it
+/// quickly.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if the connection outlived `--max-probes`, or a description of how it was dropped.
+pub fn run_command(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut interval = DEFAULT_INTERVAL;
+    let mut ramp = false;
+    let mut max_probes = None;
+    let mut rest = Vec::new();
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--interval" => {
+                let value = args.next().ok_or("Missing interval")?;
+                interval = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|secs| secs.is_finite() && *secs > 0.0)
+                    .map(Duration::from_secs_f64)
+                    .ok_or("Invalid interval")?;
+            }
+            "--ramp" => ramp = true,
+            "--max-probes" => {
+                let value = args.next().ok_or("Missing probe count")?;
+                max_probes = Some(
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or("Invalid probe count")?,
+                );
+            }
+            _ => rest.push(arg),
+        }
+    }
+    let mut args = Args::parse_from(rest)?;
+    if args.url.is_empty() {
+        return Err("Missing URL".to_string());
+    }
+    // A probe should not transfer a body
+    if args.method == Method::Get {
+        args.method = Method::Head;
+    }
+
+    let url = Url::parse(&args.url)?;
+    let (host, port, is_https) = (url.host_str(), url.port(), url.is_https());
+    let jar = CookieJar::default();
+    let started = Instant::now();
+    let stream = client::open_connection(&host, port, is_https, &args)?;
+    let (connection, use_http2) = if is_https {
+        let (stream, use_http2) = tls::connect(stream, &host, &args)?;
+        (Connection::Tls(Box::new(stream)), use_http2)
+    } else {
+        (Connection::Plain(stream), args.http2)
+    };
+    let mut probe = open(connection, use_http2, &args)?;
+    println!(
+        "Connected to {}:{} over {} in {} ms",
+        host,
+        port,
+        if use_http2 { "HTTP/2" } else { "HTTP/1.1" },
+        started.elapsed().as_millis()
+    );
+
+    let mut answered = 0;
+    let mut last_answer = Instant::now();
+    let mut gap = interval;
+    loop {
+        let sent = Instant::now();
+        let result = probe.send(&args, &jar);
+        let elapsed = started.elapsed().as_secs_f64();
+        match result {
+            Ok(answer) => {
+                answered += 1;
+                last_answer = Instant::now();
+                println!(
+                    "[{:>8.1}s] probe {}: {} in {} ms",
+                    elapsed,
+                    answered,
+                    answer,
+                    sent.elapsed().as_millis()
+                );
+            }
+            Err(err) => return Err(dropped(&err, elapsed, answered, last_answer)),
+        }
+        if max_probes.is_some_and(|max| answered >= max) {
+            println!(
+                "Connection still open after {} probe(s) over {:.1}s",
+                answered,
+                started.elapsed().as_secs_f64()
+            );
+            return Ok(());
+        }
+        if let Err(err) = probe.idle(gap) {
+            let elapsed = started.elapsed().as_secs_f64();
+            return Err(dropped(&err, elapsed, answered, last_answer));
+        }
+        if ramp {
+            gap *= 2;
+        }
+    }
+}
+
+/// Start probing `connection`, with the HTTP/2 handshake if it speaks HTTP/2.
+fn open(connection: Connection, use_http2: bool, args: &Args) -> Result<Probe, String> {
+    if !use_http2 {
+        return Ok(Probe::Http1(Some(connection)));
+    }
+    #[cfg(feature = "http2")]
+    {
+        let connection =
+            Http2Connection::handshake(connection, args.buffer_size, Logger::new(args))?;
+        Ok(Probe::Http2(Box::new(connection)))
+    }
+    #[cfg(not(feature = "http2"))]
+    {
+        let _ = args;
+        Err("HTTP/2 is not available: rurl was built without the http2 feature".to_string())
+    }
+}
+
+/// Describe a dropped connection: how, when, and after how long without traffic.
+fn dropped(err: &str, elapsed: f64, answered: u32, last_answer: Instant) -> String {
+    format!(
+        "{} at {:.1}s, {:.1}s after the last answer ({} probe(s) answered)",
+        err,
+        elapsed,
+        last_answer.elapsed().as_secs_f64(),
+        answered
+    )
+}
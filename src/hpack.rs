@@ -0,0 +1,776 @@
+//! HPACK header compression for HTTP/2 (RFC 7541).
+//!
+//! Provides the static table, a size-bounded dynamic table, prefix-integer and
+//! string literal coding, and the canonical Huffman code used by HTTP/2.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Default dynamic table size advertised by SETTINGS_HEADER_TABLE_SIZE.
+pub const DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// The HPACK static table (RFC 7541, Appendix A). Index 1 is the first entry.
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Huffman codes as (code, bit length) for symbols 0-255 and EOS (RFC 7541, Appendix B).
+const HUFFMAN_CODES: [(u32, u8); 257] = [
+    (0x1ff8, 13),
+    (0x7fffd8, 23),
+    (0xfffffe2, 28),
+    (0xfffffe3, 28),
+    (0xfffffe4, 28),
+    (0xfffffe5, 28),
+    (0xfffffe6, 28),
+    (0xfffffe7, 28),
+    (0xfffffe8, 28),
+    (0xffffea, 24),
+    (0x3ffffffc, 30),
+    (0xfffffe9, 28),
+    (0xfffffea, 28),
+    (0x3ffffffd, 30),
+    (0xfffffeb, 28),
+    (0xfffffec, 28),
+    (0xfffffed, 28),
+    (0xfffffee, 28),
+    (0xfffffef, 28),
+    (0xffffff0, 28),
+    (0xffffff1, 28),
+    (0xffffff2, 28),
+    (0x3ffffffe, 30),
+    (0xffffff3, 28),
+    (0xffffff4, 28),
+    (0xffffff5, 28),
+    (0xffffff6, 28),
+    (0xffffff7, 28),
+    (0xffffff8, 28),
+    (0xffffff9, 28),
+    (0xffffffa, 28),
+    (0xffffffb, 28),
+    (0x14, 6),
+    (0x3f8, 10),
+    (0x3f9, 10),
+    (0xffa, 12),
+    (0x1ff9, 13),
+    (0x15, 6),
+    (0xf8, 8),
+    (0x7fa, 11),
+    (0x3fa, 10),
+    (0x3fb, 10),
+    (0xf9, 8),
+    (0x7fb, 11),
+    (0xfa, 8),
+    (0x16, 6),
+    (0x17, 6),
+    (0x18, 6),
+    (0x0, 5),
+    (0x1, 5),
+    (0x2, 5),
+    (0x19, 6),
+    (0x1a, 6),
+    (0x1b, 6),
+    (0x1c, 6),
+    (0x1d, 6),
+    (0x1e, 6),
+    (0x1f, 6),
+    (0x5c, 7),
+    (0xfb, 8),
+    (0x7ffc, 15),
+    (0x20, 6),
+    (0xffb, 12),
+    (0x3fc, 10),
+    (0x1ffa, 13),
+    (0x21, 6),
+    (0x5d, 7),
+    (0x5e, 7),
+    (0x5f, 7),
+    (0x60, 7),
+    (0x61, 7),
+    (0x62, 7),
+    (0x63, 7),
+    (0x64, 7),
+    (0x65, 7),
+    (0x66, 7),
+    (0x67, 7),
+    (0x68, 7),
+    (0x69, 7),
+    (0x6a, 7),
+    (0x6b, 7),
+    (0x6c, 7),
+    (0x6d, 7),
+    (0x6e, 7),
+    (0x6f, 7),
+    (0x70, 7),
+    (0x71, 7),
+    (0x72, 7),
+    (0xfc, 8),
+    (0x73, 7),
+    (0xfd, 8),
+    (0x1ffb, 13),
+    (0x7fff0, 19),
+    (0x1ffc, 13),
+    (0x3ffc, 14),
+    (0x22, 6),
+    (0x7ffd, 15),
+    (0x3, 5),
+    (0x23, 6),
+    (0x4, 5),
+    (0x24, 6),
+    (0x5, 5),
+    (0x25, 6),
+    (0x26, 6),
+    (0x27, 6),
+    (0x6, 5),
+    (0x74, 7),
+    (0x75, 7),
+    (0x28, 6),
+    (0x29, 6),
+    (0x2a, 6),
+    (0x7, 5),
+    (0x2b, 6),
+    (0x76, 7),
+    (0x2c, 6),
+    (0x8, 5),
+    (0x9, 5),
+    (0x2d, 6),
+    (0x77, 7),
+    (0x78, 7),
+    (0x79, 7),
+    (0x7a, 7),
+    (0x7b, 7),
+    (0x7ffe, 15),
+    (0x7fc, 11),
+    (0x3ffd, 14),
+    (0x1ffd, 13),
+    (0xffffffc, 28),
+    (0xfffe6, 20),
+    (0x3fffd2, 22),
+    (0xfffe7, 20),
+    (0xfffe8, 20),
+    (0x3fffd3, 22),
+    (0x3fffd4, 22),
+    (0x3fffd5, 22),
+    (0x7fffd9, 23),
+    (0x3fffd6, 22),
+    (0x7fffda, 23),
+    (0x7fffdb, 23),
+    (0x7fffdc, 23),
+    (0x7fffdd, 23),
+    (0x7fffde, 23),
+    (0xffffeb, 24),
+    (0x7fffdf, 23),
+    (0xffffec, 24),
+    (0xffffed, 24),
+    (0x3fffd7, 22),
+    (0x7fffe0, 23),
+    (0xffffee, 24),
+    (0x7fffe1, 23),
+    (0x7fffe2, 23),
+    (0x7fffe3, 23),
+    (0x7fffe4, 23),
+    (0x1fffdc, 21),
+    (0x3fffd8, 22),
+    (0x7fffe5, 23),
+    (0x3fffd9, 22),
+    (0x7fffe6, 23),
+    (0x7fffe7, 23),
+    (0xffffef, 24),
+    (0x3fffda, 22),
+    (0x1fffdd, 21),
+    (0xfffe9, 20),
+    (0x3fffdb, 22),
+    (0x3fffdc, 22),
+    (0x7fffe8, 23),
+    (0x7fffe9, 23),
+    (0x1fffde, 21),
+    (0x7fffea, 23),
+    (0x3fffdd, 22),
+    (0x3fffde, 22),
+    (0xfffff0, 24),
+    (0x1fffdf, 21),
+    (0x3fffdf, 22),
+    (0x7fffeb, 23),
+    (0x7fffec, 23),
+    (0x1fffe0, 21),
+    (0x1fffe1, 21),
+    (0x3fffe0, 22),
+    (0x1fffe2, 21),
+    (0x7fffed, 23),
+    (0x3fffe1, 22),
+    (0x7fffee, 23),
+    (0x7fffef, 23),
+    (0xfffea, 20),
+    (0x3fffe2, 22),
+    (0x3fffe3, 22),
+    (0x3fffe4, 22),
+    (0x7ffff0, 23),
+    (0x3fffe5, 22),
+    (0x3fffe6, 22),
+    (0x7ffff1, 23),
+    (0x3ffffe0, 26),
+    (0x3ffffe1, 26),
+    (0xfffeb, 20),
+    (0x7fff1, 19),
+    (0x3fffe7, 22),
+    (0x7ffff2, 23),
+    (0x3fffe8, 22),
+    (0x1ffffec, 25),
+    (0x3ffffe2, 26),
+    (0x3ffffe3, 26),
+    (0x3ffffe4, 26),
+    (0x7ffffde, 27),
+    (0x7ffffdf, 27),
+    (0x3ffffe5, 26),
+    (0xfffff1, 24),
+    (0x1ffffed, 25),
+    (0x7fff2, 19),
+    (0x1fffe3, 21),
+    (0x3ffffe6, 26),
+    (0x7ffffe0, 27),
+    (0x7ffffe1, 27),
+    (0x3ffffe7, 26),
+    (0x7ffffe2, 27),
+    (0xfffff2, 24),
+    (0x1fffe4, 21),
+    (0x1fffe5, 21),
+    (0x3ffffe8, 26),
+    (0x3ffffe9, 26),
+    (0xffffffd, 28),
+    (0x7ffffe3, 27),
+    (0x7ffffe4, 27),
+    (0x7ffffe5, 27),
+    (0xfffec, 20),
+    (0xfffff3, 24),
+    (0xfffed, 20),
+    (0x1fffe6, 21),
+    (0x3fffe9, 22),
+    (0x1fffe7, 21),
+    (0x1fffe8, 21),
+    (0x7ffff3, 23),
+    (0x3fffea, 22),
+    (0x3fffeb, 22),
+    (0x1ffffee, 25),
+    (0x1ffffef, 25),
+    (0xfffff4, 24),
+    (0xfffff5, 24),
+    (0x3ffffea, 26),
+    (0x7ffff4, 23),
+    (0x3ffffeb, 26),
+    (0x7ffffe6, 27),
+    (0x3ffffec, 26),
+    (0x3ffffed, 26),
+    (0x7ffffe7, 27),
+    (0x7ffffe8, 27),
+    (0x7ffffe9, 27),
+    (0x7ffffea, 27),
+    (0x7ffffeb, 27),
+    (0xffffffe, 28),
+    (0x7ffffec, 27),
+    (0x7ffffed, 27),
+    (0x7ffffee, 27),
+    (0x7ffffef, 27),
+    (0x7fffff0, 27),
+    (0x3ffffee, 26),
+    (0x3fffffff, 30),
+];
+
+/// Headers whose values must never be added to a compression table.
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+/// The dynamic table shared by one direction of a connection.
+struct DynamicTable {
+    entries: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl DynamicTable {
+    fn new(max_size: usize) -> Self {
+        DynamicTable {
+            entries: VecDeque::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    fn entry_size(name: &str, value: &str) -> usize {
+        name.len() + value.len() + 32
+    }
+
+    fn insert(&mut self, name: String, value: String) {
+        let size = Self::entry_size(&name, &value);
+        if size > self.max_size {
+            // An entry larger than the table empties it (RFC 7541, Section 4.4)
+            self.entries.clear();
+            self.size = 0;
+            return;
+        }
+        self.size += size;
+        self.entries.push_front((name, value));
+        self.evict();
+    }
+
+    fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            match self.entries.pop_back() {
+                Some((name, value)) => self.size -= Self::entry_size(&name, &value),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Look up an entry by its combined static/dynamic index.
+fn lookup(table: &DynamicTable, index: usize) -> Result<(String, String), &'static str> {
+    if index == 0 {
+        return Err("HPACK index 0 is invalid");
+    }
+    if index <= STATIC_TABLE.len() {
+        let (name, value) = STATIC_TABLE[index - 1];
+        return Ok((name.to_string(), value.to_string()));
+    }
+    table
+        .entries
+        .get(index - STATIC_TABLE.len() - 1)
+        .cloned()
+        .ok_or("HPACK index out of range")
+}
+
+/// Encode an integer with an N-bit prefix, OR-ing `flags` into the first byte.
+pub fn encode_integer(value: usize, prefix_bits: u8, flags: u8, out: &mut Vec<u8>) {
+    let max_prefix = (1usize << prefix_bits) - 1;
+    if value < max_prefix {
+        out.push(flags | value as u8);
+        return;
+    }
+
+    out.push(flags | max_prefix as u8);
+    let mut remaining = value - max_prefix;
+    while remaining >= 128 {
+        out.push((remaining % 128 + 128) as u8);
+        remaining /= 128;
+    }
+    out.push(remaining as u8);
+}
+
+/// Decode an integer with an N-bit prefix starting at `*pos`, advancing `*pos`.
+pub fn decode_integer(buf: &[u8], pos: &mut usize, prefix_bits: u8) -> Result<usize, &'static str> {
+    let max_prefix = (1usize << prefix_bits) - 1;
+    let first = *buf.get(*pos).ok_or("Truncated HPACK integer")?;
+    *pos += 1;
+
+    let mut value = (first as usize) & max_prefix;
+    if value < max_prefix {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let byte = *buf.get(*pos).ok_or("Truncated HPACK integer")?;
+        *pos += 1;
+        if shift > 28 {
+            return Err("HPACK integer overflow");
+        }
+        value += ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Huffman-encode a byte string.
+pub fn huffman_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut bits: u64 = 0;
+    let mut bit_count = 0;
+
+    for &byte in data {
+        let (code, len) = HUFFMAN_CODES[byte as usize];
+        bits = (bits << len) | code as u64;
+        bit_count += len as u32;
+        while bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+
+    if bit_count > 0 {
+        // Pad with the most significant bits of EOS (all ones)
+        let pad = 8 - bit_count;
+        out.push(((bits << pad) as u8) | ((1u8 << pad) - 1));
+    }
+
+    out
+}
+
+/// Length in bytes of the Huffman encoding of `data`.
+fn huffman_encoded_len(data: &[u8]) -> usize {
+    let bits: usize = data
+        .iter()
+        .map(|&b| HUFFMAN_CODES[b as usize].1 as usize)
+        .sum();
+    bits.div_ceil(8)
+}
+
+/// Longest code in the Huffman table (EOS), in bits
+const MAX_CODE_LEN: usize = 30;
+
+/// Decoding table for the canonical Huffman code.
+///
+/// Codes of the same length are consecutive, so a code of `len` bits is valid when it
+/// lies within `count[len]` codes of `first[len]`, and decodes to
+/// `symbols[offset[len] + code - first[len]]`.
+struct HuffmanTable {
+    first: [u32; MAX_CODE_LEN + 1],
+    count: [u16; MAX_CODE_LEN + 1],
+    offset: [u16; MAX_CODE_LEN + 1],
+    symbols: [u16; 257],
+}
+
+const HUFFMAN_TABLE: HuffmanTable = huffman_table();
+
+/// Build the decoding table from the code table at compile time.
+const fn huffman_table() -> HuffmanTable {
+    let mut first = [u32::MAX; MAX_CODE_LEN + 1];
+    let mut count = [0u16; MAX_CODE_LEN + 1];
+    let mut symbol = 0;
+    while symbol < HUFFMAN_CODES.len() {
+        let (code, len) = HUFFMAN_CODES[symbol];
+        if code < first[len as usize] {
+            first[len as usize] = code;
+        }
+        count[len as usize] += 1;
+        symbol += 1;
+    }
+
+    let mut offset = [0u16; MAX_CODE_LEN + 1];
+    let mut len = 1;
+    while len <= MAX_CODE_LEN {
+        offset[len] = offset[len - 1] + count[len - 1];
+        len += 1;
+    }
+
+    let mut symbols = [u16::MAX; 257];
+    symbol = 0;
+    while symbol < HUFFMAN_CODES.len() {
+        let (code, len) = HUFFMAN_CODES[symbol];
+        let rank = code - first[len as usize];
+        let slot = offset[len as usize] as usize + rank as usize;
+        assert!(
+            rank < count[len as usize] as u32 && symbols[slot] == u16::MAX,
+            "Huffman codes of one length must be consecutive"
+        );
+        symbols[slot] = symbol as u16;
+        symbol += 1;
+    }
+
+    HuffmanTable {
+        first,
+        count,
+        offset,
+        symbols,
+    }
+}
+
+/// Decode a Huffman-encoded byte string.
+pub fn huffman_decode(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let table = &HUFFMAN_TABLE;
+    let mut out = Vec::with_capacity(data.len() * 8 / 5);
+    let mut code: u32 = 0;
+    let mut depth = 0;
+    let mut all_ones = true;
+
+    for &byte in data {
+        for i in (0..8).rev() {
+            let bit = (byte >> i) & 1;
+            all_ones &= bit == 1;
+            code = (code << 1) | bit as u32;
+            depth += 1;
+            if depth > MAX_CODE_LEN {
+                return Err("Invalid Huffman code");
+            }
+            let rank = code.wrapping_sub(table.first[depth]);
+            if rank < table.count[depth] as u32 {
+                let symbol = table.symbols[table.offset[depth] as usize + rank as usize];
+                if symbol == 256 {
+                    return Err("Huffman string contains EOS");
+                }
+                out.push(symbol as u8);
+                code = 0;
+                depth = 0;
+                all_ones = true;
+            }
+        }
+    }
+
+    // Remaining bits must be a prefix of EOS, at most 7 bits long
+    if depth > 7 || !all_ones {
+        return Err("Invalid Huffman padding");
+    }
+
+    Ok(out)
+}
+
+/// Encode a string literal, using Huffman coding when it is shorter.
+fn encode_string(value: &str, out: &mut Vec<u8>) {
+    let bytes = value.as_bytes();
+    let huffman_len = huffman_encoded_len(bytes);
+    if huffman_len < bytes.len() {
+        encode_integer(huffman_len, 7, 0x80, out);
+        out.extend_from_slice(&huffman_encode(bytes));
+    } else {
+        encode_integer(bytes.len(), 7, 0x00, out);
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Decode a string literal starting at `*pos`, advancing `*pos`.
+fn decode_string(buf: &[u8], pos: &mut usize) -> Result<String, &'static str> {
+    let huffman = buf.get(*pos).ok_or("Truncated HPACK string")? & 0x80 != 0;
+    let len = decode_integer(buf, pos, 7)?;
+    let end = pos.checked_add(len).ok_or("Truncated HPACK string")?;
+    let raw = buf.get(*pos..end).ok_or("Truncated HPACK string")?;
+    *pos = end;
+
+    let bytes = if huffman {
+        huffman_decode(raw)?
+    } else {
+        raw.to_vec()
+    };
+    String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 in HPACK string")
+}
+
+/// HPACK encoder holding the request-direction dynamic table.
+pub struct Encoder {
+    table: DynamicTable,
+    pending_size_update: Option<usize>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder {
+            table: DynamicTable::new(DEFAULT_TABLE_SIZE),
+            pending_size_update: None,
+        }
+    }
+
+    /// Apply the peer's SETTINGS_HEADER_TABLE_SIZE.
+    ///
+    /// The table never grows beyond the default; a change is signalled at the
+    /// start of the next header block.
+    pub fn set_max_table_size(&mut self, size: usize) {
+        let size = size.min(DEFAULT_TABLE_SIZE);
+        if size != self.table.max_size {
+            self.table.set_max_size(size);
+            self.pending_size_update = Some(size);
+        }
+    }
+
+    /// Find a full (name, value) match or a name-only match in both tables.
+    fn find(&self, name: &str, value: &str) -> (Option<usize>, Option<usize>) {
+        let mut name_match = None;
+        let dynamic = self
+            .table
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, (n, v))| (i + STATIC_TABLE.len() + 1, n.as_str(), v.as_str()));
+        let all = STATIC_TABLE
+            .iter()
+            .enumerate()
+            .map(|(i, (n, v))| (i + 1, *n, *v))
+            .chain(dynamic);
+
+        for (index, n, v) in all {
+            if n == name {
+                if v == value {
+                    return (Some(index), Some(index));
+                }
+                name_match.get_or_insert(index);
+            }
+        }
+        (None, name_match)
+    }
+
+    /// Encode a header list into a header block fragment.
+    ///
+    /// Names must already be lowercase, as HTTP/2 requires.
+    pub fn encode(&mut self, headers: &[(String, String)]) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        if let Some(size) = self.pending_size_update.take() {
+            encode_integer(size, 5, 0x20, &mut out);
+        }
+
+        for (name, value) in headers {
+            let (full, name_index) = self.find(name, value);
+            if let Some(index) = full {
+                encode_integer(index, 7, 0x80, &mut out);
+                continue;
+            }
+
+            if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                // Literal never indexed
+                encode_integer(name_index.unwrap_or(0), 4, 0x10, &mut out);
+                if name_index.is_none() {
+                    encode_string(name, &mut out);
+                }
+                encode_string(value, &mut out);
+                continue;
+            }
+
+            // Literal with incremental indexing
+            encode_integer(name_index.unwrap_or(0), 6, 0x40, &mut out);
+            if name_index.is_none() {
+                encode_string(name, &mut out);
+            }
+            encode_string(value, &mut out);
+            self.table.insert(name.clone(), value.clone());
+        }
+
+        out
+    }
+}
+
+/// HPACK decoder holding the response-direction dynamic table.
+pub struct Decoder {
+    table: DynamicTable,
+    max_size: usize,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder {
+            table: DynamicTable::new(DEFAULT_TABLE_SIZE),
+            max_size: DEFAULT_TABLE_SIZE,
+        }
+    }
+
+    /// Decode a complete header block into (name, value) pairs.
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, &'static str> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+
+        while pos < block.len() {
+            let byte = block[pos];
+            if byte & 0x80 != 0 {
+                // Indexed header field
+                let index = decode_integer(block, &mut pos, 7)?;
+                headers.push(lookup(&self.table, index)?);
+            } else if byte & 0xc0 == 0x40 {
+                // Literal with incremental indexing
+                let (name, value) = self.decode_literal(block, &mut pos, 6)?;
+                self.table.insert(name.clone(), value.clone());
+                headers.push((name, value));
+            } else if byte & 0xe0 == 0x20 {
+                // Dynamic table size update
+                let size = decode_integer(block, &mut pos, 5)?;
+                if size > self.max_size {
+                    return Err("HPACK table size update exceeds limit");
+                }
+                self.table.set_max_size(size);
+            } else {
+                // Literal without indexing (0000) or never indexed (0001)
+                headers.push(self.decode_literal(block, &mut pos, 4)?);
+            }
+        }
+
+        Ok(headers)
+    }
+
+    fn decode_literal(
+        &self,
+        block: &[u8],
+        pos: &mut usize,
+        prefix_bits: u8,
+    ) -> Result<(String, String), &'static str> {
+        let name_index = decode_integer(block, pos, prefix_bits)?;
+        let name = if name_index == 0 {
+            decode_string(block, pos)?
+        } else {
+            lookup(&self.table, name_index)?.0
+        };
+        let value = decode_string(block, pos)?;
+        Ok((name, value))
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt;
+
+use crate::http::response;
+
+/// Minimum HSTS max-age considered adequate (180 days).
+const HSTS_MIN_MAX_AGE: u64 = 15_552_000;
+
+/// Security headers that should appear at most once.
+const SINGLETON_HEADERS: [&str; 5] = [
+    "strict-transport-security",
+    "content-security-policy",
+    "x-content-type-options",
+    "x-frame-options",
+    "referrer-policy",
+];
+
+/// Outcome of a single audit check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Pass => write!(f, "PASS"),
+            Level::Warn => write!(f, "WARN"),
+            Level::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// One line of the audit report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub level: Level,
+    pub item: String,
+    pub detail: String,
+}
+
+impl Finding {
+    fn new(level: Level, item: &str, detail: impl Into<String>) -> Self {
+        Finding {
+            level,
+            item: item.to_string(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Audit the security-relevant headers of a response.
+///
+/// # Arguments
+///
+/// * `headers` - The response header block, including the status line.
+/// * `is_https` - Whether the response was received over TLS.
+///
+/// # Returns
+///
+/// * `Vec<Finding>` - One finding per check, in report order.
+pub fn audit(headers: &[u8], is_https: bool) -> Vec<Finding> {
+    let fields: Vec<(String, String)> = response::header_fields(headers)
+        .map(|(name, value)| {
+            (
+                String::from_utf8_lossy(name).to_lowercase(),
+                String::from_utf8_lossy(value).into_owned(),
+            )
+        })
+        .collect();
+    let get = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut findings = Vec::new();
+
+    // Strict-Transport-Security
+    let item = "Strict-Transport-Security";
+    findings.push(match (is_https, get("strict-transport-security")) {
+        (false, _) => Finding::new(Level::Warn, item, "not applicable over plain HTTP"),
+        (true, None) => Finding::new(Level::Fail, item, "missing"),
+        (true, Some(value)) => {
+            let max_age = value
+                .split(';')
+                .filter_map(|d| d.trim().split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("max-age"))
+                .and_then(|(_, v)| v.trim().trim_matches('"').parse::<u64>().ok());
+            match max_age {
+                Some(age) if age >= HSTS_MIN_MAX_AGE => Finding::new(Level::Pass, item, value),
+                Some(age) => Finding::new(
+                    Level::Warn,
+                    item,
+                    format!("max-age={} is shorter than 180 days", age),
+                ),
+                None => Finding::new(Level::Fail, item, "missing max-age directive"),
+            }
+        }
+    });
+
+    // Content-Security-Policy
+    let item = "Content-Security-Policy";
+    let csp = get("content-security-policy");
+    findings.push(match csp {
+        Some(value) => {
+            let weak: Vec<&str> = ["'unsafe-inline'", "'unsafe-eval'"]
+                .into_iter()
+                .filter(|keyword| value.contains(keyword))
+                .collect();
+            if weak.is_empty() {
+                Finding::new(Level::Pass, item, value)
+            } else {
+                Finding::new(Level::Warn, item, format!("allows {}", weak.join(", ")))
+            }
+        }
+        None if get("content-security-policy-report-only").is_some() => {
+            Finding::new(Level::Warn, item, "report-only, not enforced")
+        }
+        None => Finding::new(Level::Fail, item, "missing"),
+    });
+
+    // X-Content-Type-Options
+    let item = "X-Content-Type-Options";
+    findings.push(match get("x-content-type-options") {
+        Some(value) if value.trim().eq_ignore_ascii_case("nosniff") => {
+            Finding::new(Level::Pass, item, value)
+        }
+        Some(value) => Finding::new(Level::Warn, item, format!("unexpected value '{}'", value)),
+        None => Finding::new(Level::Fail, item, "missing"),
+    });
+
+    // Clickjacking protection
+    let item = "X-Frame-Options";
+    let frame_ancestors = csp.is_some_and(|value| value.contains("frame-ancestors"));
+    findings.push(match get("x-frame-options") {
+        Some(value)
+            if value.trim().eq_ignore_ascii_case("deny")
+                || value.trim().eq_ignore_ascii_case("sameorigin") =>
+        {
+            Finding::new(Level::Pass, item, value)
+        }
+        Some(value) => Finding::new(Level::Warn, item, format!("unexpected value '{}'", value)),
+        None if frame_ancestors => {
+            Finding::new(Level::Pass, item, "covered by CSP frame-ancestors")
+        }
+        None => Finding::new(Level::Warn, item, "missing"),
+    });
+
+    // Referrer-Policy
+    let item = "Referrer-Policy";
+    findings.push(match get("referrer-policy") {
+        Some(value) if value.contains("unsafe-url") => {
+            Finding::new(Level::Warn, item, "unsafe-url leaks full URLs")
+        }
+        Some(value) => Finding::new(Level::Pass, item, value),
+        None => Finding::new(Level::Warn, item, "missing"),
+    });
+
+    // Version disclosure
+    for name in ["server", "x-powered-by"] {
+        if let Some(value) = get(name) {
+            if value.chars().any(|c| c.is_ascii_digit()) {
+                let item = if name == "server" {
+                    "Server"
+                } else {
+                    "X-Powered-By"
+                };
+                findings.push(Finding::new(
+                    Level::Warn,
+                    item,
+                    format!("discloses version '{}'", value),
+                ));
+            }
+        }
+    }
+
+    // Duplicated security headers
+    for name in SINGLETON_HEADERS {
+        let count = fields.iter().filter(|(field, _)| field == name).count();
+        if count > 1 {
+            findings.push(Finding::new(
+                Level::Warn,
+                "Duplicate header",
+                format!("{} appears {} times", name, count),
+            ));
+        }
+    }
+
+    // Cookie flags
+    for (_, cookie) in fields.iter().filter(|(field, _)| field == "set-cookie") {
+        findings.push(audit_cookie(cookie, is_https));
+    }
+
+    findings
+}
+
+/// Check the Secure, HttpOnly, and SameSite attributes of a Set-Cookie value.
+fn audit_cookie(cookie: &str, is_https: bool) -> Finding {
+    let mut parts = cookie.split(';');
+    let name = parts
+        .next()
+        .and_then(|pair| pair.split_once('=').map(|(name, _)| name.trim()))
+        .unwrap_or("")
+        .to_string();
+    let attributes: Vec<String> = parts
+        .map(|attr| attr.trim().split('=').next().unwrap_or("").to_lowercase())
+        .collect();
+    let has = |attr: &str| attributes.iter().any(|a| a == attr);
+
+    let item = format!("Set-Cookie {}", name);
+    let mut missing = Vec::new();
+    let mut level = Level::Pass;
+
+    if !has("secure") {
+        missing.push("Secure");
+        level = if is_https { Level::Fail } else { Level::Warn };
+    }
+    if !has("httponly") {
+        missing.push("HttpOnly");
+        level = level.max(Level::Warn);
+    }
+    if !has("samesite") {
+        missing.push("SameSite");
+        level = level.max(Level::Warn);
+    }
+
+    if missing.is_empty() {
+        Finding::new(Level::Pass, &item, "Secure; HttpOnly; SameSite")
+    } else {
+        Finding::new(level, &item, format!("missing {}", missing.join(", ")))
+    }
+}
+
+/// Print the report and summary line to stdout.
+pub fn print_report(findings: &[Finding]) {
+    println!("Security header audit:");
+    for finding in findings {
+        println!("  [{}] {}: {}", finding.level, finding.item, finding.detail);
+    }
+
+    let count = |level| findings.iter().filter(|f| f.level == level).count();
+    println!(
+        "Summary: {} passed, {} warnings, {} failed",
+        count(Level::Pass),
+        count(Level::Warn),
+        count(Level::Fail)
+    );
+}
+
+/// Exit code summarizing the audit: 0 all pass, 1 warnings only, 2 any failure.
+pub fn exit_code(findings: &[Finding]) -> i32 {
+    match findings.iter().map(|f| f.level).max() {
+        Some(Level::Fail) => 2,
+        Some(Level::Warn) => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::args::Args;
+use crate::http::{proxy, response};
+
+/// Find a Basic challenge in `header` (e.g. `WWW-Authenticate`) and return its realm.
+///
+/// # Returns
+///
+/// * `Option<String>` - The realm (empty if none was given), or None if there is no Basic challenge.
+pub fn basic_challenge(headers: &[u8], header: &str) -> Option<String> {
+    response::header_fields(headers)
+        .filter(|(name, _)| name.eq_ignore_ascii_case(header.as_bytes()))
+        .map(|(_, value)| String::from_utf8_lossy(value).into_owned())
+        .find_map(|value| {
+            let (scheme, params) = value.split_once(' ').unwrap_or((&value, ""));
+            if !scheme.eq_ignore_ascii_case("basic") {
+                return None;
+            }
+            let realm = params
+                .split(',')
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("realm"))
+                .map(|(_, realm)| realm.trim().trim_matches('"').to_string());
+            Some(realm.unwrap_or_default())
+        })
+}
+
+/// Ask for a username and password on the terminal.
+///
+/// The password is read with echo disabled where `stty` is available.
+fn prompt_credentials(target: &str, realm: &str) -> Result<String, String> {
+    let mut stderr = io::stderr();
+    let stdin = io::stdin();
+    let read_line = || -> Result<String, String> {
+        let mut line = String::new();
+        stdin
+            .lock()
+            .read_line(&mut line)
+            .map_err(|err| format!("Failed to read credentials: {}", err))?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    if realm.is_empty() {
+        eprint!("Username for {}: ", target);
+    } else {
+        eprint!("Username for {} (realm '{}'): ", target, realm);
+    }
+    let _ = stderr.flush();
+    let username = read_line()?;
+
+    eprint!("Password: ");
+    let _ = stderr.flush();
+    set_echo(false);
+    let password = read_line();
+    set_echo(true);
+    eprintln!();
+
+    Ok(format!("{}:{}", username, password?))
+}
+
+/// Turn terminal echo on or off.
+#[cfg(unix)]
+fn set_echo(enabled: bool) {
+    let _ = std::process::Command::new("stty")
+        .arg(if enabled { "echo" } else { "-echo" })
+        .stdin(std::process::Stdio::inherit())
+        .status();
+}
+
+/// Turn terminal echo on or off.
+#[cfg(not(unix))]
+fn set_echo(_enabled: bool) {}
+
+/// Prompt for credentials after a Basic 401 or 407 challenge when `--prompt-auth` is set.
+///
+/// Only applies in an interactive terminal and when no credentials were supplied
+/// for the challenged party. The returned `Args` carry the credentials and have
+/// prompting disabled, so the request is retried at most once.
+///
+/// # Arguments
+///
+/// * `status` - The response status code.
+/// * `headers` - The response header block.
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+///
+/// # Returns
+///
+/// * `Result<Option<Args>, String>` - Arguments for the retry, None if no retry applies, or an error message if reading the credentials failed.
+pub fn prompt_retry(status: u16, headers: &[u8], args: &Args) -> Result<Option<Args>, String> {
+    if !args.prompt_auth || !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Ok(None);
+    }
+
+    let mut retry = args.clone();
+    retry.prompt_auth = false;
+
+    match status {
+        401 if args.user.is_none() && args.oauth2_bearer.is_none() => {
+            let realm = match basic_challenge(headers, "www-authenticate") {
+                Some(realm) => realm,
+                None => return Ok(None),
+            };
+            retry.user = Some(prompt_credentials(&args.url, &realm)?);
+        }
+        407 if args.proxy_user.is_none() => {
+            let realm = match basic_challenge(headers, "proxy-authenticate") {
+                Some(realm) => realm,
+                None => return Ok(None),
+            };
+            let proxy = proxy::for_url(args).unwrap_or("proxy");
+            retry.proxy_user = Some(prompt_credentials(proxy, &realm)?);
+        }
+        _ => return Ok(None),
+    }
+
+    Ok(Some(retry))
+}
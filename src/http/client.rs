@@ -1,377 +1,642 @@
-use native_tls::TlsConnector;
-use std::io::{ErrorKind, Read, Write};
-use std::net::{TcpStream, ToSocketAddrs};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::args::Args;
-use crate::http::response;
+use crate::cookies::CookieJar;
+use crate::http::pool::{self, Connection};
+use crate::http::reader::ResponseReader;
+use crate::http::timeout::{self, Phase};
+use crate::http::transport::Transport;
+use crate::http::{auth, guard, proxy, redirect, request, resolve, response, retry, wire};
+#[cfg(feature = "http2")]
+use crate::http2;
+use crate::output::error;
+use crate::output::log::{self, Logger};
+use crate::throttle;
+use crate::tls;
+use crate::writeout::{self, Event};
+use rurl::method::Method;
+use rurl::status::StatusCode;
+use rurl::url::Url;
 
 /// Set up TCP stream with appropriate timeouts
-pub fn setup_tcp_stream(host: &str, port: u16) -> Result<TcpStream, String> {
-    let addr = format!("{}:{}", host, port);
-    let addrs = match addr.to_socket_addrs() {
-        Ok(addrs) => addrs,
-        Err(err) => {
-            return Err(format!("DNS resolution error: {}", err));
-        }
+pub fn setup_tcp_stream(host: &str, port: u16, args: &Args) -> Result<TcpStream, String> {
+    let addrs_vec = resolve::resolve(host, port, args)?;
+    connect_addrs(&addrs_vec, host, port, args)
+}
+
+/// Open the connection for a request: directly, through the `-x/--proxy` proxy, or
+/// over the `--unix-socket` socket.
+///
+/// Through an HTTP proxy, HTTPS targets are reached through a CONNECT tunnel and
+/// plain HTTP requests are sent to the proxy itself in absolute form; a SOCKS5 proxy
+/// tunnels both. Over a Unix socket the URL's host is only used for the Host header
+/// and TLS.
+///
+/// # Arguments
+///
+/// * `host` - The target host.
+/// * `port` - The target port.
+/// * `is_https` - Whether the target uses TLS.
+/// * `args` - A reference to an `Args` struct containing the connection options.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Transport>, String>` - The connected stream if successful, or an error message if unsuccessful.
+pub fn open_connection(
+    host: &str,
+    port: u16,
+    is_https: bool,
+    args: &Args,
+) -> Result<Box<dyn Transport>, String> {
+    #[cfg(unix)]
+    if let Some(path) = &args.unix_socket {
+        let stream = crate::http::transport::connect_unix(path, args)?;
+        timeout::set_io_timeouts(&stream)?;
+        return Ok(Box::new(stream));
+    }
+
+    let proxy = match proxy::for_url(args) {
+        Some(proxy) => proxy,
+        None => return Ok(Box::new(setup_tcp_stream(host, port, args)?)),
     };
 
-    let addrs_vec: Vec<_> = addrs.collect();
-    if addrs_vec.is_empty() {
-        return Err(format!("No addresses resolved for {}:{}", host, port));
+    let (kind, proxy_host, proxy_port) = proxy::parse(proxy)?;
+    // Only a SOCKS5h proxy keeps an onion name away from everything but Tor
+    if kind != proxy::Kind::Socks5h && resolve::is_onion(host) {
+        return Err(resolve::onion_error(host));
+    }
+    log::verbose(
+        args,
+        format_args!("Connecting via proxy {}:{}", proxy_host, proxy_port),
+    );
+    let mut stream = setup_tcp_stream(&proxy_host, proxy_port, args)?;
+
+    if kind != proxy::Kind::Http {
+        proxy::socks5_connect(&mut stream, kind, host, port, args)?;
+    } else if is_https {
+        let (status, headers) = proxy::connect_tunnel(&mut stream, host, port, args)?;
+        if !(200..300).contains(&status) {
+            // The proxy may close the connection after a 407, so retry on a fresh one
+            if let Some(retry_args) = auth::prompt_retry(status, &headers, args)? {
+                return open_connection(host, port, is_https, &retry_args);
+            }
+            return Err(format!("Proxy CONNECT failed: HTTP {}", status));
+        }
+    } else if args.http2 {
+        return Err("HTTP/2 is not supported through a plain HTTP proxy".to_string());
+    }
+
+    Ok(Box::new(stream))
+}
+
+/// How long an attempt may be pending before the next address is tried alongside it
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Connect to the first reachable of the already resolved addresses.
+///
+/// Addresses are tried with IPv6 and IPv4 interleaved, starting with the family
+/// listed first (Happy Eyeballs, RFC 8305), or in the order of `--ip-strategy` when
+/// one is given. The next attempt starts as soon as one
+/// fails or after `ATTEMPT_DELAY`, and the first connection established wins. All
+/// attempts share the `--connect-timeout` budget.
+fn connect_addrs(
+    addrs_vec: &[SocketAddr],
+    host: &str,
+    port: u16,
+    args: &Args,
+) -> Result<TcpStream, String> {
+    error::enter(error::Phase::Connect);
+    let connect_timeout = args.connect_timeout;
+    let deadline = Instant::now() + connect_timeout;
+    let mut attempts = match args.ip_strategy {
+        Some(strategy) => resolve::order(addrs_vec, strategy),
+        None => interleave_families(addrs_vec),
     }
+    .into_iter();
+    let (sender, receiver) = mpsc::channel();
+    let mut pending = 0;
+    let mut last_error = None;
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            let target = format!("{}:{}", host, port);
+            return Err(timeout::error(Phase::Connect, connect_timeout, &target));
+        }
+        // Each pass follows a failure or an elapsed delay, so start the next attempt
+        if let Some(addr) = attempts.next() {
+            log::verbose(args, format_args!("Trying {}...", addr));
+            let sender = sender.clone();
+            let budget = deadline - now;
+            // A losing attempt finishes in the background; its socket is dropped
+            thread::spawn(move || {
+                let _ = sender.send((addr, TcpStream::connect_timeout(&addr, budget)));
+            });
+            pending += 1;
+        }
+        if pending == 0 {
+            break;
+        }
 
-    let stream = match TcpStream::connect_timeout(&addrs_vec[0], Duration::from_secs(10)) {
-        Ok(stream) => {
-            // Set read/write timeouts
-            if let Err(err) = stream.set_read_timeout(Some(Duration::from_secs(30))) {
-                return Err(format!("Failed to set read timeout: {}", err));
+        let wait = if attempts.len() > 0 {
+            ATTEMPT_DELAY.min(deadline - now)
+        } else {
+            deadline - now
+        };
+        match receiver.recv_timeout(wait) {
+            Ok((addr, Ok(stream))) => {
+                writeout::mark(Event::Connect);
+                writeout::record_remote(addr);
+                // Set read/write timeouts
+                timeout::set_io_timeouts(&stream)?;
+                return Ok(stream);
             }
-            if let Err(err) = stream.set_write_timeout(Some(Duration::from_secs(10))) {
-                return Err(format!("Failed to set write timeout: {}", err));
+            Ok((addr, Err(err))) => {
+                log::verbose(args, format_args!("Connection to {} failed: {}", addr, err));
+                error::record_os_error(&err);
+                pending -= 1;
+                last_error = Some(err);
             }
-            stream
+            Err(_) => {}
         }
-        Err(err) => {
-            return Err(format!("Connection error: {} ({}:{})", err, host, port));
+    }
+
+    match last_error {
+        Some(err) if timeout::is_timeout(&err) => {
+            let target = format!("{}:{}", host, port);
+            Err(timeout::error(Phase::Connect, connect_timeout, &target))
         }
-    };
+        Some(err) => Err(format!("Connection error: {} ({}:{})", err, host, port)),
+        None => Err(format!("No addresses resolved for {}:{}", host, port)),
+    }
+}
 
-    Ok(stream)
+/// Order addresses so the two families alternate, starting with the family of the
+/// first address and otherwise keeping the resolver's order.
+fn interleave_families(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return Vec::new();
+    };
+    let (preferred, other): (Vec<SocketAddr>, Vec<SocketAddr>) = addrs
+        .iter()
+        .partition(|addr| addr.is_ipv4() == first.is_ipv4());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    let mut ordered = Vec::with_capacity(addrs.len());
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
 }
 
 /// Check if a status code is a redirect
-fn is_redirect_status(status: u16) -> bool {
-    matches!(status, 301 | 302 | 303 | 307 | 308)
+fn is_redirect_status(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 300 | 301 | 302 | 303 | 307 | 308)
 }
 
 /// Handle redirect logic (shared between HTTP and HTTPS)
 fn handle_redirect(
     location: &str,
     args: &Args,
+    jar: &mut CookieJar,
+    status: StatusCode,
     redirect_count: usize,
 ) -> Result<(), String> {
-    const MAX_REDIRECTS: usize = 10;
-    
-    if redirect_count >= MAX_REDIRECTS {
+    if redirect_count >= args.max_redirs {
         return Err("Too many redirects".to_string());
     }
 
-    if args.verbose && !args.silent {
-        println!("Following redirect to: {}", location);
-    }
+    // Resolve a relative Location such as `/login` or `../other` against the current URL
+    // (RFC 3986, Section 5); an unsupported absolute one is left for `check_hop` to report
+    let resolved = Url::parse(&args.url).and_then(|base| base.join(location));
+    let location = match &resolved {
+        Ok(url) => url.to_string(),
+        Err(_) => location.to_string(),
+    };
+    let location = location.as_str();
 
-    // Parse the new location
-    use crate::http::url;
-    let (new_host, new_port, _, new_is_https) = url::parse(location)?;
-    
-    // Build new request with updated URL
-    let mut new_args = args.clone();
-    new_args.url = location.to_string();
-    let new_request_bytes = crate::http::request::build(&new_args)
-        .map_err(|e| e.to_string())?;
-
-    // Setup new TCP stream
-    let new_stream = setup_tcp_stream(&new_host, new_port)?;
+    log::verbose(args, format_args!("Following redirect to: {}", location));
 
-    // Follow redirect
-    if new_is_https {
-        handle_https_connection_impl(new_stream, &new_host, &new_request_bytes, &new_args, redirect_count + 1)
-    } else {
-        handle_http_connection_impl(new_stream, &new_host, &new_request_bytes, &new_args, redirect_count + 1)
+    // Validate the hop before touching the network
+    redirect::check_hop(location, args)?;
+
+    // Parse the new location
+    let new_url = Url::parse(location)?;
+    let (new_host, new_port, new_is_https) =
+        (new_url.host_str(), new_url.port(), new_url.is_https());
+
+    if !new_is_https && args.url.starts_with("https://") && !args.silent {
+        eprintln!(
+            "Warning: redirect downgrades from HTTPS to plain HTTP: {}",
+            location
+        );
     }
-}
 
-/// Read HTTP response from any type of stream that implements Read
-pub fn read_http_response<T: Read>(stream: &mut T, verbose: bool) -> Result<Vec<u8>, String> {
-    // Read response with a maximum size to prevent excessive memory usage
-    let mut response = Vec::with_capacity(1024 * 1024); // Start with 1MB capacity
-    let mut buffer = [0u8; 8192]; // 8KB buffer for faster reading
-    let mut total_read = 0;
-    const MAX_SIZE: usize = 10 * 1024 * 1024; // 10 MB max response
-    let mut attempts = 0;
-    const MAX_ATTEMPTS: usize = 50; // Limit attempts to avoid infinite loops
-
-    // Read initial response headers
-    while attempts < MAX_ATTEMPTS {
-        match stream.read(&mut buffer) {
-            Ok(0) => {
-                if attempts > 0 {
-                    // End of stream after reading some data
-                    break;
-                }
-                if verbose {
-                    println!("No data received, retrying...");
-                }
-                thread::sleep(Duration::from_millis(100));
-                attempts += 1;
-                continue;
-            }
-            Ok(n) => {
-                attempts = 0; // Reset attempts counter on successful read
-                total_read += n;
-                response.extend_from_slice(&buffer[..n]);
-
-                // Try to find the end of headers to parse Content-Length
-                if let Some(header_end) =
-                    response.windows(4).position(|window| window == b"\r\n\r\n")
-                {
-                    let content_length = response::get_content_length(&response[..header_end + 4]);
-
-                    // If Content-Length is present, use it to determine when to stop
-                    if let Some(length) = content_length {
-                        if verbose {
-                            println!("Response Content-Length: {} bytes", length);
-                        }
-
-                        // Calculate the total expected size
-                        let expected_size = header_end + 4 + length;
-
-                        // If we've read at least that much, we're done
-                        if response.len() >= expected_size {
-                            if verbose {
-                                println!("Response complete based on Content-Length");
-                            }
-                            break;
-                        }
-                    } else if response::is_chunked_transfer(&response[..header_end + 4]) {
-                        // For chunked responses, look for the ending pattern 0\r\n\r\n
-                        if response.windows(5).any(|window| window == b"0\r\n\r\n") {
-                            if verbose {
-                                println!("Chunked response complete");
-                            }
-                            break;
-                        }
-                    }
-                    // If no content-length and not chunked, rely on connection close
-                }
-
-                if total_read > MAX_SIZE {
-                    return Err(format!(
-                        "Response too large, truncating at {} bytes",
-                        MAX_SIZE
-                    ));
-                }
-            }
-            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
-                // On macOS, non-blocking read can return EAGAIN (Resource temporarily unavailable)
-                if !response.is_empty() {
-                    // We have some data already, check if we might be done
-                    attempts += 1;
-                    if attempts >= 5 {
-                        if verbose {
-                            println!(
-                                "No more data after {} attempts, considering response complete",
-                                attempts
-                            );
-                        }
-                        break;
-                    }
-                }
-                // Just retry after a short sleep
-                thread::sleep(Duration::from_millis(100));
-                continue;
-            }
-            Err(err) => {
-                if !response.is_empty() {
-                    if verbose {
-                        println!("Processing partial response of {} bytes", response.len());
-                    }
-                    break;
-                }
-                return Err(format!("Read error: {}", err));
-            }
+    // Build the request for the new URL, rewriting the method and credentials as needed,
+    // and check what it still carries
+    let new_args = redirect::next_request(args, status, location);
+    guard::check(location, new_is_https, &new_args)?;
+    let new_request_bytes =
+        crate::http::request::build(&new_args, jar).map_err(|e| e.to_string())?;
+
+    // Resolve and re-check the addresses, then connect to exactly those
+    let new_stream = if proxy::for_url(&new_args).is_some() || args.unix_socket.is_some() {
+        // The proxy or socket reaches the target, so only resolve locally to enforce the private-address guard
+        if args.redirect_deny_private {
+            let new_addrs = resolve::resolve(&new_host, new_port, &new_args)?;
+            redirect::check_addrs(location, &new_addrs, args)?;
         }
-    }
+        open_connection(&new_host, new_port, new_is_https, &new_args)?
+    } else {
+        let new_addrs = resolve::resolve(&new_host, new_port, &new_args)?;
+        redirect::check_addrs(location, &new_addrs, args)?;
+
+        // Stay on the connection of an earlier hop to the same origin
+        if let Some(connection) = reuse_connection(&new_args) {
+            return exchange(
+                connection,
+                &new_request_bytes,
+                &new_args,
+                jar,
+                redirect_count + 1,
+                true,
+            );
+        }
+        Box::new(connect_addrs(&new_addrs, &new_host, new_port, args)?)
+    };
 
-    if attempts >= MAX_ATTEMPTS && response.is_empty() {
-        return Err("No response received after maximum attempts".to_string());
-    }
+    // Follow redirect
+    handle_connection(
+        new_stream,
+        &new_host,
+        new_is_https,
+        &new_request_bytes,
+        &new_args,
+        jar,
+        redirect_count + 1,
+    )
+}
 
-    if verbose {
-        println!("Received {} bytes", response.len());
+/// Send a request, retrying connection errors, timeouts, and 5xx/429 responses as `--retry` allows.
+///
+/// # Arguments
+///
+/// * `host` - The target host.
+/// * `port` - The target port.
+/// * `is_https` - Whether the target uses TLS.
+/// * `request_bytes` - The serialized request.
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar, updated from the responses.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once the response was handled, or an error message from the last attempt.
+pub fn send(
+    host: &str,
+    port: u16,
+    is_https: bool,
+    request_bytes: &[u8],
+    args: &Args,
+    jar: &mut CookieJar,
+) -> Result<(), String> {
+    if let Some(connection) = reuse_connection(args) {
+        return exchange(connection, request_bytes, args, jar, 0, true);
     }
+    let stream = match open_connection(host, port, is_https, args) {
+        Ok(stream) => stream,
+        Err(err) => return retry_after_error(err, args, jar, 0),
+    };
 
-    Ok(response)
+    handle_connection(stream, host, is_https, request_bytes, args, jar, 0)
 }
 
-/// Get the TLS protocol version from the specified string
-fn get_tls_protocol_version(version: &str) -> Option<native_tls::Protocol> {
-    match version.trim() {
-        "1.0" => Some(native_tls::Protocol::Tlsv10),
-        "1.1" => Some(native_tls::Protocol::Tlsv11),
-        "1.2" => Some(native_tls::Protocol::Tlsv12),
-        // TLS 1.3 is not explicitly supported in native-tls yet, but we can try to leave it to the system
-        "1.3" => None,
-        _ => None,
-    }
+/// Retry after a failure that happened before a response arrived, or return the error
+fn retry_after_error(
+    err: String,
+    args: &Args,
+    jar: &mut CookieJar,
+    redirect_count: usize,
+) -> Result<(), String> {
+    let Some((retry_args, delay)) = retry::next(args, None) else {
+        return Err(err);
+    };
+    retry::announce(&err, delay, &retry_args);
+    retry_args.clock.sleep(delay);
+    resend(&retry_args, jar, redirect_count)
 }
 
-/// Get the default minimum TLS protocol version for the current OS
-fn get_default_tls_protocol() -> Option<native_tls::Protocol> {
-    // Different OS versions have different defaults/support for TLS versions
-    // Here we're making conservative choices
-    #[cfg(target_os = "macos")]
-    {
-        // macOS typically has good support for recent TLS versions
-        Some(native_tls::Protocol::Tlsv12)
-    }
+/// Send the request described by `args` again, on an idle connection if one is available
+fn resend(args: &Args, jar: &mut CookieJar, redirect_count: usize) -> Result<(), String> {
+    send_request(args, jar, redirect_count, true)
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        // Windows support depends a lot on the version, default to 1.2 for security
-        Some(native_tls::Protocol::Tlsv12)
+/// Send the request described by `args`, reusing an idle connection only if `allow_reuse` is set
+fn send_request(
+    args: &Args,
+    jar: &mut CookieJar,
+    redirect_count: usize,
+    allow_reuse: bool,
+) -> Result<(), String> {
+    let url = Url::parse(&args.url)?;
+    let (host, port, is_https) = (url.host_str(), url.port(), url.is_https());
+    let request_bytes = crate::http::request::build(args, jar)?;
+    if let Some(connection) = reuse_connection(args).filter(|_| allow_reuse) {
+        return exchange(connection, &request_bytes, args, jar, redirect_count, true);
     }
+    let stream = match open_connection(&host, port, is_https, args) {
+        Ok(stream) => stream,
+        Err(err) => return retry_after_error(err, args, jar, redirect_count),
+    };
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux typically supports recent versions
-        Some(native_tls::Protocol::Tlsv12)
-    }
+    handle_connection(
+        stream,
+        &host,
+        is_https,
+        &request_bytes,
+        args,
+        jar,
+        redirect_count,
+    )
+}
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+/// The pool key for the request in `args`, or None if its connection is not shared.
+///
+/// Proxied, Unix socket, and HTTP/2 connections are never pooled.
+fn pool_key(args: &Args) -> Option<pool::Key> {
+    // A connection that answered CONNECT has become a tunnel
+    if proxy::for_url(args).is_some()
+        || args.unix_socket.is_some()
+        || args.http2
+        || args.no_keepalive
+        || args.method == Method::Connect
+        || args.ip_strategy.is_some_and(|strategy| strategy.rotates())
     {
-        // For other platforms, use TLS 1.2 as a safe default
-        Some(native_tls::Protocol::Tlsv12)
+        return None;
     }
+    let url = Url::parse(&args.url).ok()?;
+    Some(pool::Key::new(&url.host_str(), url.port(), url.is_https()))
 }
 
-/// Handle HTTPS connections
-pub fn handle_https_connection(
-    stream: TcpStream,
-    host: &str,
-    request_bytes: &[u8],
-    args: &Args,
-) -> Result<(), String> {
-    handle_https_connection_impl(stream, host, request_bytes, args, 0)
+/// Take an idle connection to the origin of `args.url` from the pool
+fn reuse_connection(args: &Args) -> Option<Connection> {
+    let key = pool_key(args)?;
+    let connection = pool::checkout(&key)?;
+    log::verbose(
+        args,
+        format_args!("Reusing connection to {}:{}", key.host, key.port),
+    );
+    Some(connection)
 }
 
-fn handle_https_connection_impl(
-    stream: TcpStream,
-    host: &str,
+/// Write the request on an HTTP/1.1 connection and handle the response.
+///
+/// A `reused` connection may have been closed by the server while idle; the request
+/// is then sent again on a new connection.
+fn exchange(
+    mut connection: Connection,
     request_bytes: &[u8],
     args: &Args,
+    jar: &mut CookieJar,
     redirect_count: usize,
+    reused: bool,
 ) -> Result<(), String> {
+    writeout::mark(Event::PreTransfer);
+    writeout::record_request(request_bytes.len());
+    error::enter(error::Phase::Request);
+    wire::print_request(&Logger::new(args), request_bytes);
+    let mut out = throttle::Writer::new(&mut connection, args.limit_rate);
+    let sent = out
+        .write_all(request_bytes)
+        .map_err(|err| {
+            error::record_os_error(&err);
+            format!("Write error: {}", err)
+        })
+        .and_then(|()| request::send_upload(args, &mut out));
+    match sent {
+        Ok(()) => receive_response(connection, reused, args, jar, redirect_count),
+        Err(_) if reused => send_request(args, jar, redirect_count, false),
+        Err(err) => retry_after_error(err, args, jar, redirect_count),
+    }
+}
 
-    // Determine which TLS version to use
-    let tls_version = args
-        .tls_version
-        .as_deref()
-        .and_then(get_tls_protocol_version)
-        .or_else(get_default_tls_protocol);
-
-    let mut builder = TlsConnector::builder();
+/// Keep the connection for a later request once the rest of the body is read.
+///
+/// Only small leftovers, such as a redirect page, are drained; connections whose
+/// body ends when they close are dropped.
+fn release(mut reader: ResponseReader<Connection>, headers: &[u8], args: &Args) {
+    /// Largest remaining body read just to keep a connection
+    const MAX_DRAIN: u64 = 64 * 1024;
 
-    // Set minimum protocol version if specified
-    if let Some(version) = tls_version {
-        builder.min_protocol_version(Some(version));
+    let Some(key) = pool_key(args) else {
+        return;
+    };
+    if !pool::allows_reuse(headers) {
+        return;
+    }
+    if io::copy(&mut (&mut reader).take(MAX_DRAIN), &mut io::sink()).is_err() {
+        return;
     }
+    if let Some(connection) = reader.into_reusable() {
+        log::verbose(
+            args,
+            format_args!("Keeping connection to {}:{} open", key.host, key.port),
+        );
+        pool::checkin(key, connection);
+    }
+}
 
-    // Complete the connector configuration
-    let connector = match builder
-        .danger_accept_invalid_certs(false)
-        .danger_accept_invalid_hostnames(false)
-        .build()
-    {
-        Ok(connector) => connector,
+/// Read the response from `connection`, following redirects or streaming the body to its destination
+fn receive_response(
+    connection: Connection,
+    reused: bool,
+    args: &Args,
+    jar: &mut CookieJar,
+    redirect_count: usize,
+) -> Result<(), String> {
+    let mut reader = ResponseReader::new(connection, args.buffer_size, Logger::new(args));
+    let headers = match reader.read_head(&args.method) {
+        Ok(headers) => headers,
+        // The server closed the idle connection before answering
+        Err(_) if reused => {
+            drop(reader);
+            return send_request(args, jar, redirect_count, false);
+        }
         Err(err) => {
-            return Err(format!("TLS error: {}", err));
+            drop(reader);
+            return retry_after_error(err, args, jar, redirect_count);
         }
     };
-
-    if args.verbose && !args.silent {
-        println!("Connecting to {} (HTTPS)...", host);
-        if let Some(version) = &args.tls_version {
-            println!("Using minimum TLS version: {}", version);
+    writeout::mark(Event::StartTransfer);
+    error::enter(error::Phase::Body);
+    wire::print_response(&Logger::new(args), &headers);
+
+    // Remember cookies before following a redirect or handing off the body
+    if let Ok(url) = Url::parse(&args.url) {
+        let host = url.host_str();
+        let stored = jar.store_response(&headers, &host, url.path(), args.clock.unix_time());
+        if stored > 0 {
+            log::verbose(
+                args,
+                format_args!("Stored {} cookie(s) from {}", stored, host),
+            );
         }
     }
-
-    let mut tls_stream = match connector.connect(host, stream) {
-        Ok(stream) => stream,
-        Err(err) => {
-            return Err(format!("TLS handshake error: {}", err));
-        }
-    };
-
-    if args.verbose && !args.silent {
-        println!("Sending request...");
-        println!("Waiting for response...");
+    if let Some(path) = &args.cookie_jar {
+        jar.save(path)?;
     }
 
-    // Use the TLS stream for communication
-    if let Err(err) = tls_stream.write_all(request_bytes) {
-        return Err(format!("Write error: {}", err));
+    // Check for redirect status codes before any of the body is read
+    let code = response::parse_status(&headers).ok();
+    let status = code.map_or(0, StatusCode::as_u16);
+
+    if let Some(code) = code.filter(|&code| args.follow_redirects && is_redirect_status(code)) {
+        match response::get_location(&headers) {
+            Some(location) => {
+                release(reader, &headers, args);
+                return handle_redirect(&location, args, jar, code, redirect_count);
+            }
+            None => log::verbose(
+                args,
+                format_args!(
+                    "Warning: HTTP {} has no Location header, treating it as the final response",
+                    status
+                ),
+            ),
+        }
     }
 
-    // Read response
-    match read_http_response(&mut tls_stream, args.verbose && !args.silent) {
-        Ok(response_bytes) => {
-            // Check for redirect status codes
-            let status = response::parse_status_line(&response_bytes).unwrap_or(0);
-            
-            if args.follow_redirects && is_redirect_status(status) {
-                if let Some(location) = response::get_location(&response_bytes) {
-                    return handle_redirect(&location, args, redirect_count);
-                }
+    // List what a 300 Multiple Choices response offers, since none was followed
+    if status == 300 && !args.silent {
+        let choices = redirect::alternatives(&headers, &args.url);
+        if choices.is_empty() {
+            eprintln!("Multiple choices: the response names no alternatives");
+        } else {
+            eprintln!("Multiple choices:");
+            for choice in choices {
+                eprintln!("  {}", choice);
             }
+        }
+    }
 
-            // Process response
-            response::process(&response_bytes, args);
-            Ok(())
+    // Back off and try again on a transient server error
+    if retry::is_retryable_status(status) {
+        if let Some((retry_args, delay)) = retry::next(args, Some(&headers)) {
+            drop(reader);
+            retry::announce(&format!("HTTP {}", status), delay, &retry_args);
+            retry_args.clock.sleep(delay);
+            return resend(&retry_args, jar, redirect_count);
         }
-        Err(err) => Err(err),
     }
-}
 
-/// Handle HTTP connections
-pub fn handle_http_connection(
-    stream: TcpStream,
-    host: &str,
-    request_bytes: &[u8],
-    args: &Args,
-) -> Result<(), String> {
-    handle_http_connection_impl(stream, host, request_bytes, args, 0)
+    // Ask for credentials on a Basic challenge and retry once
+    if let Some(retry_args) = auth::prompt_retry(status, &headers, args)? {
+        drop(reader);
+        return resend(&retry_args, jar, redirect_count);
+    }
+
+    // Process response
+    if redirect_count > 0 {
+        log::verbose(
+            args,
+            format_args!(
+                "Effective URL: {} (after {} redirect(s))",
+                args.url, redirect_count
+            ),
+        );
+    }
+    writeout::record_response(&headers, &args.url, redirect_count);
+    if response::fails_without_body(&headers, args) {
+        // An error page of any size is thrown away by -f, so none of it is downloaded
+        drop(reader);
+        log::verbose(
+            args,
+            format_args!("Closing the connection without reading the error body"),
+        );
+        return response::process(&headers, &mut io::empty(), args);
+    }
+    response::process(&headers, &mut reader, args)?;
+    if response::headers_only(args) && args.method != Method::Head {
+        // Closing the connection is cheaper than downloading a body nobody reads
+        log::verbose(
+            args,
+            format_args!("Closing the connection without reading the body"),
+        );
+        return Ok(());
+    }
+    release(reader, &headers, args);
+    Ok(())
 }
 
-fn handle_http_connection_impl(
-    mut stream: TcpStream,
+/// Start the exchange on a new connection: the TLS handshake for HTTPS, then the
+/// request and its response over HTTP/2 or HTTP/1.1.
+///
+/// HTTP/2 is used when ALPN selected it, or with `--http2` over plain HTTP (prior knowledge).
+fn handle_connection(
+    stream: Box<dyn Transport>,
     host: &str,
+    is_https: bool,
     request_bytes: &[u8],
     args: &Args,
+    jar: &mut CookieJar,
     redirect_count: usize,
 ) -> Result<(), String> {
+    let (connection, use_http2) = if is_https {
+        log::verbose(args, format_args!("Connecting to {} (HTTPS)...", host));
+        if let Some(version) = &args.tls_version {
+            log::verbose(args, format_args!("Using minimum TLS version: {}", version));
+        }
+        error::enter(error::Phase::Tls);
+        match tls::connect(stream, host, args) {
+            Ok((tls_stream, use_http2)) => {
+                writeout::mark(Event::AppConnect);
+                (Connection::Tls(Box::new(tls_stream)), use_http2)
+            }
+            Err(err) => return retry_after_error(err, args, jar, redirect_count),
+        }
+    } else {
+        log::verbose(args, format_args!("Connecting to {} (HTTP)...", host));
+        (Connection::Plain(stream), args.http2)
+    };
 
-    if args.verbose && !args.silent {
-        println!("Connecting to {} (HTTP)...", host);
-    }
-
-    if let Err(err) = stream.write_all(request_bytes) {
-        return Err(format!("Write error: {}", err));
+    log::verbose(args, format_args!("Sending request..."));
+    log::verbose(args, format_args!("Waiting for response..."));
+
+    if use_http2 {
+        writeout::mark(Event::PreTransfer);
+        error::enter(error::Phase::Request);
+        let response = match exchange_http2(connection, args, jar) {
+            Ok(response) => response,
+            Err(err) => return retry_after_error(err, args, jar, redirect_count),
+        };
+        return receive_response(response, false, args, jar, redirect_count);
     }
 
-    if args.verbose && !args.silent {
-        println!("Sending request...");
-        println!("Waiting for response...");
-    }
+    exchange(connection, request_bytes, args, jar, redirect_count, false)
+}
 
-    // Read response
-    match read_http_response(&mut stream, args.verbose && !args.silent) {
-        Ok(response_bytes) => {
-            // Check for redirect status codes
-            let status = response::parse_status_line(&response_bytes).unwrap_or(0);
-            
-            if args.follow_redirects && is_redirect_status(status) {
-                if let Some(location) = response::get_location(&response_bytes) {
-                    return handle_redirect(&location, args, redirect_count);
-                }
-            }
+/// Speak HTTP/2 on `connection` and return its response, to be read as it arrives.
+#[cfg(feature = "http2")]
+fn exchange_http2(
+    connection: Connection,
+    args: &Args,
+    jar: &mut CookieJar,
+) -> Result<Connection, String> {
+    let response = http2::exchange(connection, args, jar)?;
+    Ok(Connection::Http2(Box::new(response)))
+}
 
-            // Process response
-            response::process(&response_bytes, args);
-            Ok(())
-        }
-        Err(err) => Err(err),
-    }
+/// Always fails: this build has no HTTP/2 support.
+#[cfg(not(feature = "http2"))]
+fn exchange_http2(
+    _connection: Connection,
+    _args: &Args,
+    _jar: &mut CookieJar,
+) -> Result<Connection, String> {
+    Err("HTTP/2 is not available: rurl was built without the http2 feature".to_string())
 }
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io::Read;
+
+use crate::args::Args;
+use crate::json::{self, Value};
+
+/// Longest line shown when reporting a difference
+const MAX_SHOWN: usize = 200;
+
+/// Compare the response body with the golden file given with `--compare`.
+///
+/// Bytes are compared exactly, or with `--compare-json` as JSON documents whose
+/// formatting and object key order do not matter.
+///
+/// # Arguments
+///
+/// * `body` - The response body.
+/// * `args` - A reference to an `Args` struct holding `--compare` and `--compare-json`.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if the body matches, or an error message describing the first difference.
+pub fn check(body: &mut dyn Read, args: &Args) -> Result<(), String> {
+    let Some(path) = &args.compare else {
+        return Ok(());
+    };
+    let expected =
+        fs::read(path).map_err(|err| format!("Failed to read golden file '{}': {}", path, err))?;
+    let mut actual = Vec::new();
+    body.read_to_end(&mut actual)
+        .map_err(|err| format!("Read error: {}", err))?;
+
+    let difference = if args.compare_json {
+        let parse = |bytes: &[u8], what: &str| {
+            json::parse(&String::from_utf8_lossy(bytes))
+                .map_err(|err| format!("{} is not JSON: {}", what, err))
+        };
+        let expected = parse(&expected, &format!("Golden file '{}'", path))?;
+        let actual = parse(&actual, &format!("Response from {}", args.url))?;
+        json_difference(&expected, &actual, "$")
+    } else {
+        text_difference(&expected, &actual)
+    };
+
+    match difference {
+        Some(difference) => Err(format!(
+            "Response from {} differs from '{}': {}",
+            args.url, path, difference
+        )),
+        None => {
+            if !args.silent {
+                println!("Response matches '{}'", path);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Describe the first line where `actual` differs from `expected`.
+pub fn text_difference(expected: &[u8], actual: &[u8]) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected_lines: Vec<&[u8]> = expected.split(|&byte| byte == b'\n').collect();
+    let actual_lines: Vec<&[u8]> = actual.split(|&byte| byte == b'\n').collect();
+    let line = expected_lines
+        .iter()
+        .zip(&actual_lines)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected_lines.len().min(actual_lines.len()));
+    let show = |lines: &[&[u8]]| match lines.get(line) {
+        Some(text) => {
+            let text = String::from_utf8_lossy(text);
+            let shown: String = text.chars().take(MAX_SHOWN).collect();
+            format!("{:?}", shown)
+        }
+        None => "end of body".to_string(),
+    };
+    Some(format!(
+        "line {}: expected {}, got {}",
+        line + 1,
+        show(&expected_lines),
+        show(&actual_lines)
+    ))
+}
+
+/// Describe the first place where `actual` differs from `expected`, as a JSONPath.
+///
+/// Object members are matched by key, so their order does not matter.
+pub fn json_difference(expected: &Value, actual: &Value, path: &str) -> Option<String> {
+    match (expected, actual) {
+        (Value::Object(expected_fields), Value::Object(actual_fields)) => {
+            for (key, expected) in expected_fields {
+                let member = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => {
+                        if let Some(difference) = json_difference(expected, actual, &member) {
+                            return Some(difference);
+                        }
+                    }
+                    None => return Some(format!("{}: missing", member)),
+                }
+            }
+            actual_fields
+                .iter()
+                .find(|(key, _)| expected.get(key).is_none())
+                .map(|(key, _)| format!("{}.{}: unexpected member", path, key))
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            for (index, (expected, actual)) in expected_items.iter().zip(actual_items).enumerate() {
+                let element = format!("{}[{}]", path, index);
+                if let Some(difference) = json_difference(expected, actual, &element) {
+                    return Some(difference);
+                }
+            }
+            (expected_items.len() != actual_items.len()).then(|| {
+                format!(
+                    "{}: expected {} elements, got {}",
+                    path,
+                    expected_items.len(),
+                    actual_items.len()
+                )
+            })
+        }
+        _ if expected == actual => None,
+        _ => Some(format!("{}: expected {}, got {}", path, expected, actual)),
+    }
+}
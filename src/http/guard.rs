@@ -0,0 +1,75 @@
+use crate::args::Args;
+use crate::netrc;
+use rurl::url::Url;
+
+/// Names of the credentials a request would carry, in report order.
+fn credentials(args: &Args) -> Vec<&'static str> {
+    let has_header = |name: &str| {
+        args.headers.iter().any(|header| {
+            header
+                .split_once(':')
+                .is_some_and(|(field, _)| field.trim().eq_ignore_ascii_case(name))
+        })
+    };
+
+    let mut found = Vec::new();
+    if args.user.is_some() {
+        found.push("basic auth (-u)");
+    } else if let Ok(url) = Url::parse(&args.url) {
+        if url.username().is_some() {
+            found.push("basic auth (URL)");
+        } else if netrc::find(&args.netrc, &url.host_str(), None)
+            .is_some_and(|machine| machine.login.is_some())
+        {
+            found.push("basic auth (.netrc)");
+        }
+    }
+    if args.oauth2_bearer.is_some() {
+        found.push("bearer token");
+    }
+    if has_header("authorization") {
+        found.push("Authorization header");
+    }
+    if args.cookie.is_some() || has_header("cookie") {
+        found.push("cookies");
+    }
+    found
+}
+
+/// Check a request against the transport policy before connecting.
+///
+/// With `--require-tls` any plain HTTP request (initial or redirect) is refused.
+/// Otherwise a warning is printed when credentials would be sent in cleartext.
+///
+/// # Arguments
+///
+/// * `url` - The URL about to be requested.
+/// * `is_https` - Whether the URL uses TLS.
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if the request may be sent, or an error message if it is refused.
+pub fn check(url: &str, is_https: bool, args: &Args) -> Result<(), String> {
+    if is_https {
+        return Ok(());
+    }
+
+    if args.require_tls {
+        return Err(format!(
+            "Refusing to send request over plain HTTP (--require-tls): {}",
+            url
+        ));
+    }
+
+    let credentials = credentials(args);
+    if !credentials.is_empty() && !args.silent {
+        eprintln!(
+            "Warning: sending {} over cleartext HTTP to {}",
+            credentials.join(", "),
+            url
+        );
+    }
+
+    Ok(())
+}
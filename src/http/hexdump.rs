@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+
+/// Bytes shown per dump line
+const LINE_WIDTH: usize = 16;
+
+/// Writer that renders everything written to it as a canonical hex+ASCII dump.
+///
+/// Each line holds the offset, sixteen bytes in hex (split into two groups of
+/// eight), and their printable ASCII form, like `hexdump -C`. Bytes are buffered
+/// until a line is full, so the dump can be fed from a streaming body; call
+/// `finish` to emit the last partial line and the final offset.
+pub struct HexDump<W: Write> {
+    out: W,
+    line: Vec<u8>,
+    offset: u64,
+}
+
+impl<W: Write> HexDump<W> {
+    /// Create a dump writer over `out`.
+    pub fn new(out: W) -> Self {
+        HexDump {
+            out,
+            line: Vec::with_capacity(LINE_WIDTH),
+            offset: 0,
+        }
+    }
+
+    /// Write the buffered line, if any, and advance the offset.
+    fn write_line(&mut self) -> io::Result<()> {
+        if self.line.is_empty() {
+            return Ok(());
+        }
+        let rendered = format_line(self.offset, &self.line);
+        self.out.write_all(rendered.as_bytes())?;
+        self.offset += self.line.len() as u64;
+        self.line.clear();
+        Ok(())
+    }
+
+    /// Emit the last partial line and the total length, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_line()?;
+        writeln!(self.out, "{:08x}", self.offset)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for HexDump<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let n = (LINE_WIDTH - self.line.len()).min(rest.len());
+            self.line.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+            if self.line.len() == LINE_WIDTH {
+                self.write_line()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Flush complete lines only; a partial line is held back until more bytes or `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Render one dump line for up to sixteen bytes starting at `offset`.
+///
+/// # Arguments
+///
+/// * `offset` - The offset of the first byte in the dumped stream.
+/// * `bytes` - The bytes on this line.
+///
+/// # Returns
+///
+/// * `String` - The formatted line, including the trailing newline.
+pub fn format_line(offset: u64, bytes: &[u8]) -> String {
+    let mut line = format!("{:08x} ", offset);
+    for i in 0..LINE_WIDTH {
+        if i % 8 == 0 {
+            line.push(' ');
+        }
+        match bytes.get(i) {
+            Some(byte) => line.push_str(&format!("{:02x} ", byte)),
+            None => line.push_str("   "),
+        }
+    }
+    line.push_str(" |");
+    line.extend(bytes.iter().map(|&b| {
+        if b.is_ascii_graphic() || b == b' ' {
+            b as char
+        } else {
+            '.'
+        }
+    }));
+    line.push_str("|\n");
+    line
+}
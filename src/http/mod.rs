@@ -1,4 +1,23 @@
+pub mod audit;
+pub mod auth;
 pub mod client;
+pub mod compare;
+pub mod guard;
+pub mod hexdump;
+pub mod multipart;
+pub mod negotiate;
+pub mod paginate;
+pub mod parts;
+pub mod pool;
+pub mod proxy;
+pub mod range;
+pub mod reader;
+pub mod redirect;
 pub mod request;
+pub mod resolve;
 pub mod response;
-pub mod url;
+pub mod retry;
+pub mod sniff;
+pub mod timeout;
+pub mod transport;
+pub mod wire;
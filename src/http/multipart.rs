@@ -0,0 +1,299 @@
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::http::response::find_header;
+
+/// Content of one form field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartValue {
+    /// A literal value, from `name=value`
+    Text(String),
+    /// A file upload, from `name=@path`
+    File {
+        path: String,
+        filename: String,
+        content_type: Option<String>,
+    },
+}
+
+/// One `-F/--form` field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: String,
+    pub value: PartValue,
+}
+
+/// Parse a `-F/--form` value.
+///
+/// Accepts `name=value` and `name=@path`; a file may be followed by
+/// `;type=<media type>` and `;filename=<name>` to override what is sent.
+///
+/// # Arguments
+///
+/// * `spec` - The form field, e.g. `user=alice` or `avatar=@me.png;type=image/png`.
+///
+/// # Returns
+///
+/// * `Result<Part, &'static str>` - The parsed field if successful, or an error message if unsuccessful.
+pub fn parse_part(spec: &str) -> Result<Part, &'static str> {
+    let (name, value) = spec.split_once('=').ok_or("Invalid form field")?;
+    if name.is_empty() {
+        return Err("Invalid form field");
+    }
+
+    let value = match value.strip_prefix('@') {
+        Some(file) => {
+            let mut params = file.split(';');
+            let path = params.next().unwrap_or("").to_string();
+            if path.is_empty() {
+                return Err("Missing form file");
+            }
+            let mut filename = Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            let mut content_type = None;
+            for param in params {
+                match param.split_once('=') {
+                    Some(("type", media_type)) => content_type = Some(media_type.to_string()),
+                    Some(("filename", name)) => filename = name.to_string(),
+                    _ => return Err("Invalid form file option"),
+                }
+            }
+            PartValue::File {
+                path,
+                filename,
+                content_type,
+            }
+        }
+        None => PartValue::Text(value.to_string()),
+    };
+
+    Ok(Part {
+        name: name.to_string(),
+        value,
+    })
+}
+
+/// Guess a part's Content-Type from its file extension.
+pub fn content_type_for(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" | "log" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The boundary used for multipart bodies in this process.
+///
+/// It is generated once so the Content-Type header and the body always agree.
+pub fn boundary() -> &'static str {
+    static BOUNDARY: OnceLock<String> = OnceLock::new();
+    BOUNDARY.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let seed = nanos ^ (u64::from(std::process::id()) << 32);
+        format!("------------------------rurl{:016x}", seed)
+    })
+}
+
+/// Quote a name for a Content-Disposition parameter.
+fn quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Encode form fields as a multipart/form-data body.
+///
+/// # Arguments
+///
+/// * `parts` - The form fields, in command-line order.
+/// * `boundary` - The boundary announced in the Content-Type header.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, &'static str>` - The encoded body if successful, or an error message if a file could not be read.
+pub fn encode(parts: &[Part], boundary: &str) -> Result<Vec<u8>, &'static str> {
+    let mut body = Vec::new();
+
+    for part in parts {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        match &part.value {
+            PartValue::Text(value) => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                        quote(&part.name)
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(value.as_bytes());
+            }
+            PartValue::File {
+                path,
+                filename,
+                content_type,
+            } => {
+                let content = std::fs::read(path).map_err(|_| "Failed to read form file")?;
+                let content_type = content_type
+                    .as_deref()
+                    .unwrap_or_else(|| content_type_for(filename));
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        quote(&part.name),
+                        quote(filename),
+                        content_type
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(&content);
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok(body)
+}
+
+/// One part of a multipart response body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Received {
+    /// The part's header fields, names as received
+    pub headers: Vec<(String, String)>,
+    pub data: Vec<u8>,
+}
+
+impl Received {
+    /// The value of the first header field called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The boundary of a response whose Content-Type is one of `media_types`.
+pub fn find_boundary(headers: &[u8], media_types: &[&str]) -> Option<String> {
+    let content_type = String::from_utf8_lossy(find_header(headers, "content-type")?);
+    let (media_type, params) = content_type.split_once(';')?;
+    let media_type = media_type.trim();
+    if !media_types
+        .iter()
+        .any(|expected| media_type.eq_ignore_ascii_case(expected))
+    {
+        return None;
+    }
+    param(params, "boundary")
+}
+
+/// The value of parameter `name` in a `; name=value; ...` list, unquoted.
+///
+/// Quoted values may contain `;`. Parameters without a value are skipped.
+pub fn param(params: &str, name: &str) -> Option<String> {
+    let mut rest = params;
+    loop {
+        let (key, after) = rest.split_once('=')?;
+        // A parameter without a value, such as `attachment`, is skipped
+        let key = key.rsplit(';').next().unwrap_or(key);
+        let after = after.trim_start();
+        let (value, next) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (quoted[..end].to_string(), &quoted[end + 1..])
+            }
+            None => {
+                let end = after.find(';').unwrap_or(after.len());
+                (after[..end].trim().to_string(), &after[end..])
+            }
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = next.trim_start_matches(|c: char| c != ';');
+    }
+}
+
+/// Split a multipart body into its parts.
+///
+/// # Arguments
+///
+/// * `body` - The complete response body.
+/// * `boundary` - The boundary from the Content-Type.
+///
+/// # Returns
+///
+/// * `Result<Vec<Received>, String>` - The parts in the order received if successful, or an error message if the body is malformed or truncated.
+pub fn split(body: &[u8], boundary: &str) -> Result<Vec<Received>, String> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let next_delimiter = [b"\r\n".as_slice(), &delimiter].concat();
+    let malformed = || "Malformed multipart body".to_string();
+
+    let start = find(body, &delimiter).ok_or_else(malformed)?;
+    let mut rest = &body[start + delimiter.len()..];
+    let mut parts = Vec::new();
+    loop {
+        // The close delimiter ends the body
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        let line_end = find(rest, b"\r\n").ok_or_else(malformed)?;
+        rest = &rest[line_end + 2..];
+
+        let (headers, data_start) = if rest.starts_with(b"\r\n") {
+            (&rest[..0], 2)
+        } else {
+            let end = find(rest, b"\r\n\r\n").ok_or_else(malformed)?;
+            (&rest[..end], end + 4)
+        };
+        let data_end =
+            find(&rest[data_start..], &next_delimiter).ok_or("Truncated multipart body")?;
+
+        let headers = headers
+            .split(|&b| b == b'\n')
+            .filter_map(|line| {
+                let line = String::from_utf8_lossy(line);
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect();
+        parts.push(Received {
+            headers,
+            data: rest[data_start..data_start + data_end].to_vec(),
+        });
+        rest = &rest[data_start + data_end + next_delimiter.len()..];
+    }
+}
+
+/// The position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,311 @@
+use std::cell::RefCell;
+use std::iter;
+
+use crate::args::Args;
+use crate::http::{multipart, response};
+use crate::output::log::{Level, Logger};
+
+/// Request headers varied by `--negotiate-report`, one at a time
+const VARIANTS: [(&str, &str); 8] = [
+    ("Accept", "application/json"),
+    ("Accept", "text/html"),
+    ("Accept", "application/xml"),
+    ("Accept-Encoding", "gzip"),
+    ("Accept-Encoding", "br"),
+    ("Accept-Encoding", "identity"),
+    ("Accept-Language", "en"),
+    ("Accept-Language", "de"),
+];
+
+/// What a response said about the representation it carries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Representation {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_language: Option<String>,
+    pub vary: Option<String>,
+    /// Body bytes received, as sent on the wire
+    pub length: u64,
+}
+
+impl Representation {
+    /// Describe the response with header block `headers` and a body of `length` bytes.
+    pub fn new(headers: &[u8], length: u64) -> Self {
+        let header = |name| {
+            response::find_header(headers, name)
+                .map(|value| String::from_utf8_lossy(value).into_owned())
+        };
+        Representation {
+            status: response::parse_status_line(headers).unwrap_or(0),
+            content_type: header("content-type"),
+            content_encoding: header("content-encoding"),
+            content_language: header("content-language"),
+            vary: header("vary"),
+            length,
+        }
+    }
+
+    /// Whether `other` is a different representation; the length alone is not
+    /// compared, since dynamic pages change size between requests.
+    fn differs_from(&self, other: &Representation) -> bool {
+        self.status != other.status
+            || self.content_type != other.content_type
+            || self.content_encoding != other.content_encoding
+            || self.content_language != other.content_language
+    }
+
+    /// Whether the Vary header names `header` (or is `*`).
+    fn varies_by(&self, header: &str) -> bool {
+        self.vary.as_deref().is_some_and(|vary| {
+            vary.split(',')
+                .map(str::trim)
+                .any(|name| name == "*" || name.eq_ignore_ascii_case(header))
+        })
+    }
+}
+
+/// A response whose language or charset is not one the request asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The request header that was not honoured, `Accept-Language` or `Accept-Charset`
+    pub header: &'static str,
+    pub requested: String,
+    /// The Content-Language or charset received, None if the response has no Content-Language
+    pub received: Option<String>,
+}
+
+thread_local! {
+    static RECORDED: RefCell<Option<Representation>> = const { RefCell::new(None) };
+    static MISMATCHES: RefCell<Vec<Mismatch>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Compare the response with the `Accept-Language` and `Accept-Charset` headers the
+/// request sent, warn about each one it did not honour with `-v`, and keep them for
+/// the `--summary-file` entry of the transfer.
+///
+/// A response without Content-Language does not honour an `Accept-Language`; one
+/// without a charset parameter is not checked, since many media types have none.
+///
+/// # Arguments
+///
+/// * `headers` - The response head.
+/// * `args` - A reference to an `Args` struct holding the request headers.
+pub fn check(headers: &[u8], args: &Args) {
+    let header = |name| {
+        response::find_header(headers, name)
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+    };
+    let mut mismatches = Vec::new();
+    if let Some(requested) = requested(args, "Accept-Language") {
+        let received = header("content-language");
+        let honoured = received.as_deref().is_some_and(|received| {
+            received
+                .split(',')
+                .any(|tag| accepts(&requested, tag.trim(), language_matches))
+        });
+        if !honoured {
+            mismatches.push(Mismatch {
+                header: "Accept-Language",
+                requested,
+                received,
+            });
+        }
+    }
+    if let Some(requested) = requested(args, "Accept-Charset") {
+        let charset = header("content-type").and_then(|content_type| {
+            let (_, params) = content_type.split_once(';')?;
+            multipart::param(params, "charset")
+        });
+        if let Some(charset) = charset {
+            if !accepts(&requested, &charset, str::eq_ignore_ascii_case) {
+                mismatches.push(Mismatch {
+                    header: "Accept-Charset",
+                    requested,
+                    received: Some(charset),
+                });
+            }
+        }
+    }
+
+    let logger = Logger::new(args);
+    for mismatch in &mismatches {
+        let received = match (&mismatch.received, mismatch.header) {
+            (Some(language), "Accept-Language") => format!("Content-Language: {}", language),
+            (Some(charset), _) => format!("charset={}", charset),
+            (None, _) => "no Content-Language".to_string(),
+        };
+        logger.log(
+            Level::Verbose,
+            format_args!(
+                "Warning: requested {}: {}, got {}",
+                mismatch.header, mismatch.requested, received
+            ),
+        );
+    }
+    MISMATCHES.with(|recorded| *recorded.borrow_mut() = mismatches);
+}
+
+/// The mismatches found for the last response of the current transfer, clearing them.
+pub fn take_mismatches() -> Vec<Mismatch> {
+    MISMATCHES.with(|recorded| recorded.borrow_mut().split_off(0))
+}
+
+/// The value of the last `-H` header called `name`, if it is not empty.
+fn requested(args: &Args, name: &str) -> Option<String> {
+    args.headers
+        .iter()
+        .rev()
+        .filter_map(|header| header.split_once(':'))
+        .find(|(field, _)| field.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Whether the `Accept-*` list `accept` allows `value`; ranges with `q=0` are refused.
+fn accepts(accept: &str, value: &str, matches: fn(&str, &str) -> bool) -> bool {
+    accept.split(',').any(|item| {
+        let mut params = item.split(';');
+        let range = params.next().unwrap_or("").trim();
+        let refused = params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        });
+        !refused && (range == "*" || matches(range, value))
+    })
+}
+
+/// RFC 4647 basic filtering: `en` matches `en` and `en-GB`, but `en-GB` does not match `en`.
+fn language_matches(range: &str, tag: &str) -> bool {
+    tag.len() >= range.len()
+        && tag.as_bytes()[..range.len()].eq_ignore_ascii_case(range.as_bytes())
+        && matches!(tag.as_bytes().get(range.len()), None | Some(b'-'))
+}
+
+/// Remember the final response of the current request for the report.
+pub fn record(headers: &[u8], length: u64) {
+    RECORDED.with(|recorded| *recorded.borrow_mut() = Some(Representation::new(headers, length)));
+}
+
+/// Send the request once as given and once per negotiation header, then report how
+/// the representation changed.
+///
+/// Each variant replaces any `-H` header of the same name. Response bodies are
+/// counted, not printed.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct describing the request.
+/// * `fetch` - Sends one request through the usual path, with profiles, redirects, and retries.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once the report was printed, or the error of the request that failed.
+pub fn report(
+    args: &Args,
+    mut fetch: impl FnMut(Args) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut rows = Vec::new();
+    for variant in iter::once(None).chain(VARIANTS.iter().map(Some)) {
+        let mut variant_args = args.clone();
+        if let Some((name, value)) = variant {
+            variant_args.headers.retain(|header| {
+                !header
+                    .split_once(':')
+                    .is_some_and(|(field, _)| field.trim().eq_ignore_ascii_case(name))
+            });
+            variant_args.headers.push(format!("{}: {}", name, value));
+        }
+        fetch(variant_args)?;
+        let representation = RECORDED
+            .with(|recorded| recorded.borrow_mut().take())
+            .ok_or("No response to report on")?;
+        rows.push((variant.copied(), representation));
+    }
+
+    if !args.silent {
+        print_report(&args.url, &rows);
+    }
+    Ok(())
+}
+
+/// Print one row per request, then which headers changed the representation.
+fn print_report(url: &str, rows: &[(Option<(&str, &str)>, Representation)]) {
+    let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let table: Vec<[String; 7]> = rows
+        .iter()
+        .map(|(variant, representation)| {
+            [
+                match variant {
+                    Some((name, value)) => format!("{}: {}", name, value),
+                    None => "(as given)".to_string(),
+                },
+                representation.status.to_string(),
+                show(&representation.content_type),
+                representation.length.to_string(),
+                show(&representation.content_encoding),
+                show(&representation.content_language),
+                show(&representation.vary),
+            ]
+        })
+        .collect();
+    let titles = [
+        "Request", "Status", "Type", "Bytes", "Encoding", "Language", "Vary",
+    ];
+    let widths: Vec<usize> = (0..titles.len())
+        .map(|column| {
+            table
+                .iter()
+                .map(|row| row[column].len())
+                .chain(iter::once(titles[column].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &[&str]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("  {}", padded.join("  ").trim_end());
+    };
+
+    println!("Content negotiation for {}:", url);
+    line(&titles);
+    for row in &table {
+        line(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+
+    let baseline = &rows[0].1;
+    let mut changed = Vec::new();
+    for (name, _) in VARIANTS {
+        let differs = rows[1..].iter().any(|(variant, representation)| {
+            variant.is_some_and(|(field, _)| field == name) && representation.differs_from(baseline)
+        });
+        if differs && !changed.contains(&name) {
+            changed.push(name);
+        }
+    }
+    if changed.is_empty() {
+        println!("Varies with: nothing (every request got the same representation)");
+        return;
+    }
+    println!("Varies with: {}", changed.join(", "));
+
+    // Caches key stored responses on the headers Vary names
+    for name in changed {
+        if !rows
+            .iter()
+            .all(|(_, representation)| representation.varies_by(name))
+        {
+            println!(
+                "Warning: the response changes with {} but Vary does not list it",
+                name
+            );
+        }
+    }
+}
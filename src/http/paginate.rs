@@ -0,0 +1,271 @@
+use std::cell::RefCell;
+
+use crate::args::Args;
+use crate::http::response;
+use crate::json::{self, Value};
+use crate::output::log;
+use rurl::url::Url;
+
+/// Default for `--max-pages`
+pub const DEFAULT_MAX_PAGES: usize = 100;
+
+/// Where `--paginate` finds the next page in a JSON response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Next {
+    /// The URL of the next page, absolute or relative to the current one
+    Url(json::Path),
+    /// A cursor to send in the named query parameter
+    Cursor(json::Path, String),
+    /// A query parameter advanced by the number of items on each page
+    Offset(String),
+}
+
+/// A parsed `--paginate` specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spec {
+    pub next: Next,
+    /// The array whose elements are merged across pages
+    pub items: Option<json::Path>,
+}
+
+impl Spec {
+    /// Parse a specification such as `next=$.next_url`, `cursor=$.meta.cursor,param=after`,
+    /// or `offset=start,items=$.results[*]`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Spec, &'static str>` - The parsed specification if successful, or an error message if unsuccessful.
+    pub fn parse(spec: &str) -> Result<Spec, &'static str> {
+        let (mut next, mut cursor, mut param, mut offset, mut items) =
+            (None, None, None, None, None);
+        for entry in spec.split(',') {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or("Invalid --paginate entry (expected key=value)")?;
+            let value = value.trim();
+            match key.trim() {
+                "next" => next = Some(json::Path::parse(value)?),
+                "cursor" => cursor = Some(json::Path::parse(value)?),
+                "param" if !value.is_empty() => param = Some(value.to_string()),
+                "offset" if !value.is_empty() => offset = Some(value.to_string()),
+                // `[*]` only spells out that the elements are taken
+                "items" => {
+                    items = Some(json::Path::parse(
+                        value.strip_suffix("[*]").unwrap_or(value),
+                    )?)
+                }
+                _ => {
+                    return Err(
+                        "Unknown --paginate key (expected next, cursor, param, offset, or items)",
+                    )
+                }
+            }
+        }
+
+        let next = match (next, cursor, param, offset) {
+            (Some(path), None, None, None) => Next::Url(path),
+            (None, Some(path), Some(param), None) => Next::Cursor(path, param),
+            (None, Some(_), None, None) => return Err("--paginate cursor= needs param="),
+            (None, None, None, Some(param)) if items.is_some() => Next::Offset(param),
+            (None, None, None, Some(_)) => return Err("--paginate offset= needs items="),
+            _ => return Err("--paginate needs exactly one of next=, cursor=, or offset="),
+        };
+        Ok(Spec { next, items })
+    }
+}
+
+thread_local! {
+    static NEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+    static ITEMS: RefCell<Vec<Value>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Find the target of the `Link` header entry whose relation includes `rel`.
+///
+/// Entries look like `<https://api.example.com/items?page=2>; rel="next"`; several may
+/// share one field or be spread over several `Link` fields.
+///
+/// # Arguments
+///
+/// * `headers` - The response header block, including the status line.
+/// * `rel` - The link relation to look for, compared case-insensitively.
+///
+/// # Returns
+///
+/// * `Option<String>` - The link target as written, if an entry matches.
+pub fn find_link(headers: &[u8], rel: &str) -> Option<String> {
+    find_links(headers, rel).into_iter().next()
+}
+
+/// Find the targets of every `Link` header entry whose relation includes `rel`, in order.
+pub fn find_links(headers: &[u8], rel: &str) -> Vec<String> {
+    response::header_fields(headers)
+        .filter(|(name, _)| name.eq_ignore_ascii_case(b"link"))
+        .map(|(_, value)| String::from_utf8_lossy(value).into_owned())
+        .flat_map(|value| {
+            // The target is enclosed in <...>, so a comma inside it does not split entries
+            value
+                .split('<')
+                .skip(1)
+                .filter_map(|entry| {
+                    let (target, params) = entry.split_once('>')?;
+                    let matches = params.split(';').any(|param| {
+                        param.split_once('=').is_some_and(|(name, value)| {
+                            name.trim().eq_ignore_ascii_case("rel")
+                                && value
+                                    .trim()
+                                    .trim_end_matches(',')
+                                    .trim_matches('"')
+                                    .split_whitespace()
+                                    .any(|relation| relation.eq_ignore_ascii_case(rel))
+                        })
+                    });
+                    matches.then(|| target.trim().to_string())
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Remember the `--follow-link` target of the current page's final response.
+///
+/// Relative targets are resolved against the URL of the page.
+pub fn record(headers: &[u8], args: &Args) -> Result<(), String> {
+    let rel = args.follow_link.as_deref().unwrap_or("next");
+    let next = match find_link(headers, rel) {
+        Some(link) => Some(Url::parse(&args.url)?.join(&link)?.to_string()),
+        None => None,
+    };
+    NEXT.with(|recorded| *recorded.borrow_mut() = next);
+    Ok(())
+}
+
+/// Find the next page in the JSON body of the current page, as described by `spec`, and
+/// collect its items.
+///
+/// A missing, `null`, or empty next URL or cursor ends the walk, as does an empty
+/// page with `offset=`.
+pub fn record_page(page: &Value, spec: &Spec, args: &Args) -> Result<(), String> {
+    let items = match &spec.items {
+        Some(path) => page
+            .select(path)
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("No array at {} in the response from {}", path, args.url))?,
+        None => &[],
+    };
+    ITEMS.with(|collected| collected.borrow_mut().extend_from_slice(items));
+
+    let url = Url::parse(&args.url)?;
+    let next = match &spec.next {
+        Next::Url(path) => match page.select(path) {
+            Some(Value::String(link)) if !link.is_empty() => Some(url.join(link)?),
+            _ => None,
+        },
+        Next::Cursor(path, param) => {
+            let cursor = match page.select(path) {
+                Some(Value::String(cursor)) if !cursor.is_empty() => Some(cursor.clone()),
+                Some(Value::Number(number)) => Some(number.to_string()),
+                _ => None,
+            };
+            cursor.map(|cursor| {
+                let mut url = url.clone();
+                url.set_query_pair(param, &cursor);
+                url
+            })
+        }
+        Next::Offset(param) if !items.is_empty() => {
+            let current = url
+                .query_pairs()
+                .into_iter()
+                .find(|(name, _)| name == param)
+                .and_then(|(_, value)| value.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut url = url.clone();
+            url.set_query_pair(param, &(current + items.len()).to_string());
+            Some(url)
+        }
+        Next::Offset(_) => None,
+    };
+    NEXT.with(|recorded| *recorded.borrow_mut() = next.map(|url| url.to_string()));
+    Ok(())
+}
+
+/// Fetch the first page, then keep following the `--follow-link` relation or the
+/// `--paginate` specification until a page has no next one or `--max-pages` pages
+/// were fetched.
+///
+/// Every page is fetched with the same options, so authentication and headers carry
+/// over, and idle connections are reused between pages. Items merged with `items=`
+/// are printed once the last page arrived.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct describing the first page.
+/// * `fetch` - Sends one request through the usual path, with profiles, redirects, and retries.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once the last page was written, or the error of the page that failed.
+pub fn follow(
+    args: &Args,
+    mut fetch: impl FnMut(Args) -> Result<(), String>,
+) -> Result<(), String> {
+    let source = match &args.follow_link {
+        Some(rel) => format!("rel=\"{}\" link", rel),
+        None => "--paginate link".to_string(),
+    };
+    let mut visited = Vec::new();
+    let mut page_args = args.clone();
+    ITEMS.with(|collected| collected.borrow_mut().clear());
+
+    for page in 1.. {
+        visited.push(page_args.url.clone());
+        NEXT.with(|recorded| recorded.borrow_mut().take());
+        fetch(page_args.clone())?;
+
+        let Some(next) = NEXT.with(|recorded| recorded.borrow_mut().take()) else {
+            break;
+        };
+        if visited.contains(&next) {
+            if !args.silent {
+                eprintln!("Warning: the {} leads back to {}, stopping", source, next);
+            }
+            break;
+        }
+        if page >= args.max_pages {
+            if !args.silent {
+                eprintln!(
+                    "Warning: stopped after {} pages (--max-pages); the next one is {}",
+                    page, next
+                );
+            }
+            break;
+        }
+
+        log::verbose(
+            args,
+            format_args!("Following {} to page {}: {}", source, page + 1, next),
+        );
+        page_args.url = next;
+    }
+
+    if args
+        .paginate
+        .as_ref()
+        .is_some_and(|spec| spec.items.is_some())
+    {
+        print_items(args);
+    }
+    Ok(())
+}
+
+/// Print the merged items: one per line with `--ndjson`, otherwise as one array.
+fn print_items(args: &Args) {
+    let items = ITEMS.with(|collected| std::mem::take(&mut *collected.borrow_mut()));
+    if args.ndjson {
+        for item in &items {
+            println!("{}", item);
+        }
+    } else {
+        println!("{}", Value::Array(items));
+    }
+}
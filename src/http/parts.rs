@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::http::multipart::{self, Received};
+use crate::http::range::MAX_MULTIPART_SIZE;
+use crate::json::Value;
+
+/// Media types whose parts `--extract-parts` saves
+const MEDIA_TYPES: [&str; 2] = ["multipart/mixed", "multipart/form-data"];
+
+/// File in the `--extract-parts` directory listing the saved parts
+pub const MANIFEST: &str = "index.json";
+
+/// Save each part of a multipart response as its own file in `dir`, for `--extract-parts`.
+///
+/// Parts are named by `file_name` and listed, in the order received, in
+/// `index.json`: the file, the form field name, the Content-Type, and the size.
+///
+/// # Arguments
+///
+/// * `headers` - The response head.
+/// * `body` - The response body.
+/// * `dir` - The directory to write to; it is created if needed.
+/// * `args` - A reference to an `Args` struct containing the output options.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if every part was saved, or an error message if unsuccessful.
+pub fn extract(headers: &[u8], body: &mut dyn Read, dir: &str, args: &Args) -> Result<(), String> {
+    let boundary = multipart::find_boundary(headers, &MEDIA_TYPES).ok_or_else(|| {
+        let content_type = crate::http::response::find_header(headers, "content-type")
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+            .unwrap_or_else(|| "none".to_string());
+        format!(
+            "--extract-parts needs a multipart/mixed or multipart/form-data response, got Content-Type: {}",
+            content_type
+        )
+    })?;
+    let mut multipart = Vec::new();
+    body.take(MAX_MULTIPART_SIZE + 1)
+        .read_to_end(&mut multipart)
+        .map_err(|err| format!("Read error: {}", err))?;
+    if multipart.len() as u64 > MAX_MULTIPART_SIZE {
+        return Err("Multipart response too large".to_string());
+    }
+    let parts = multipart::split(&multipart, &boundary)?;
+
+    fs::create_dir_all(dir).map_err(|err| format!("Failed to create '{}': {}", dir, err))?;
+    let mut used = vec![MANIFEST.to_string()];
+    let mut manifest = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        let name = unique(file_name(part, index + 1), &used);
+        let path = Path::new(dir).join(&name);
+        fs::write(&path, &part.data)
+            .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+        if !args.silent {
+            eprintln!("Part {}: {} ({} bytes)", index + 1, name, part.data.len());
+        }
+        let optional = |value: Option<String>| value.map(Value::String).unwrap_or(Value::Null);
+        manifest.push(Value::Object(vec![
+            ("file".to_string(), Value::String(name.clone())),
+            ("name".to_string(), optional(disposition(part, "name"))),
+            (
+                "content_type".to_string(),
+                optional(part.header("content-type").map(str::to_string)),
+            ),
+            ("size".to_string(), Value::Number(part.data.len() as f64)),
+        ]));
+        used.push(name);
+    }
+    let path = Path::new(dir).join(MANIFEST);
+    fs::write(&path, format!("{}\n", Value::Array(manifest)))
+        .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+    if !args.silent {
+        eprintln!("Saved {} part(s) to {}", parts.len(), dir);
+    }
+    Ok(())
+}
+
+/// The file name a part is saved under.
+///
+/// The `filename*` or `filename` of its Content-Disposition comes first, then the
+/// form field `name` (with an extension for its Content-Type), then `part-N`. Like
+/// `-O`, the name never leaves the directory: only its last path segment is kept, and
+/// control characters become `_`.
+pub fn file_name(part: &Received, number: usize) -> String {
+    let extension = || extension_for(part.header("content-type").unwrap_or(""));
+    let name = match disposition(part, "filename*")
+        .and_then(|value| decode_extended(&value))
+        .or_else(|| disposition(part, "filename"))
+    {
+        Some(filename) => Some(safe_name(&filename)),
+        None => disposition(part, "name")
+            .map(|name| safe_name(&name))
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if name.contains('.') {
+                    name
+                } else {
+                    format!("{}.{}", name, extension())
+                }
+            }),
+    };
+    name.filter(|name| !name.is_empty() && name != "." && name != "..")
+        .unwrap_or_else(|| format!("part-{}.{}", number, extension()))
+}
+
+/// Parameter `name` of the part's Content-Disposition.
+fn disposition(part: &Received, name: &str) -> Option<String> {
+    let value = part.header("content-disposition")?;
+    let (_, params) = value.split_once(';')?;
+    multipart::param(params, name)
+}
+
+/// Decode an RFC 8187 value such as `UTF-8''na%C3%AFve.txt`.
+fn decode_extended(value: &str) -> Option<String> {
+    let (charset, rest) = value.split_once('\'')?;
+    let (_, encoded) = rest.split_once('\'')?;
+    if !charset.eq_ignore_ascii_case("utf-8") {
+        return None;
+    }
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = encoded.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// The last path segment of `name`, with control characters replaced.
+fn safe_name(name: &str) -> String {
+    let segment = name.rsplit(['/', '\\']).next().unwrap_or("");
+    segment
+        .trim()
+        .chars()
+        .map(|c| if c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// `name`, or `name` with `-2`, `-3`, ... before its extension if it is taken.
+pub fn unique(name: String, used: &[String]) -> String {
+    if !used.contains(&name) {
+        return name;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name.as_str(), String::new()),
+    };
+    (2..)
+        .map(|n| format!("{}-{}{}", stem, n, extension))
+        .find(|candidate| !used.contains(candidate))
+        .unwrap_or(name)
+}
+
+/// A file extension for `content_type`, `bin` if it is not a common one.
+fn extension_for(content_type: &str) -> &'static str {
+    let media_type = content_type.split(';').next().unwrap_or("").trim();
+    match media_type.to_ascii_lowercase().as_str() {
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "application/pdf" => "pdf",
+        "text/plain" => "txt",
+        "text/html" => "html",
+        "text/csv" => "csv",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "application/zip" => "zip",
+        _ => "bin",
+    }
+}
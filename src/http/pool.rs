@@ -0,0 +1,147 @@
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use crate::http::transport::Transport;
+use crate::http::wire::{self, Direction};
+/// Idle connections kept at most; the oldest is closed first
+const MAX_IDLE: usize = 4;
+
+use crate::tls::{self, TlsStream};
+
+/// An open connection to an origin, or an HTTP/2 response read through one.
+pub enum Connection {
+    Plain(Box<dyn Transport>),
+    Tls(Box<TlsStream>),
+    /// An HTTP/2 response as an HTTP/1.1-style head and body; never pooled
+    #[cfg(feature = "http2")]
+    Http2(Box<dyn Read + Send>),
+}
+
+impl Connection {
+    /// Set the read and write timeouts of the underlying socket; an HTTP/2 response keeps those of its connection.
+    pub fn set_timeouts(&self, read: Option<Duration>, write: Option<Duration>) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.set_timeouts(read, write),
+            Connection::Tls(stream) => tls::transport(stream).set_timeouts(read, write),
+            #[cfg(feature = "http2")]
+            Connection::Http2(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = match self {
+            Connection::Plain(stream) => stream.read(buf)?,
+            Connection::Tls(stream) => stream.read(buf)?,
+            // The frames were traced as the connection underneath read them
+            #[cfg(feature = "http2")]
+            Connection::Http2(response) => return response.read(buf),
+        };
+        wire::trace(Direction::Recv, &buf[..n]);
+        Ok(n)
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = match self {
+            Connection::Plain(stream) => stream.write(buf)?,
+            Connection::Tls(stream) => stream.write(buf)?,
+            #[cfg(feature = "http2")]
+            Connection::Http2(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot write to an HTTP/2 response",
+                ))
+            }
+        };
+        wire::trace(Direction::Send, &buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            Connection::Tls(stream) => stream.flush(),
+            #[cfg(feature = "http2")]
+            Connection::Http2(_) => Ok(()),
+        }
+    }
+}
+
+/// Identifies the connections that can serve a request: host, port, and whether TLS is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Key {
+    pub fn new(host: &str, port: u16, tls: bool) -> Self {
+        Key {
+            host: host.to_ascii_lowercase(),
+            port,
+            tls,
+        }
+    }
+}
+
+thread_local! {
+    static IDLE: RefCell<Vec<(Key, Connection)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Take an idle connection for `key` out of the pool.
+pub fn checkout(key: &Key) -> Option<Connection> {
+    IDLE.with(|idle| {
+        let mut idle = idle.borrow_mut();
+        let index = idle.iter().position(|(k, _)| k == key)?;
+        Some(idle.remove(index).1)
+    })
+}
+
+/// Return a connection whose response was read completely, so a later request can reuse it.
+///
+/// HTTP/2 responses are discarded.
+pub fn checkin(key: Key, connection: Connection) {
+    #[cfg(feature = "http2")]
+    if matches!(connection, Connection::Http2(_)) {
+        return;
+    }
+    IDLE.with(|idle| {
+        let mut idle = idle.borrow_mut();
+        if idle.len() >= MAX_IDLE {
+            idle.remove(0);
+        }
+        idle.push((key, connection));
+    });
+}
+
+/// Check whether the server lets the connection stay open after `headers`.
+///
+/// HTTP/1.1 connections persist unless `Connection: close` is sent; HTTP/1.0
+/// connections only with `Connection: keep-alive`. A response with both
+/// Transfer-Encoding and Content-Length is framed ambiguously, so its connection
+/// is closed (RFC 9112, section 6.3).
+pub fn allows_reuse(headers: &[u8]) -> bool {
+    let connection = crate::http::response::find_header(headers, "connection")
+        .map(|value| String::from_utf8_lossy(value).to_ascii_lowercase());
+    let has = |token: &str| {
+        connection
+            .as_deref()
+            .is_some_and(|value| value.split(',').any(|t| t.trim() == token))
+    };
+
+    let find = crate::http::response::find_header;
+    if find(headers, "transfer-encoding").is_some() && find(headers, "content-length").is_some() {
+        false
+    } else if headers.starts_with(b"HTTP/1.1") {
+        !has("close")
+    } else if headers.starts_with(b"HTTP/1.0") {
+        has("keep-alive")
+    } else {
+        false
+    }
+}
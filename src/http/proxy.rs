@@ -0,0 +1,406 @@
+use std::env;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+use crate::args::Args;
+use crate::http::{resolve, response};
+use crate::output::log;
+use rurl::request::base64_encode;
+use rurl::url::Url;
+
+/// Upper bound for the CONNECT response header block
+const MAX_CONNECT_RESPONSE: usize = 64 * 1024;
+
+/// The Tor client's SOCKS port, used by `--tor`
+pub const TOR_PROXY: &str = "socks5h://127.0.0.1:9050";
+
+/// The protocol spoken to a `-x/--proxy` proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// An HTTP proxy: requests in absolute form, CONNECT for HTTPS
+    Http,
+    /// SOCKS5 with the target resolved locally
+    Socks5,
+    /// SOCKS5 with the target host name resolved by the proxy
+    Socks5h,
+}
+
+/// Parse a `-x/--proxy` value into its kind, host, and port.
+///
+/// The scheme is optional and defaults to `http://`; the port defaults to 1080 like curl.
+///
+/// # Arguments
+///
+/// * `proxy` - The proxy URL, e.g. `http://proxy:3128`, `proxy:3128`, or `socks5h://127.0.0.1:9050`.
+///
+/// # Returns
+///
+/// * `Result<(Kind, String, u16), &'static str>` - The proxy kind, host, and port if successful, or an error message if unsuccessful.
+pub fn parse(proxy: &str) -> Result<(Kind, String, u16), &'static str> {
+    if proxy.starts_with("https://") {
+        return Err("HTTPS proxies are not supported");
+    }
+    let (kind, rest) = match proxy.split_once("://") {
+        None => (Kind::Http, proxy),
+        Some(("http", rest)) => (Kind::Http, rest),
+        Some(("socks5", rest)) => (Kind::Socks5, rest),
+        Some(("socks5h", rest)) => (Kind::Socks5h, rest),
+        Some(_) => return Err("Unsupported proxy scheme"),
+    };
+
+    let authority = rest.split('/').next().unwrap_or("");
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| "Invalid proxy port")?),
+        None => (authority, 1080),
+    };
+    if host.is_empty() {
+        return Err("Invalid proxy host");
+    }
+
+    Ok((kind, host.to_string(), port))
+}
+
+/// Proxies named by the environment, read once at startup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Env {
+    /// `http_proxy`; only the lowercase name, since CGI servers set `HTTP_PROXY` from a request header
+    pub http: Option<String>,
+    /// `https_proxy` or `HTTPS_PROXY`
+    pub https: Option<String>,
+    /// `all_proxy` or `ALL_PROXY`, for URLs whose scheme has no proxy of its own
+    pub all: Option<String>,
+}
+
+impl Env {
+    /// Read the proxy variables, ignoring empty ones.
+    pub fn read() -> Env {
+        let var = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| env::var(name).ok())
+                .find(|value| !value.is_empty())
+        };
+        Env {
+            http: var(&["http_proxy"]),
+            https: var(&["https_proxy", "HTTPS_PROXY"]),
+            all: var(&["all_proxy", "ALL_PROXY"]),
+        }
+    }
+}
+
+/// Split a `--noproxy` or `no_proxy` list into its entries.
+pub fn parse_noproxy(list: &str) -> Vec<String> {
+    list.split([',', ' '])
+        .map(|entry| entry.trim().to_ascii_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Check whether `host` is exempt from proxying by a `no_proxy` entry.
+///
+/// `*` matches every host. A name matches itself and its subdomains, with or
+/// without a leading dot (`example.com` and `.example.com` both match
+/// `api.example.com`). An address matches itself, and a CIDR block such as
+/// `10.0.0.0/8` or `fd00::/8` matches the addresses inside it.
+///
+/// # Arguments
+///
+/// * `host` - The host of the request URL.
+/// * `noproxy` - The entries from `--noproxy` or `no_proxy`.
+///
+/// # Returns
+///
+/// * `bool` - `true` if the request should go direct.
+pub fn is_bypassed(host: &str, noproxy: &[String]) -> bool {
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    let addr = host.parse::<IpAddr>().ok();
+    noproxy.iter().any(|entry| {
+        if entry == "*" {
+            return true;
+        }
+        if let Some((network, prefix)) = entry.split_once('/') {
+            return match (addr, network.parse::<IpAddr>(), prefix.parse::<u32>()) {
+                (Some(addr), Ok(network), Ok(prefix)) => in_block(addr, network, prefix),
+                _ => false,
+            };
+        }
+        let entry = entry
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_start_matches('.');
+        host == entry || host.ends_with(&format!(".{}", entry))
+    })
+}
+
+/// Whether `addr` lies in the block of `network` with a `prefix`-bit mask.
+fn in_block(addr: IpAddr, network: IpAddr, prefix: u32) -> bool {
+    let (addr, network, bits) = match (addr, network) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => (u32::from(a) as u128, u32::from(n) as u128, 32),
+        (IpAddr::V6(a), IpAddr::V6(n)) => (u128::from(a), u128::from(n), 128),
+        _ => return false,
+    };
+    if prefix > bits {
+        return false;
+    }
+    let shift = bits - prefix;
+    shift >= bits || addr >> shift == network >> shift
+}
+
+/// The proxy for the request to `args.url`, if any.
+///
+/// `--noproxy` (or `no_proxy`) exempts hosts from every proxy. Otherwise `-x/--proxy`
+/// is used, and without it the environment's proxy for the URL's scheme.
+pub fn for_url(args: &Args) -> Option<&str> {
+    if args.unix_socket.is_some() {
+        return None;
+    }
+    let url = Url::parse(&args.url).ok()?;
+    if is_bypassed(&url.host_str(), &args.noproxy) {
+        return None;
+    }
+    if let Some(proxy) = &args.proxy {
+        return Some(proxy);
+    }
+    let scheme_proxy = if url.is_https() {
+        &args.env_proxy.https
+    } else {
+        &args.env_proxy.http
+    };
+    scheme_proxy.as_deref().or(args.env_proxy.all.as_deref())
+}
+
+/// The kind of the proxy for the request, if one is configured and valid.
+pub fn kind(args: &Args) -> Option<Kind> {
+    let proxy = for_url(args)?;
+    parse(proxy).ok().map(|(kind, _, _)| kind)
+}
+
+/// The `Proxy-Authorization` header value for `--proxy-user`, if given.
+pub fn authorization(args: &Args) -> Option<String> {
+    args.proxy_user
+        .as_ref()
+        .map(|user| format!("Basic {}", base64_encode(user.as_bytes())))
+}
+
+/// Whether the request for `args.url` is sent to the proxy in absolute form (plain HTTP through a proxy).
+pub fn is_forwarded(args: &Args) -> bool {
+    kind(args) == Some(Kind::Http) && !args.url.starts_with("https://")
+}
+
+/// Send a CONNECT request for `host:port` through an established proxy connection.
+///
+/// The proxy's response is read byte by byte so no bytes of the tunnelled
+/// stream are consumed. A 2xx status means the tunnel is open.
+///
+/// # Arguments
+///
+/// * `stream` - The connection to the proxy.
+/// * `host` - The target host.
+/// * `port` - The target port.
+/// * `args` - A reference to an `Args` struct containing the proxy options.
+///
+/// # Returns
+///
+/// * `Result<(u16, Vec<u8>), String>` - The proxy's status code and response headers if successful, or an error message if unsuccessful.
+pub fn connect_tunnel(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    args: &Args,
+) -> Result<(u16, Vec<u8>), String> {
+    let authority = format!("{}:{}", host, port);
+
+    let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+    if let Some(user_agent) = &args.user_agent {
+        request.push_str(&format!("User-Agent: {}\r\n", user_agent));
+    }
+    if let Some(credentials) = authorization(args) {
+        request.push_str(&format!("Proxy-Authorization: {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|err| format!("Write error: {}", err))?;
+
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_CONNECT_RESPONSE {
+            return Err("Proxy CONNECT response too large".to_string());
+        }
+        match stream.read(&mut byte) {
+            Ok(0) => return Err("Proxy closed the connection during CONNECT".to_string()),
+            Ok(_) => head.push(byte[0]),
+            Err(err) => return Err(format!("Read error: {}", err)),
+        }
+    }
+
+    let status = response::parse_status_line(&head)?;
+    if (200..300).contains(&status) {
+        log::verbose(
+            args,
+            format_args!("CONNECT tunnel to {} established", authority),
+        );
+    }
+
+    Ok((status, head))
+}
+
+/// The target URL in absolute form, as sent to a proxy.
+pub fn absolute_target(url: &Url) -> String {
+    format!(
+        "{}://{}:{}{}",
+        url.scheme(),
+        url.host(),
+        url.port(),
+        url.request_target()
+    )
+}
+
+/// Ask a SOCKS5 proxy to connect to `host:port` over an established connection (RFC 1928).
+///
+/// With `Kind::Socks5h` the host name is sent to the proxy and never resolved
+/// locally; with `Kind::Socks5` it is resolved first and the address is sent.
+/// `--proxy-user` credentials are offered as username/password authentication
+/// (RFC 1929).
+///
+/// # Arguments
+///
+/// * `stream` - The connection to the proxy.
+/// * `kind` - `Kind::Socks5` or `Kind::Socks5h`.
+/// * `host` - The target host.
+/// * `port` - The target port.
+/// * `args` - A reference to an `Args` struct containing the proxy options.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once the proxy connected, or an error message if it refused.
+pub fn socks5_connect(
+    stream: &mut TcpStream,
+    kind: Kind,
+    host: &str,
+    port: u16,
+    args: &Args,
+) -> Result<(), String> {
+    let io_error = |err: std::io::Error| format!("SOCKS5 proxy error: {}", err);
+
+    // Greeting: no authentication, and username/password when credentials are given
+    let methods: &[u8] = if args.proxy_user.is_some() {
+        &[0x00, 0x02]
+    } else {
+        &[0x00]
+    };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).map_err(io_error)?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).map_err(io_error)?;
+    match reply {
+        [0x05, 0x00] => {}
+        [0x05, 0x02] => {
+            let user = args.proxy_user.as_deref().unwrap_or("");
+            let (name, password) = user.split_once(':').unwrap_or((user, ""));
+            if name.len() > 255 || password.len() > 255 {
+                return Err("SOCKS5 credentials are longer than 255 bytes".to_string());
+            }
+            let mut request = vec![0x01, name.len() as u8];
+            request.extend_from_slice(name.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).map_err(io_error)?;
+            stream.read_exact(&mut reply).map_err(io_error)?;
+            if reply[1] != 0x00 {
+                return Err("SOCKS5 proxy rejected the --proxy-user credentials".to_string());
+            }
+        }
+        [0x05, _] => {
+            return Err(
+                "SOCKS5 proxy accepts none of the offered authentication methods".to_string(),
+            )
+        }
+        _ => return Err("Not a SOCKS5 proxy".to_string()),
+    }
+
+    // CONNECT, by name or by a locally resolved address
+    let mut request = vec![0x05, 0x01, 0x00];
+    let literal = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok();
+    let address = match (literal, kind) {
+        (Some(ip), _) => Some(ip),
+        (None, Kind::Socks5h) => None,
+        (None, _) => Some(resolve::resolve(host, port, args)?[0].ip()),
+    };
+    match address {
+        Some(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Some(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        None => {
+            if host.len() > 255 {
+                return Err("Host name is too long for SOCKS5".to_string());
+            }
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).map_err(io_error)?;
+
+    // The reply ends with the address the proxy bound, whose length depends on its type
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).map_err(io_error)?;
+    if head[1] != 0x00 {
+        return Err(format!(
+            "SOCKS5 proxy could not connect to {}:{}: {}",
+            host,
+            port,
+            socks5_reply(head[1])
+        ));
+    }
+    let bound = match head[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut length = [0u8; 1];
+            stream.read_exact(&mut length).map_err(io_error)?;
+            length[0] as usize
+        }
+        _ => return Err("Invalid SOCKS5 reply".to_string()),
+    };
+    let mut rest = vec![0u8; bound + 2];
+    stream.read_exact(&mut rest).map_err(io_error)?;
+
+    log::verbose(
+        args,
+        format_args!("SOCKS5 tunnel to {}:{} established", host, port),
+    );
+    Ok(())
+}
+
+/// Describe a SOCKS5 reply code.
+fn socks5_reply(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
@@ -0,0 +1,69 @@
+use crate::http::multipart;
+
+/// Largest multipart/byteranges body that is split into its parts
+pub const MAX_MULTIPART_SIZE: u64 = 64 * 1024 * 1024;
+
+/// One part of a multipart/byteranges response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// The part's `Content-Range`, e.g. `bytes 0-99/1000`
+    pub content_range: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Check a `-r` value and return it without whitespace.
+///
+/// The value is a comma-separated list of byte ranges: `first-last`, `first-`
+/// (to the end), or `-suffix` (the last `suffix` bytes).
+///
+/// # Returns
+///
+/// * `Result<String, &'static str>` - The range list if valid, or an error message if unsuccessful.
+pub fn parse_spec(spec: &str) -> Result<String, &'static str> {
+    let mut ranges = Vec::new();
+    for range in spec.split(',') {
+        let range = range.trim();
+        let (first, last) = range.split_once('-').ok_or("Invalid range")?;
+        let first = (!first.is_empty())
+            .then(|| first.parse::<u64>())
+            .transpose()
+            .map_err(|_| "Invalid range")?;
+        let last = (!last.is_empty())
+            .then(|| last.parse::<u64>())
+            .transpose()
+            .map_err(|_| "Invalid range")?;
+        match (first, last) {
+            (None, None) => return Err("Invalid range"),
+            (Some(first), Some(last)) if last < first => return Err("Invalid range"),
+            _ => {}
+        }
+        ranges.push(range);
+    }
+    Ok(ranges.join(","))
+}
+
+/// The boundary of a `multipart/byteranges` response, if it is one.
+pub fn multipart_boundary(headers: &[u8]) -> Option<String> {
+    multipart::find_boundary(headers, &["multipart/byteranges"])
+}
+
+/// Split a `multipart/byteranges` body into its parts.
+///
+/// # Arguments
+///
+/// * `body` - The complete response body.
+/// * `boundary` - The boundary from the Content-Type.
+///
+/// # Returns
+///
+/// * `Result<Vec<Part>, String>` - The parts in the order received if successful, or an error message if the body is malformed or truncated.
+pub fn split_parts(body: &[u8], boundary: &str) -> Result<Vec<Part>, String> {
+    let parts = multipart::split(body, boundary)?;
+    Ok(parts
+        .into_iter()
+        .map(|part| Part {
+            content_range: part.header("content-range").map(str::to_string),
+            data: part.data,
+        })
+        .collect())
+}
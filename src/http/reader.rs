@@ -0,0 +1,361 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+use std::thread;
+use std::time::Duration;
+
+use crate::http::response;
+use crate::output::log::{Level, Logger};
+use rurl::chunked;
+use rurl::method::Method;
+use rurl::status::StatusCode;
+
+/// Default size of the read buffer
+pub const DEFAULT_BUFFER_SIZE: usize = 128 * 1024;
+
+/// Upper bound for adaptive read buffer growth
+const MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Upper bound for the header block, so a misbehaving server cannot exhaust memory
+const MAX_HEADER_SIZE: usize = 1024 * 1024;
+
+/// Limit attempts to avoid infinite loops while waiting for the first bytes
+const MAX_ATTEMPTS: usize = 50;
+
+/// Timeouts tolerated before a close-delimited body is considered complete
+const MAX_IDLE_ATTEMPTS: usize = 5;
+
+/// How the end of the body is determined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    /// Content-Length, with the number of bytes still expected
+    Length(u64),
+    /// Transfer-Encoding: chunked, decoded by `ResponseReader::chunks`
+    Chunked,
+    /// Neither, so the body runs until the connection closes
+    UntilClose,
+}
+
+/// Whether the response to `method` with header block `headers` has no body (RFC 9112, section 6.3).
+fn has_no_body(method: &Method, headers: &[u8]) -> bool {
+    let Ok(status) = response::parse_status(headers) else {
+        return false;
+    };
+    let code = status.as_u16();
+    *method == Method::Head
+        || (100..200).contains(&code)
+        || code == 204
+        || code == 304
+        || (*method == Method::Connect && status.is_success())
+}
+
+/// Streaming reader for an HTTP/1.x response.
+///
+/// `read_head` returns the header block; the reader then implements `Read` over the
+/// decoded body, so callers can copy it to stdout or a file as it arrives without
+/// buffering the whole response. The read buffer starts at `buffer_size` and doubles
+/// whenever a read fills it completely, so fast transfers need fewer read calls.
+///
+/// Verbose diagnostics go to stderr because they can occur while the body streams to
+/// stdout; only interim responses, which come before the final head, go to stdout
+/// like the other response details.
+pub struct ResponseReader<R: Read> {
+    stream: R,
+    pending: Vec<u8>,
+    pos: usize,
+    buffer: Vec<u8>,
+    max_buffer_size: usize,
+    framing: Framing,
+    chunks: chunked::Decoder,
+    complete: bool,
+    logger: Logger,
+}
+
+impl<R: Read> ResponseReader<R> {
+    /// Create a reader over `stream` with an initial read buffer of `buffer_size` bytes.
+    pub fn new(stream: R, buffer_size: usize, logger: Logger) -> Self {
+        let buffer = vec![0u8; buffer_size.max(1)];
+        ResponseReader {
+            stream,
+            pending: Vec::new(),
+            pos: 0,
+            max_buffer_size: buffer.len().max(MAX_BUFFER_SIZE),
+            buffer,
+            framing: Framing::UntilClose,
+            chunks: chunked::Decoder::new(),
+            complete: false,
+            logger,
+        }
+    }
+
+    /// Read the status line and headers, and set up body framing from them.
+    ///
+    /// Interim 1xx responses such as 100 Continue and 103 Early Hints are consumed
+    /// until the final response arrives; in verbose mode the headers of Early Hints
+    /// are shown. 101 Switching Protocols is final.
+    ///
+    /// A response to HEAD, a 1xx, 204, or 304 status, and a successful CONNECT have
+    /// no body whatever their Content-Length says, so reading stops after the headers
+    /// instead of waiting for bytes that never come. `Transfer-Encoding: chunked`
+    /// overrides Content-Length (RFC 9112, section 6.3).
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The method of the request being answered.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, String>` - The header block including the blank line if successful, or an error message if unsuccessful.
+    pub fn read_head(&mut self, method: &Method) -> Result<Vec<u8>, String> {
+        let headers = loop {
+            let headers = self.read_block()?;
+            match response::parse_status(&headers) {
+                Ok(status) if status.is_informational() && status.as_u16() != 101 => {
+                    self.interim(status, &headers)
+                }
+                _ => break headers,
+            }
+        };
+
+        self.framing = if has_no_body(method, &headers) {
+            Framing::Length(0)
+        } else if response::is_chunked_transfer(&headers) {
+            if response::get_content_length(&headers).is_some() {
+                self.verbose(format_args!(
+                    "Ignoring Content-Length since Transfer-Encoding: chunked is set"
+                ));
+            }
+            Framing::Chunked
+        } else if let Some(length) = response::get_content_length(&headers) {
+            self.verbose(format_args!("Response Content-Length: {} bytes", length));
+            Framing::Length(length as u64)
+        } else {
+            // If no content-length and not chunked, rely on connection close
+            Framing::UntilClose
+        };
+
+        Ok(headers)
+    }
+
+    /// Report an interim response in verbose mode, with the headers of Early Hints.
+    fn interim(&self, status: StatusCode, headers: &[u8]) {
+        let logger = &self.logger;
+        logger.log(Level::Verbose, format_args!("Interim response: {}", status));
+        if status.as_u16() == 103 {
+            let text = String::from_utf8_lossy(headers);
+            for line in text.lines().skip(1).filter(|line| !line.is_empty()) {
+                logger.log(Level::Verbose, format_args!("Early Hints: {}", line));
+            }
+        }
+    }
+
+    /// Print a verbose diagnostic to stderr.
+    fn verbose(&self, message: fmt::Arguments) {
+        self.logger.log_err(Level::Verbose, message);
+    }
+
+    /// Read the next header block, up to and including its blank line.
+    fn read_block(&mut self) -> Result<Vec<u8>, String> {
+        // Drop the previous block so scanning starts at the next one
+        self.pending.drain(..self.pos);
+        self.pos = 0;
+        let mut attempts = 0;
+        let mut scanned = 0;
+
+        let header_end = loop {
+            // Scan only the newly read bytes (plus a possible partial terminator)
+            if let Some(pos) = response::find_header_end(&self.pending[scanned..]) {
+                break scanned + pos;
+            }
+            scanned = self.pending.len().saturating_sub(3);
+
+            if self.pending.len() > MAX_HEADER_SIZE {
+                return Err("Response headers too large".to_string());
+            }
+
+            match self.fill() {
+                Ok(0) => {
+                    if !self.pending.is_empty() {
+                        return Err("Invalid HTTP response".to_string());
+                    }
+                    attempts += 1;
+                    if attempts >= MAX_ATTEMPTS {
+                        return Err("No response received after maximum attempts".to_string());
+                    }
+                    self.verbose(format_args!("No data received, retrying..."));
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Ok(_) => attempts = 0,
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    // On macOS, a read timeout can surface as EAGAIN (Resource temporarily unavailable)
+                    attempts += 1;
+                    if attempts >= MAX_ATTEMPTS {
+                        return Err("No response received after maximum attempts".to_string());
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(err) => return Err(format!("Read error: {}", err)),
+            }
+        };
+
+        let headers = self.pending[..header_end].to_vec();
+        self.pos = header_end;
+        Ok(headers)
+    }
+
+    /// Hand back the stream for another request once the body has been read to its end.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<R>` - The stream, or None if the body is unfinished, ends with the connection, or was followed by unexpected bytes.
+    pub fn into_reusable(self) -> Option<R> {
+        let finished = match self.framing {
+            Framing::Length(remaining) => remaining == 0,
+            Framing::Chunked => self.chunks.is_done(),
+            Framing::UntilClose => false,
+        };
+        (finished && self.pos == self.pending.len()).then_some(self.stream)
+    }
+
+    /// Read more bytes from the stream into `pending`, growing the buffer on full reads.
+    fn fill(&mut self) -> io::Result<usize> {
+        if self.pos > 0 {
+            self.pending.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        loop {
+            match self.stream.read(&mut self.buffer) {
+                Ok(n) => {
+                    self.pending.extend_from_slice(&self.buffer[..n]);
+
+                    // A full buffer means more data is already waiting, so read more per call
+                    if n == self.buffer.len() && self.buffer.len() < self.max_buffer_size {
+                        let grown = (self.buffer.len() * 2).min(self.max_buffer_size);
+                        self.buffer.resize(grown, 0);
+                        self.verbose(format_args!("Read buffer grown to {} bytes", grown));
+                    }
+                    return Ok(n);
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Make sure unconsumed bytes are available, returning false at end of stream.
+    fn ensure_data(&mut self) -> io::Result<bool> {
+        let mut attempts = 0;
+        while self.pos == self.pending.len() {
+            match self.fill() {
+                Ok(0) => return Ok(false),
+                Ok(_) => {}
+                Err(e)
+                    if self.framing == Framing::UntilClose
+                        && (e.kind() == ErrorKind::WouldBlock
+                            || e.kind() == ErrorKind::TimedOut) =>
+                {
+                    attempts += 1;
+                    if attempts >= MAX_IDLE_ATTEMPTS {
+                        self.verbose(format_args!(
+                            "No more data after {} attempts, considering response complete",
+                            attempts
+                        ));
+                        return Ok(false);
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e)
+                    if self.framing == Framing::UntilClose
+                        && (e.kind() == ErrorKind::UnexpectedEof
+                            || e.kind() == ErrorKind::ConnectionReset) =>
+                {
+                    // Servers often close without a TLS close_notify or with a reset
+                    return Ok(false);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Copy up to `limit` unconsumed bytes into `out`.
+    fn take(&mut self, out: &mut [u8], limit: u64) -> usize {
+        let available = self.pending.len() - self.pos;
+        let n = out
+            .len()
+            .min(available)
+            .min(usize::try_from(limit).unwrap_or(usize::MAX));
+        out[..n].copy_from_slice(&self.pending[self.pos..self.pos + n]);
+        self.pos += n;
+        n
+    }
+
+    /// Mark the body as complete, reporting why in verbose mode.
+    fn finish(&mut self, reason: &str) {
+        if !self.complete {
+            self.complete = true;
+            self.verbose(format_args!("{}", reason));
+        }
+    }
+}
+
+impl<R: Read> Read for ResponseReader<R> {
+    /// Read decoded body bytes; call `read_head` first.
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.framing {
+                Framing::Length(0) => {
+                    self.finish("Response complete based on Content-Length");
+                    return Ok(0);
+                }
+                Framing::Length(remaining) => {
+                    if !self.ensure_data()? {
+                        return Err(io::Error::new(
+                            ErrorKind::UnexpectedEof,
+                            format!("connection closed with {} bytes remaining", remaining),
+                        ));
+                    }
+                    let n = self.take(out, remaining);
+                    self.framing = Framing::Length(remaining - n as u64);
+                    return Ok(n);
+                }
+                Framing::UntilClose => {
+                    if !self.ensure_data()? {
+                        self.finish("Response complete (connection closed)");
+                        return Ok(0);
+                    }
+                    return Ok(self.take(out, u64::MAX));
+                }
+                Framing::Chunked => {
+                    if self.chunks.is_done() {
+                        self.finish("Chunked response complete");
+                        return Ok(0);
+                    }
+                    if self.pos == self.pending.len() && self.fill()? == 0 {
+                        return Err(io::Error::new(
+                            ErrorKind::UnexpectedEof,
+                            format!("connection closed before {}", self.chunks.expected()),
+                        ));
+                    }
+                    let (used, n) = self
+                        .chunks
+                        .decode(&self.pending[self.pos..], out)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?;
+                    self.pos += used;
+
+                    // Trailer fields are only reported; they carry nothing the body needs
+                    for (name, value) in self.chunks.take_trailers() {
+                        self.verbose(format_args!("Trailer: {}: {}", name, value));
+                    }
+                    if n > 0 {
+                        return Ok(n);
+                    }
+                }
+            }
+        }
+    }
+}
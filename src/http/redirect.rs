@@ -0,0 +1,283 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::args::Args;
+use crate::http::{paginate, response};
+use crate::output::log;
+use rurl::method::Method;
+use rurl::status::StatusCode;
+use rurl::url::Url;
+
+/// Why a redirect hop was refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockReason {
+    /// The hop uses a protocol outside the redirect allow-list.
+    Protocol(String),
+    /// An allow-list is configured and the host is not on it.
+    HostNotAllowed(String),
+    /// The host matches an entry on the deny-list.
+    HostDenied(String),
+    /// The host resolved to a private, loopback, or link-local address.
+    PrivateAddress(IpAddr),
+}
+
+/// Error returned when a redirect hop fails the guard checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedirectBlocked {
+    /// The Location value of the offending hop.
+    pub hop: String,
+    pub reason: BlockReason,
+}
+
+impl fmt::Display for RedirectBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            BlockReason::Protocol(protocol) => write!(
+                f,
+                "Redirect blocked: protocol '{}' not allowed ({})",
+                protocol, self.hop
+            ),
+            BlockReason::HostNotAllowed(host) => write!(
+                f,
+                "Redirect blocked: host '{}' is not on the allow-list ({})",
+                host, self.hop
+            ),
+            BlockReason::HostDenied(host) => write!(
+                f,
+                "Redirect blocked: host '{}' is on the deny-list ({})",
+                host, self.hop
+            ),
+            BlockReason::PrivateAddress(addr) => write!(
+                f,
+                "Redirect blocked: private address {} ({})",
+                addr, self.hop
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RedirectBlocked {}
+
+impl From<RedirectBlocked> for String {
+    fn from(err: RedirectBlocked) -> Self {
+        err.to_string()
+    }
+}
+
+/// Canonicalize a hostname for comparison: lowercase, no trailing dot, no IPv6 brackets.
+pub fn canonical_host(host: &str) -> String {
+    host.trim_end_matches('.')
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_lowercase()
+}
+
+/// Extract the host from the part of a URL following `scheme://`.
+fn authority_host(rest: &str) -> String {
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+
+    if let Some(end) = authority.find(']') {
+        // IPv6 literal, keep the brackets for canonical_host to strip
+        return authority[..=end].to_string();
+    }
+    authority.split(':').next().unwrap_or("").to_string()
+}
+
+/// Check whether `host` equals `pattern` or is a subdomain of it.
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = canonical_host(pattern);
+    let pattern = pattern.trim_start_matches('.');
+    host == pattern || host.ends_with(&format!(".{}", pattern))
+}
+
+/// Check whether an address is private, loopback, link-local, or unspecified.
+pub fn is_private_addr(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => {
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private_addr(&IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Validate the protocol and host of a redirect hop before any connection is made.
+///
+/// # Arguments
+///
+/// * `location` - The absolute URL of the redirect target.
+/// * `args` - A reference to an `Args` struct containing the redirect policy.
+///
+/// # Returns
+///
+/// * `Result<(), RedirectBlocked>` - `Ok(())` if the hop may be followed, or the reason it was blocked.
+pub fn check_hop(location: &str, args: &Args) -> Result<(), RedirectBlocked> {
+    let blocked = |reason| RedirectBlocked {
+        hop: location.to_string(),
+        reason,
+    };
+
+    let (protocol, rest) = location.split_once("://").unwrap_or(("", location));
+    let protocol = protocol.to_lowercase();
+    if !args.redirect_protocols.contains(&protocol) {
+        return Err(blocked(BlockReason::Protocol(protocol)));
+    }
+
+    let host = canonical_host(&authority_host(rest));
+
+    if args
+        .redirect_deny_hosts
+        .iter()
+        .any(|pattern| host_matches(&host, pattern))
+    {
+        return Err(blocked(BlockReason::HostDenied(host)));
+    }
+
+    if !args.redirect_allow_hosts.is_empty()
+        && !args
+            .redirect_allow_hosts
+            .iter()
+            .any(|pattern| host_matches(&host, pattern))
+    {
+        return Err(blocked(BlockReason::HostNotAllowed(host)));
+    }
+
+    Ok(())
+}
+
+/// Reject resolved addresses that point into private networks when `--redir-deny-private` is set.
+pub fn check_addrs(
+    location: &str,
+    addrs: &[SocketAddr],
+    args: &Args,
+) -> Result<(), RedirectBlocked> {
+    if !args.redirect_deny_private {
+        return Ok(());
+    }
+
+    match addrs.iter().find(|addr| is_private_addr(&addr.ip())) {
+        Some(addr) => Err(RedirectBlocked {
+            hop: location.to_string(),
+            reason: BlockReason::PrivateAddress(addr.ip()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Whether a `status` redirect turns a `method` request into a GET without a body.
+///
+/// Like browsers and curl, 303 does so for every method but HEAD, and 301 and 302 only
+/// for POST. 307 and 308 repeat the request unchanged (RFC 9110, Section 15.4).
+pub fn switches_to_get(status: StatusCode, method: &Method) -> bool {
+    match status.as_u16() {
+        301 | 302 => *method == Method::Post,
+        303 => !matches!(method, Method::Get | Method::Head),
+        _ => false,
+    }
+}
+
+/// The choices offered by a 300 Multiple Choices response with header block `headers`.
+///
+/// The `Location` header names the server's preferred choice and comes first; the
+/// others are `Link` entries with `rel="alternate"`. Relative targets are resolved
+/// against `url`, and a target is listed once.
+pub fn alternatives(headers: &[u8], url: &str) -> Vec<String> {
+    let base = Url::parse(url).ok();
+    let mut choices: Vec<String> = Vec::new();
+    let preferred = response::get_location(headers);
+    for target in preferred
+        .into_iter()
+        .chain(paginate::find_links(headers, "alternate"))
+    {
+        let target = match base.as_ref().map(|base| base.join(&target)) {
+            Some(Ok(resolved)) => resolved.to_string(),
+            _ => target,
+        };
+        if !choices.contains(&target) {
+            choices.push(target);
+        }
+    }
+    choices
+}
+
+/// Whether two URLs share scheme, host, and port.
+pub fn same_origin(from: &str, to: &str) -> bool {
+    match (Url::parse(from), Url::parse(to)) {
+        (Ok(from), Ok(to)) => {
+            from.scheme() == to.scheme()
+                && canonical_host(&from.host_str()) == canonical_host(&to.host_str())
+                && from.port() == to.port()
+        }
+        _ => false,
+    }
+}
+
+/// Describe the request that follows a `status` redirect from `args.url` to `location`.
+///
+/// The method and body carry over unless `switches_to_get` says otherwise, in which case
+/// the body options are dropped. Credentials from `-u`, `--oauth2-bearer`, and
+/// `-H Authorization`, and cookies from a `-b name=value` literal or `-H Cookie`, are
+/// only sent to the origin they were given for; cookies from the jar follow their
+/// own domain rules.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct describing the request that was redirected.
+/// * `status` - The redirect status code.
+/// * `location` - The absolute URL of the redirect target.
+///
+/// # Returns
+///
+/// * `Args` - The arguments for the next hop.
+pub fn next_request(args: &Args, status: StatusCode, location: &str) -> Args {
+    let mut next = args.clone();
+    next.url = location.to_string();
+    // An edited request and a raw target only describe the first hop
+    next.edited_request = None;
+    next.raw_path = None;
+    next.request_target = None;
+
+    if switches_to_get(status, &args.method) {
+        log::verbose(
+            args,
+            format_args!("Switching from {} to GET after {}", args.method, status),
+        );
+        next.method = Method::Get;
+        next.data = None;
+        next.data_urlencode.clear();
+        next.form.clear();
+        next.upload_file = None;
+        next.json = false;
+        next.trailers.clear();
+    }
+
+    if !same_origin(&args.url, location) {
+        next.user = None;
+        next.oauth2_bearer = None;
+        next.cookie = None;
+        next.headers.retain(|header| {
+            let name = header.split(':').next().unwrap_or("").trim();
+            !name.eq_ignore_ascii_case("authorization") && !name.eq_ignore_ascii_case("cookie")
+        });
+    }
+    next
+}
@@ -1,86 +1,401 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+
 use crate::args::Args;
-use crate::http::url;
+use crate::clock;
+use crate::cookies::{self, CookieJar};
+use crate::editor;
+use crate::http::{multipart, proxy, response};
+use crate::netrc;
+use crate::output::log;
+use crate::progress::{self, Direction, Meter};
+use rurl::chunked;
+use rurl::request::{Request, RequestBuilder};
+use rurl::url::{self, Url};
 
-/// Build an HTTP request from the given arguments.
+/// Describe the request given by the command-line arguments.
 ///
-/// This function takes an `Args` struct and builds an HTTP request string.
+/// This is where the CLI options map onto the library's `RequestBuilder`, so the
+/// binary and library consumers share the same request construction.
 ///
 /// # Arguments
 ///
 /// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar; cookies matching the URL are sent.
 ///
 /// # Returns
 ///
-/// * `Result<Vec<u8>, &'static str>` - A vector of bytes representing the HTTP request if successful, or an error message if unsuccessful.
-pub fn build(args: &Args) -> Result<Vec<u8>, &'static str> {
-    let (host, _port, path, _) = url::parse(&args.url)?;
+/// * `Result<Request, &'static str>` - The request if successful, or an error message if unsuccessful.
+pub fn prepare(args: &Args, jar: &CookieJar) -> Result<Request, &'static str> {
+    // A request edited with --edit is sent exactly as the user left it
+    if let Some(request) = &args.edited_request {
+        return Ok(request.clone());
+    }
+
+    let mut builder = RequestBuilder::new(args.method.as_str(), &args.url)
+        .connect_timeout(args.connect_timeout)
+        .keep_alive(!args.no_keepalive)
+        .accept_trailers(true)
+        .http2(args.http2)
+        .allow_unsafe(args.allow_unsafe_headers);
+
+    // Deliberately malformed request lines for desync testing
+    if let Some(method) = &args.raw_method {
+        builder = builder.raw_method(method);
+    }
+
+    // Plain HTTP through a proxy uses the absolute form of the target
+    if proxy::is_forwarded(args) {
+        builder = builder.target(&proxy::absolute_target(&Url::parse(&args.url)?));
 
-    let mut request = format!(
-        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
-        args.method, path, host
-    );
+        // Credentials for the proxy travel with the request itself when it is not tunnelled
+        if let Some(credentials) = proxy::authorization(args) {
+            builder = builder.header("Proxy-Authorization", &credentials);
+        }
+    }
+
+    if let Some(target) = &args.raw_path {
+        builder = builder.target(target);
+    }
+
+    // OPTIONS *, CONNECT host:port, and other targets the URL cannot express
+    if let Some(target) = &args.request_target {
+        builder = builder.request_target(target);
+    }
 
     // Add User-Agent header if specified
     if let Some(user_agent) = &args.user_agent {
-        request.push_str(&format!("User-Agent: {}\r\n", user_agent));
+        builder = builder.header("User-Agent", user_agent);
     }
 
-    // Add Basic Authentication if specified
-    if let Some(user) = &args.user {
-        let encoded = base64_encode(user.as_bytes());
-        request.push_str(&format!("Authorization: Basic {}\r\n", encoded));
+    // Stamp the request with --date-header, on the clock corrected by --clock-offset
+    if args.date_header && !has_header(args, "date") {
+        let now = clock::request_time(args.clock.as_ref(), args.clock_offset);
+        builder = builder.header("Date", &clock::http_date(now));
+    }
+
+    // Add the bearer token, or else Basic Authentication from -u, the URL's
+    // user:password@, or the netrc file, in that order
+    if let Some(token) = &args.oauth2_bearer {
+        builder = builder.bearer_auth(token);
+    } else if let Some(user) = &args.user {
+        builder = builder.basic_auth(user, None);
+    } else if let Ok(url) = Url::parse(&args.url) {
+        // A netrc entry fills in the password for a URL user, or both for a bare URL
+        let user = url.username();
+        let machine = netrc::find(&args.netrc, &url.host_str(), user.as_deref())
+            .filter(|_| !has_header(args, "authorization"));
+        match (user, machine) {
+            (Some(user), machine) => {
+                let password = url
+                    .password()
+                    .or_else(|| machine.and_then(|machine| machine.password.clone()));
+                builder = builder.basic_auth(&user, password.as_deref());
+            }
+            (None, Some(machine)) => {
+                if let Some(login) = &machine.login {
+                    builder = builder.basic_auth(login, machine.password.as_deref());
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Ask for byte ranges given with -r
+    if let Some(range) = &args.range {
+        builder = builder.header("Range", &format!("bytes={}", range));
+    }
+
+    // Add cookies from -b and the jar
+    if let Some(cookie) = cookies::request_header(args, jar) {
+        builder = builder.header("Cookie", &cookie);
+    }
+
+    // --json asks for JSON back unless -H says otherwise
+    if args.json && !has_header(args, "accept") {
+        builder = builder.header("Accept", "application/json");
     }
 
     // Add headers
     for header in &args.headers {
-        request.push_str(&format!("{}\r\n", header));
+        builder = builder.header_line(header);
     }
 
-    // Add content length if there's a body
-    if let Some(data) = &args.data {
-        request.push_str(&format!("Content-Length: {}\r\n", data.len()));
+    // Add content type and length if there's a body; uploaded files are streamed by `send_upload`
+    match body(args)? {
+        Some(Body::Bytes { data, content_type }) => {
+            if let Some(content_type) = content_type {
+                if !has_header(args, "content-type") {
+                    builder = builder.header("Content-Type", &content_type);
+                }
+            }
+            builder = builder.body(data);
+        }
+        Some(Body::File { length, .. }) => builder = builder.content_length(length),
+        Some(Body::Stdin) => builder = builder.chunked(),
+        None => {}
     }
 
-    // End headers
-    request.push_str("\r\n");
+    // Declared trailers send the body chunked; `send_upload` ends it with their values
+    for (name, _) in trailer_fields(args) {
+        builder = builder.trailer(&name);
+    }
 
-    // Add body if present
-    let mut request_bytes = request.into_bytes();
-    if let Some(data) = &args.data {
-        request_bytes.extend_from_slice(data.as_bytes());
+    builder.build()
+}
+
+/// Build an HTTP/1.1 request from the given arguments.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar; cookies matching the URL are sent.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, &'static str>` - A vector of bytes representing the HTTP request if successful, or an error message if unsuccessful.
+pub fn build(args: &Args, jar: &CookieJar) -> Result<Vec<u8>, &'static str> {
+    Ok(prepare(args, jar)?.to_http1())
+}
+
+/// Open the composed request in the user's editor and parse the result back.
+///
+/// The raw HTTP/1.1 request is shown as it would be sent. Editors usually end the
+/// file with a newline, so one trailing newline is dropped from the edited body
+/// unless the original body ended with one.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar; cookies matching the URL are included.
+///
+/// # Returns
+///
+/// * `Result<Request, String>` - The edited request if successful, or an error message if the editor failed or the request cannot be parsed.
+pub fn edit(args: &Args, jar: &CookieJar) -> Result<Request, String> {
+    let request = prepare(args, jar)?;
+    let raw = String::from_utf8(request.to_http1())
+        .map_err(|_| "Cannot edit a request with a binary body")?;
+
+    let edited = editor::edit(&raw, "request.http")?;
+    let mut edited = edited.as_str();
+    let original_newline = request.body().is_some_and(|body| body.ends_with(b"\n"));
+    if !original_newline {
+        edited = edited
+            .strip_suffix("\r\n")
+            .or_else(|| edited.strip_suffix('\n'))
+            .unwrap_or(edited);
     }
 
-    Ok(request_bytes)
+    let request = Request::parse_http1(edited, request.url())
+        .map_err(|err| format!("Edited request: {}", err))?;
+    Ok(request)
 }
 
-/// Base64 encode a byte slice
-fn base64_encode(data: &[u8]) -> String {
-    const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut result = String::new();
-    let mut i = 0;
-
-    while i < data.len() {
-        let b1 = data[i];
-        let b2 = if i + 1 < data.len() { data[i + 1] } else { 0 };
-        let b3 = if i + 2 < data.len() { data[i + 2] } else { 0 };
-
-        result.push(BASE64_CHARS[(b1 >> 2) as usize] as char);
-        result.push(BASE64_CHARS[(((b1 & 0x03) << 4) | (b2 >> 4)) as usize] as char);
-        
-        if i + 1 < data.len() {
-            result.push(BASE64_CHARS[(((b2 & 0x0f) << 2) | (b3 >> 6)) as usize] as char);
-        } else {
-            result.push('=');
+/// A request body.
+pub enum Body {
+    /// Built in memory, from `-d`, `--data-urlencode`, or `-F`, with the Content-Type it implies
+    Bytes {
+        data: Vec<u8>,
+        content_type: Option<String>,
+    },
+    /// Streamed from disk, from `-T`
+    File { path: String, length: u64 },
+    /// Streamed from stdin, from `-T -`; its size is unknown, so it is sent chunked
+    Stdin,
+}
+
+impl Body {
+    /// The number of bytes sent as the body, None if it is only known once stdin ends.
+    pub fn length(&self) -> Option<u64> {
+        match self {
+            Body::Bytes { data, .. } => Some(data.len() as u64),
+            Body::File { length, .. } => Some(*length),
+            Body::Stdin => None,
+        }
+    }
+
+    /// Open the body for reading from the start, limited to `length` bytes.
+    pub fn reader(&self) -> Result<Box<dyn Read + '_>, String> {
+        match self {
+            Body::Bytes { data, .. } => Ok(Box::new(&data[..])),
+            Body::File { path, length } => {
+                let file = File::open(path)
+                    .map_err(|err| format!("Failed to open upload file '{}': {}", path, err))?;
+                Ok(Box::new(file.take(*length)))
+            }
+            Body::Stdin => Ok(Box::new(io::stdin())),
         }
-        
-        if i + 2 < data.len() {
-            result.push(BASE64_CHARS[(b3 & 0x3f) as usize] as char);
+    }
+}
+
+/// Build the request body from `-d`, `--data-urlencode`, `-F`, or `-T`.
+///
+/// An upload file is only measured here; its contents, or stdin for `-T -`, are read
+/// while sending.
+///
+/// # Arguments
+///
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+///
+/// # Returns
+///
+/// * `Result<Option<Body>, &'static str>` - The body, None if the request has none, or an error message if a file could not be read.
+pub fn body(args: &Args) -> Result<Option<Body>, &'static str> {
+    if let Some(request) = &args.edited_request {
+        return Ok(request.body().map(|data| Body::Bytes {
+            data: data.to_vec(),
+            content_type: None,
+        }));
+    }
+
+    if let Some(path) = &args.upload_file {
+        if path == "-" {
+            return Ok(Some(Body::Stdin));
+        }
+        let length = std::fs::metadata(path)
+            .map_err(|_| "Failed to read upload file")?
+            .len();
+        return Ok(Some(Body::File {
+            path: path.clone(),
+            length,
+        }));
+    }
+
+    if !args.form.is_empty() {
+        let boundary = multipart::boundary();
+        return Ok(Some(Body::Bytes {
+            data: multipart::encode(&args.form, boundary)?,
+            content_type: Some(format!("multipart/form-data; boundary={}", boundary)),
+        }));
+    }
+
+    // -d data comes first, then the --data-urlencode fields, joined with `&`
+    if !args.data_urlencode.is_empty() {
+        let mut data = args.data.clone().unwrap_or_default();
+        for field in &args.data_urlencode {
+            if !data.is_empty() {
+                data.push(b'&');
+            }
+            data.extend_from_slice(field.as_bytes());
+        }
+        return Ok(Some(Body::Bytes {
+            data,
+            content_type: Some("application/x-www-form-urlencoded".to_string()),
+        }));
+    }
+
+    Ok(args.data.as_ref().map(|data| Body::Bytes {
+        data: data.clone(),
+        content_type: args.json.then(|| "application/json".to_string()),
+    }))
+}
+
+/// Encode one `--data-urlencode` argument, like curl.
+///
+/// `content` and `=content` send the encoded content, `name=content` sends
+/// `name=` followed by it, and `@file` and `name@file` do the same with the
+/// file's bytes. The name is sent as given.
+///
+/// # Returns
+///
+/// * `Result<String, &'static str>` - The encoded field if successful, or an error message if the file cannot be read.
+pub fn encode_form_field(spec: &str) -> Result<String, &'static str> {
+    let (name, content) = match spec.find(['=', '@']) {
+        Some(at) if spec.as_bytes()[at] == b'@' => (&spec[..at], read_data_file(&spec[at + 1..])?),
+        Some(at) => (&spec[..at], spec.as_bytes()[at + 1..].to_vec()),
+        None => ("", spec.as_bytes().to_vec()),
+    };
+    let encoded = url::percent_encode(&content);
+    Ok(if name.is_empty() {
+        encoded
+    } else {
+        format!("{}={}", name, encoded)
+    })
+}
+
+/// Read the file of a `-d @FILE` or `--data-urlencode @FILE` argument, or stdin for `-`.
+pub fn read_data_file(path: &str) -> Result<Vec<u8>, &'static str> {
+    if path == "-" {
+        let mut data = Vec::new();
+        io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|_| "Failed to read data from stdin")?;
+        return Ok(data);
+    }
+    std::fs::read(path).map_err(|_| "Failed to read data file")
+}
+
+/// Stream the rest of the body after the request head.
+///
+/// That is the `-T` upload file, in fixed-size chunks, or stdin as chunks of a chunked
+/// body, and with `--trailer` or stdin the last chunk and the trailer fields. Other
+/// bodies are part of `build`'s output.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if successful, or an error message if reading or writing failed.
+pub fn send_upload<W: Write>(args: &Args, out: &mut W) -> Result<(), String> {
+    let body = body(args)?;
+    let chunked = !args.trailers.is_empty() || matches!(body, Some(Body::Stdin));
+
+    if let Some(body @ (Body::File { .. } | Body::Stdin)) = &body {
+        let meter = Meter::new(
+            Direction::Upload,
+            body.length(),
+            0,
+            progress::enabled(args, Direction::Upload),
+        );
+        let mut reader = progress::Reader::new(body.reader()?, meter);
+        let sent = if chunked {
+            response::copy_body(&mut reader, &mut Chunks(&mut *out))?
         } else {
-            result.push('=');
+            response::copy_body(&mut reader, out)?
+        };
+        if let Some(length) = body.length().filter(|length| sent != *length) {
+            return Err(format!(
+                "Upload file changed while sending ({} of {} bytes)",
+                sent, length
+            ));
         }
+        log::verbose(args, format_args!("Uploaded {} bytes", sent));
+    }
+
+    if chunked {
+        out.write_all(&chunked::encode_end(&trailer_fields(args)))
+            .map_err(|err| format!("Write error: {}", err))?;
+    }
+    Ok(())
+}
 
-        i += 3;
+/// The `--trailer` fields as (name, value) pairs.
+fn trailer_fields(args: &Args) -> Vec<(String, String)> {
+    args.trailers
+        .iter()
+        .filter_map(|trailer| trailer.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Writes everything as chunks of a chunked body.
+struct Chunks<W: Write>(W);
+
+impl<W: Write> Write for Chunks<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write_all(&chunked::encode_chunk(buf))?;
+        Ok(buf.len())
     }
 
-    result
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Check whether a header was given with `-H`, compared case-insensitively.
+pub fn has_header(args: &Args, name: &str) -> bool {
+    args.headers.iter().any(|header| {
+        header
+            .split_once(':')
+            .is_some_and(|(field, _)| field.trim().eq_ignore_ascii_case(name))
+    })
 }
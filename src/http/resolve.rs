@@ -0,0 +1,287 @@
+use std::cell::Cell;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::args::Args;
+use crate::http::timeout::{self, Phase};
+use crate::output::{error, log};
+use crate::writeout::{self, Event};
+
+/// Whether `host` is a Tor onion service, which only a Tor SOCKS proxy can reach.
+pub fn is_onion(host: &str) -> bool {
+    host.trim_end_matches('.')
+        .to_ascii_lowercase()
+        .ends_with(".onion")
+}
+
+/// The error for an onion host that would be resolved or sent outside Tor.
+pub fn onion_error(host: &str) -> String {
+    format!(
+        "Refusing to look up .onion host '{}' outside Tor; use --tor or -x socks5h://HOST:PORT",
+        host
+    )
+}
+
+/// The order in which resolved addresses are attempted, from `--ip-strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// All IPv4 addresses, then all IPv6 addresses
+    V4First,
+    /// All IPv6 addresses, then all IPv4 addresses
+    V6First,
+    /// The resolver's order, rotated by one more address on each connection
+    RoundRobin,
+    /// A fresh shuffle on each connection
+    Random,
+}
+
+impl Strategy {
+    /// Parse a `--ip-strategy` value.
+    pub fn parse(value: &str) -> Result<Strategy, &'static str> {
+        match value {
+            "v4-first" => Ok(Strategy::V4First),
+            "v6-first" => Ok(Strategy::V6First),
+            "round-robin" => Ok(Strategy::RoundRobin),
+            "random" => Ok(Strategy::Random),
+            _ => Err("Invalid IP strategy (expected v4-first, v6-first, round-robin or random)"),
+        }
+    }
+
+    /// Whether successive connections to a host may start at different addresses,
+    /// so reusing a pooled connection would defeat the strategy.
+    pub fn rotates(self) -> bool {
+        matches!(self, Strategy::RoundRobin | Strategy::Random)
+    }
+}
+
+thread_local! {
+    /// Connections ordered so far with `Strategy::RoundRobin`
+    static ROTATION: Cell<usize> = const { Cell::new(0) };
+    /// xorshift state for `Strategy::Random`, seeded on first use
+    static SEED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Next number of a xorshift generator seeded from the clock and the process id.
+fn next_random() -> u64 {
+    SEED.with(|seed| {
+        let mut x = seed.get();
+        if x == 0 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or(0);
+            x = (nanos ^ (u64::from(std::process::id()) << 32)) | 1;
+        }
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        seed.set(x);
+        x
+    })
+}
+
+/// Order resolved addresses for connecting according to `strategy`.
+///
+/// # Arguments
+///
+/// * `addrs` - The addresses in the resolver's order.
+/// * `strategy` - The `--ip-strategy` to apply.
+///
+/// # Returns
+///
+/// * `Vec<SocketAddr>` - The addresses in the order they should be attempted.
+pub fn order(addrs: &[SocketAddr], strategy: Strategy) -> Vec<SocketAddr> {
+    let mut ordered = addrs.to_vec();
+    match strategy {
+        // Stable sorts keep the resolver's order within each family
+        Strategy::V4First => ordered.sort_by_key(|addr| addr.is_ipv6()),
+        Strategy::V6First => ordered.sort_by_key(|addr| addr.is_ipv4()),
+        Strategy::RoundRobin => {
+            let turn = ROTATION.with(|rotation| rotation.replace(rotation.get() + 1));
+            if !ordered.is_empty() {
+                let len = ordered.len();
+                ordered.rotate_left(turn % len);
+            }
+        }
+        Strategy::Random => {
+            for i in (1..ordered.len()).rev() {
+                let j = (next_random() % (i as u64 + 1)) as usize;
+                ordered.swap(i, j);
+            }
+        }
+    }
+    ordered
+}
+
+/// Parse `/etc/hosts`-format content into (hostname, address) pairs.
+///
+/// Each non-comment line holds an IP address followed by one or more names.
+/// Everything after a `#` is ignored.
+///
+/// # Arguments
+///
+/// * `content` - The contents of a hosts file.
+///
+/// # Returns
+///
+/// * `Result<Vec<(String, IpAddr)>, String>` - The parsed entries in file order if successful, or an error message naming the offending line.
+pub fn parse_hosts(content: &str) -> Result<Vec<(String, IpAddr)>, String> {
+    let mut entries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let addr = fields.next().unwrap_or("");
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("Invalid address in hosts file line {}: {}", index + 1, addr))?;
+
+        let mut has_name = false;
+        for name in fields {
+            entries.push((name.to_lowercase(), addr));
+            has_name = true;
+        }
+        if !has_name {
+            return Err(format!("Missing hostname in hosts file line {}", index + 1));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Load and parse a hosts file from disk.
+pub fn load_hosts_file(path: &str) -> Result<Vec<(String, IpAddr)>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read hosts file '{}': {}", path, err))?;
+    parse_hosts(&content)
+}
+
+/// Parse a `--resolve` entry: `host:port:addr`, where `addr` may list several addresses
+/// separated by commas and IPv6 addresses may be bracketed.
+///
+/// # Returns
+///
+/// * `Result<(String, u16, Vec<IpAddr>), &'static str>` - The lowercased host, port, and addresses if valid, or an error message if unsuccessful.
+pub fn parse_resolve(entry: &str) -> Result<(String, u16, Vec<IpAddr>), &'static str> {
+    const INVALID: &str = "Invalid --resolve entry (expected host:port:addr)";
+
+    // IPv6 addresses contain colons, so only the first two separate fields
+    let (host, rest) = entry.split_once(':').ok_or(INVALID)?;
+    let (port, addrs) = rest.split_once(':').ok_or(INVALID)?;
+    let port = port.parse::<u16>().map_err(|_| INVALID)?;
+    if host.is_empty() {
+        return Err(INVALID);
+    }
+    let addrs = addrs
+        .split(',')
+        .map(|addr| {
+            let addr = addr.trim();
+            let addr = addr
+                .strip_prefix('[')
+                .and_then(|addr| addr.strip_suffix(']'))
+                .unwrap_or(addr);
+            addr.parse::<IpAddr>().map_err(|_| INVALID)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((host.to_lowercase(), port, addrs))
+}
+
+/// Look up addresses for `host` in the override layer.
+///
+/// `--resolve` entries for the host and port come first, then `--hosts-file`.
+/// Returns `Ok(None)` when no override applies, so the caller falls back to system DNS.
+pub fn lookup_override(
+    host: &str,
+    port: u16,
+    args: &Args,
+) -> Result<Option<Vec<SocketAddr>>, String> {
+    let host = host.to_lowercase();
+
+    for entry in &args.resolve {
+        let (name, entry_port, addrs) = parse_resolve(entry)?;
+        if name == host && entry_port == port {
+            return Ok(Some(
+                addrs
+                    .into_iter()
+                    .map(|addr| SocketAddr::new(addr, port))
+                    .collect(),
+            ));
+        }
+    }
+
+    if let Some(path) = &args.hosts_file {
+        let addrs: Vec<SocketAddr> = load_hosts_file(path)?
+            .into_iter()
+            .filter(|(name, _)| *name == host)
+            .map(|(_, addr)| SocketAddr::new(addr, port))
+            .collect();
+        if !addrs.is_empty() {
+            return Ok(Some(addrs));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Resolve a host and port to socket addresses.
+///
+/// Overrides (`--resolve` and `--hosts-file` entries) are consulted before system DNS.
+///
+/// # Arguments
+///
+/// * `host` - The hostname to resolve.
+/// * `port` - The port to connect to.
+/// * `args` - A reference to an `Args` struct containing the resolver options.
+///
+/// # Returns
+///
+/// * `Result<Vec<SocketAddr>, String>` - The resolved addresses if successful, or an error message if unsuccessful.
+pub fn resolve(host: &str, port: u16, args: &Args) -> Result<Vec<SocketAddr>, String> {
+    error::enter(error::Phase::Dns);
+    // Looking up an onion service name leaks it to the DNS servers, and never works
+    if is_onion(host) {
+        return Err(onion_error(host));
+    }
+    if let Some(addrs) = lookup_override(host, port, args)? {
+        log::verbose(args, format_args!("Resolved {} from override list", host));
+        writeout::mark(Event::NameLookup);
+        return Ok(addrs);
+    }
+
+    let addr = format!("{}:{}", host, port);
+    let result = match args.dns_timeout {
+        Some(limit) => {
+            // The system resolver blocks, so run it on a thread and stop waiting at the limit
+            let (sender, receiver) = mpsc::channel();
+            let lookup = addr.clone();
+            thread::spawn(move || {
+                let _ = sender.send(lookup.to_socket_addrs().map(Iterator::collect));
+            });
+            match receiver.recv_timeout(limit) {
+                Ok(result) => result,
+                Err(_) => return Err(timeout::error(Phase::Dns, limit, host)),
+            }
+        }
+        None => addr.to_socket_addrs().map(Iterator::collect),
+    };
+
+    let addrs_vec: Vec<SocketAddr> = match result {
+        Ok(addrs) => addrs,
+        Err(err) => {
+            return Err(format!("DNS resolution error: {}", err));
+        }
+    };
+    if addrs_vec.is_empty() {
+        return Err(format!("No addresses resolved for {}:{}", host, port));
+    }
+
+    writeout::mark(Event::NameLookup);
+    Ok(addrs_vec)
+}
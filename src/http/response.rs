@@ -1,182 +1,238 @@
 use crate::args::Args;
-use std::fs::File;
-use std::io::Write;
+use crate::clipboard;
+use crate::disk;
+use crate::history;
+use crate::http::audit;
+use crate::http::compare;
+use crate::http::hexdump::HexDump;
+use crate::http::negotiate;
+use crate::http::paginate;
+use crate::http::parts;
+use crate::http::range;
+use crate::http::sniff;
+use crate::json;
+use crate::output;
+use crate::output::log::{self, Level, Logger};
+use crate::pipe;
+use crate::progress::{self, Direction, Meter};
+use crate::quota;
+use crate::resume;
+use crate::throttle;
+use crate::tui;
+use crate::waterfall;
+use crate::writeout;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+
+pub use rurl::response::{
+    find_header, find_header_end, get_content_length, get_location, header_fields,
+    is_chunked_transfer, parse_status, parse_status_line, parse_version,
+};
+use rurl::url::Url;
+
+/// Whether only the response head is used, so the body need not be downloaded.
+pub fn headers_only(args: &Args) -> bool {
+    args.head_only
+        || args.status_only
+        || !args.expect_headers.is_empty()
+        || !args.header_values.is_empty()
+}
 
-/// Extract the Content-Length header value from an HTTP response.
-///
-/// # Arguments
-///
-/// * `response` - A slice of bytes representing an HTTP response.
+/// Whether `-f` fails on the status of `headers`, so the error body need not be downloaded.
+pub fn fails_without_body(headers: &[u8], args: &Args) -> bool {
+    args.fail_fast && parse_status(headers).is_ok_and(|status| status.is_error())
+}
+
+/// Check the `--expect-header` assertions against the response head.
 ///
 /// # Returns
 ///
-/// * `Option<usize>` - The Content-Length value if found, otherwise None.
-pub fn get_content_length(response: &[u8]) -> Option<usize> {
-    // Convert to string for easier parsing
-    let headers = std::str::from_utf8(&response[..std::cmp::min(response.len(), 2048)]).ok()?;
-
-    for line in headers.lines() {
-        let line = line.trim().to_lowercase();
-        if line.starts_with("content-length:") {
-            // Extract the value part
-            let value = line.split(':').nth(1)?.trim().parse::<usize>().ok()?;
-            return Some(value);
+/// * `Result<(), String>` - `Ok(())` if every expected header is present, or an error message naming the first that is not.
+pub fn check_expected_headers(headers: &[u8], args: &Args) -> Result<(), String> {
+    for expected in &args.expect_headers {
+        let (name, value) = match expected.split_once(':') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (expected.trim(), None),
+        };
+        let mut values = header_fields(headers)
+            .filter(|(field, _)| field.eq_ignore_ascii_case(name.as_bytes()))
+            .map(|(_, found)| String::from_utf8_lossy(found).into_owned())
+            .peekable();
+        let found = match value {
+            Some(value) => values.any(|found| found == value),
+            None => values.peek().is_some(),
+        };
+        if !found {
+            return Err(format!(
+                "Expected header '{}' not found in the response",
+                expected
+            ));
         }
     }
-
-    None
+    Ok(())
 }
 
-/// Check if the response is using chunked transfer encoding.
-///
-/// # Arguments
-///
-/// * `response` - A slice of bytes representing an HTTP response.
+/// Print the values of the `--header-value` headers, one per line and in the order
+/// asked for; a header that occurs several times prints each value.
 ///
 /// # Returns
 ///
-/// * `bool` - Whether the response is using chunked transfer encoding.
-pub fn is_chunked_transfer(response: &[u8]) -> bool {
-    // Convert to string for easier parsing
-    if let Ok(headers) = std::str::from_utf8(&response[..std::cmp::min(response.len(), 2048)]) {
-        for line in headers.lines() {
-            let line = line.trim().to_lowercase();
-            if line.starts_with("transfer-encoding:") && line.contains("chunked") {
-                return true;
-            }
+/// * `Result<(), String>` - `Ok(())` if every header was found, or an error message naming the first that was not.
+pub fn print_header_values(headers: &[u8], args: &Args) -> Result<(), String> {
+    let mut missing = None;
+    for name in &args.header_values {
+        let mut found = false;
+        for (_, value) in
+            header_fields(headers).filter(|(field, _)| field.eq_ignore_ascii_case(name.as_bytes()))
+        {
+            found = true;
+            println!("{}", String::from_utf8_lossy(value));
+        }
+        if !found && missing.is_none() {
+            missing = Some(name);
         }
     }
-
-    false
+    match missing {
+        Some(name) => Err(format!("Header '{}' not found in the response", name)),
+        None => Ok(()),
+    }
 }
 
-/// Parse the status line of an HTTP response.
-///
-/// This function takes a slice of bytes representing an HTTP response and parses the status line.
-///
-/// # Arguments
-///
-/// * `response` - A slice of bytes representing an HTTP response.
-///
-/// # Returns
-///
-/// * `Result<u16, &'static str>` - The status code of the response if successful, or an error message if unsuccessful.
-pub fn parse_status_line(response: &[u8]) -> Result<u16, &'static str> {
-    let status_line = match response.split(|&b| b == b'\r').next() {
-        Some(line) => line,
-        None => return Err("Invalid response format"),
-    };
-
-    let status_line = match std::str::from_utf8(status_line) {
-        Ok(line) => line,
-        Err(_) => return Err("Invalid UTF-8 in status line"),
-    };
-
-    let status_code = status_line
-        .split_whitespace()
-        .nth(1)
-        .ok_or("Missing status code")?
-        .parse::<u16>()
-        .map_err(|_| "Invalid status code")?;
+/// File name used by `-O` when the URL path ends in `/`
+pub const DEFAULT_REMOTE_NAME: &str = "index.html";
 
-    Ok(status_code)
+/// The file name `-O` saves `url` under: its last path segment.
+///
+/// The name never leaves the current directory: backslashes and control characters
+/// become `_`, and an empty, `.`, or `..` segment falls back to `DEFAULT_REMOTE_NAME`.
+pub fn remote_file_name(url: &Url) -> String {
+    let segment = url.path_segments().last().unwrap_or("");
+    let name: String = segment
+        .chars()
+        .map(|c| if c == '\\' || c.is_control() { '_' } else { c })
+        .collect();
+    if name.is_empty() || name == "." || name == ".." {
+        DEFAULT_REMOTE_NAME.to_string()
+    } else {
+        name
+    }
 }
 
-/// Decode a chunked transfer encoded response body
-///
-/// # Arguments
-///
-/// * `body` - A slice of bytes containing the chunked response body
+/// Copy a response body to `out` as it arrives.
 ///
 /// # Returns
 ///
-/// * `Vec<u8>` - The decoded response body
-pub fn decode_chunked_transfer(body: &[u8]) -> Vec<u8> {
-    let mut result = Vec::new();
-    let mut i = 0;
-
-    while i < body.len() {
-        // Find the end of the chunk size line
-        let chunk_size_end = match &body[i..].windows(2).position(|w| w == b"\r\n") {
-            Some(pos) => i + pos,
-            None => break, // Malformed chunked encoding
-        };
-
-        if chunk_size_end == i {
-            break; // No more chunks
-        }
-
-        // Parse the chunk size from hex
-        let chunk_size_line = std::str::from_utf8(&body[i..chunk_size_end]).unwrap_or("");
-        let chunk_size = match usize::from_str_radix(chunk_size_line.trim(), 16) {
-            Ok(size) => size,
-            Err(_) => break, // Invalid hex
+/// * `Result<u64, String>` - The number of body bytes written if successful, or an error message if unsuccessful.
+pub fn copy_body<R: Read, W: Write>(body: &mut R, out: &mut W) -> Result<u64, String> {
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut total = 0;
+
+    loop {
+        let n = match body.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("Read error: {}", err)),
         };
+        out.write_all(&buffer[..n])
+            .map_err(|err| format!("Write error: {}", err))?;
+        total += n as u64;
+    }
 
-        // Check if this is the last chunk (zero size)
-        if chunk_size == 0 {
-            break;
-        }
-
-        // Skip the CRLF after the chunk size
-        let chunk_start = chunk_size_end + 2;
+    out.flush().map_err(|err| format!("Write error: {}", err))?;
+    Ok(total)
+}
 
-        // Ensure we don't go beyond the buffer
-        if chunk_start + chunk_size > body.len() {
-            break;
+/// A writer that flushes whenever a newline passes through, for `--line-buffered`.
+///
+/// Each line reaches the reader of a pipe or file as soon as it has been received,
+/// instead of when an output buffer fills up.
+pub struct FlushLines<W: Write>(pub W);
+
+impl<W: Write> Write for FlushLines<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.0.write(buf)?;
+        if buf[..n].contains(&b'\n') {
+            self.0.flush()?;
         }
-
-        // Append the chunk data to the result
-        result.extend_from_slice(&body[chunk_start..chunk_start + chunk_size]);
-
-        // Move index to the next chunk, skipping the CRLF after the chunk data
-        i = chunk_start + chunk_size + 2;
+        Ok(n)
     }
 
-    result
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
 }
 
-/// Extract the Location header from an HTTP response.
+/// Read a multipart/byteranges body and join the data of its parts.
 ///
-/// # Arguments
-///
-/// * `response` - A slice of bytes representing an HTTP response.
-///
-/// # Returns
-///
-/// * `Option<String>` - The Location header value if found, otherwise None.
-pub fn get_location(response: &[u8]) -> Option<String> {
-    // Convert to string for easier parsing
-    let headers = std::str::from_utf8(&response[..std::cmp::min(response.len(), 2048)]).ok()?;
-
-    for line in headers.lines() {
-        if line.to_lowercase().starts_with("location:") {
-            // Extract the value part (everything after first colon)
-            if let Some((_, value)) = line.split_once(':') {
-                return Some(value.trim().to_string());
-            }
+/// Each part's range is reported on stderr unless `-s` is given.
+fn read_parts<R: Read + ?Sized>(
+    body: &mut R,
+    boundary: &str,
+    args: &Args,
+) -> Result<io::Cursor<Vec<u8>>, String> {
+    let mut multipart = Vec::new();
+    body.take(range::MAX_MULTIPART_SIZE + 1)
+        .read_to_end(&mut multipart)
+        .map_err(|err| format!("Read error: {}", err))?;
+    if multipart.len() as u64 > range::MAX_MULTIPART_SIZE {
+        return Err("multipart/byteranges response too large".to_string());
+    }
+
+    let mut data = Vec::new();
+    for part in range::split_parts(&multipart, boundary)? {
+        if !args.silent {
+            eprintln!(
+                "Part: {} ({} bytes)",
+                part.content_range.as_deref().unwrap_or("unknown range"),
+                part.data.len()
+            );
         }
+        data.extend_from_slice(&part.data);
     }
+    Ok(io::Cursor::new(data))
+}
 
-    None
+/// Write the body, preceded by the headers with `-i`, as a hex dump to `out`.
+fn write_hexdump<R: Read, W: Write>(
+    headers: &[u8],
+    body: &mut R,
+    out: &mut W,
+    args: &Args,
+) -> Result<(), String> {
+    let mut dump = HexDump::new(out);
+    if args.include_headers {
+        dump.write_all(headers)
+            .map_err(|err| format!("Write error: {}", err))?;
+    }
+    copy_body(body, &mut dump)?;
+    dump.finish()
+        .map_err(|err| format!("Write error: {}", err))?;
+    Ok(())
 }
 
 /// Process an HTTP response.
 ///
-/// This function takes a slice of bytes representing an HTTP response and processes it.
+/// The headers are inspected first; the body is then streamed from `body` to
+/// stdout or the output file without being buffered in memory.
 ///
 /// # Arguments
 ///
-/// * `response` - A slice of bytes representing an HTTP response.
+/// * `headers` - The response header block, including the status line.
+/// * `body` - A reader yielding the decoded response body.
 /// * `args` - A reference to an `Args` struct containing the request parameters.
 ///
 /// # Returns
 ///
-/// * `()` - This function does not return a value.
-pub fn process(response: &[u8], args: &Args) {
+/// * `Result<(), String>` - `Ok(())` if successful, or an error message if reading or writing the body failed.
+pub fn process<R: Read>(headers: &[u8], body: &mut R, args: &Args) -> Result<(), String> {
+    // Every body counts against --max-total-bytes and --limit-rate, whatever is done with it
+    let body = &mut quota::Reader::new(body, args.max_total_bytes);
+    let body = &mut throttle::Reader::new(body, args.limit_rate);
+
     // Find the end of headers
-    let header_end = match response.windows(4).position(|window| window == b"\r\n\r\n") {
-        Some(pos) => pos + 4,
+    let header_end = match find_header_end(headers) {
+        Some(pos) => pos,
         None => {
             if !args.silent {
                 eprintln!("Invalid HTTP response");
@@ -186,7 +242,7 @@ pub fn process(response: &[u8], args: &Args) {
     };
 
     // Check status code
-    let status = match parse_status_line(response) {
+    let status = match parse_status(headers) {
         Ok(status) => status,
         Err(err) => {
             if !args.silent {
@@ -195,12 +251,52 @@ pub fn process(response: &[u8], args: &Args) {
             std::process::exit(1);
         }
     };
+    history::record(args, &headers[..header_end]);
+    negotiate::check(&headers[..header_end], args);
+
+    // Report security header posture instead of the body
+    if args.audit_headers {
+        let is_https = args.url.to_lowercase().starts_with("https://");
+        let findings = audit::audit(&headers[..header_end], is_https);
+        if !args.silent {
+            audit::print_report(&findings);
+        }
+        std::process::exit(audit::exit_code(&findings));
+    }
+
+    // Answer --status, --header-value, and --expect-header from the head alone
+    if args.status_only || !args.expect_headers.is_empty() || !args.header_values.is_empty() {
+        if args.status_only {
+            println!("{}", status.as_u16());
+        }
+        print_header_values(&headers[..header_end], args)?;
+        return check_expected_headers(&headers[..header_end], args);
+    }
+
+    // Only measure the body for the --negotiate-report table
+    if args.negotiate_report {
+        let length = io::copy(body, &mut io::sink())
+            .map_err(|err| format!("Error reading response: {}", err))?;
+        negotiate::record(&headers[..header_end], length);
+        return Ok(());
+    }
+
+    // Describe the whole response as one JSON object instead
+    if args.json_output {
+        return output::json::write(&headers[..header_end], body, args);
+    }
+
+    // Note where the next page is before the body is written
+    if args.follow_link.is_some() {
+        paginate::record(&headers[..header_end], args)?;
+    }
 
     // Print status line and essential headers
-    if args.verbose && !args.silent {
-        if let Ok(headers) = std::str::from_utf8(&response[..header_end]) {
+    let logger = Logger::new(args);
+    if logger.enabled(Level::Verbose) {
+        if let Ok(headers) = std::str::from_utf8(&headers[..header_end]) {
             let status_line = headers.lines().next().unwrap_or("Unknown status");
-            println!("Status: {}", status_line);
+            logger.log(Level::Verbose, format_args!("Status: {}", status_line));
 
             // Print some important headers
             let mut content_type = None;
@@ -208,99 +304,337 @@ pub fn process(response: &[u8], args: &Args) {
             let mut transfer_encoding = None;
 
             for line in headers.lines().skip(1) {
-                let lower_line = line.to_lowercase();
-                if lower_line.starts_with("content-type:") {
+                let name = line.split(':').next().unwrap_or("");
+                if name.eq_ignore_ascii_case("content-type") {
                     content_type = Some(line);
-                } else if lower_line.starts_with("content-length:") {
+                } else if name.eq_ignore_ascii_case("content-length") {
                     content_length = Some(line);
-                } else if lower_line.starts_with("transfer-encoding:") {
+                } else if name.eq_ignore_ascii_case("transfer-encoding") {
                     transfer_encoding = Some(line);
                 }
             }
 
-            if let Some(ct) = content_type {
-                println!("{}", ct);
-            }
-            if let Some(cl) = content_length {
-                println!("{}", cl);
-            }
-            if let Some(te) = transfer_encoding {
-                println!("{}", te);
+            for line in [content_type, content_length, transfer_encoding]
+                .into_iter()
+                .flatten()
+            {
+                logger.log(Level::Verbose, format_args!("{}", line));
             }
-            println!();
+            logger.log(Level::Verbose, format_args!(""));
+        }
+    }
+
+    // Warn when the declared Content-Type contradicts the body's magic bytes, and
+    // report the sniffed type to --write-out; a line-buffered stream is not held back
+    let wanted = logger.enabled(Level::Verbose)
+        || args
+            .write_out
+            .as_deref()
+            .is_some_and(|template| writeout::uses(template, "content_type_sniffed"));
+    let prefix = if wanted && !args.head_only && !args.line_buffered {
+        sniff::peek(body).map_err(|err| format!("Read error: {}", err))?
+    } else {
+        Vec::new()
+    };
+    let sniffed = sniff::sniff(&prefix);
+    if let Some(sniffed) = sniffed {
+        writeout::record_sniffed(sniffed);
+    }
+    if let (Some(sniffed), Some(content_type)) = (
+        sniffed.filter(|_| logger.enabled(Level::Verbose)),
+        find_header(&headers[..header_end], "content-type"),
+    ) {
+        let content_type = String::from_utf8_lossy(content_type);
+        let content_encoding = find_header(&headers[..header_end], "content-encoding")
+            .map(|value| String::from_utf8_lossy(value).into_owned());
+        if sniff::is_mismatch(&content_type, content_encoding.as_deref(), sniffed) {
+            logger.log_err(
+                Level::Verbose,
+                format_args!(
+                    "Warning: Content-Type is '{}' but the body looks like {}",
+                    content_type, sniffed
+                ),
+            );
+        }
+    }
+    let body = &mut io::Cursor::new(prefix).chain(body);
+
+    // The range of a resumed download starts at the end of the resource
+    if status == 416 && args.resume_from.is_some() {
+        if let Some(offset) = args.resume_from {
+            resume::complete(args, offset)?;
+        }
+        if !args.silent {
+            println!("Download already complete");
+        }
+        return Ok(());
+    }
+
+    // -C only writes the rest of the body if the server resumed where asked
+    if let (Some(_), Some(offset)) = (args.continue_at, args.resume_from) {
+        if status.is_success() {
+            resume::check_continued(status.as_u16(), &headers[..header_end], offset)?;
         }
     }
 
     // Check for error status
-    if status >= 400 {
+    if status.is_error() {
+        if args.error_json {
+            let status = status.as_u16();
+            output::error::print(&format!("HTTP Error: {}", status), Some(status));
+            std::process::exit(if args.fail_fast { 22 } else { 1 });
+        }
         if args.fail_fast {
             // Fail silently with no output
             std::process::exit(22); // Exit code 22 like curl does
         }
         if !args.silent {
-            eprintln!("HTTP Error: {}", status);
-            if let Ok(body) = std::str::from_utf8(&response[header_end..]) {
-                eprintln!("Response body: {}", body);
+            eprintln!("HTTP Error: {}", status.as_u16());
+            let mut error_body = Vec::new();
+            if !args.head_only && body.read_to_end(&mut error_body).is_ok() {
+                if let Ok(body) = std::str::from_utf8(&error_body) {
+                    eprintln!("Response body: {}", body);
+                }
             }
         }
         std::process::exit(1);
     }
 
-    // Handle chunked transfer encoding
-    let body = if is_chunked_transfer(&response[..header_end]) {
-        decode_chunked_transfer(&response[header_end..])
-    } else {
-        response[header_end..].to_vec()
+    // Check the body against a golden file instead of printing it
+    if args.compare.is_some() {
+        return compare::check(body, args);
+    }
+
+    // Save the parts of a multipart response as separate files
+    if let Some(dir) = &args.extract_parts {
+        return parts::extract(&headers[..header_end], body, dir, args);
+    }
+
+    // --paginate reads each page as JSON to find the next one
+    let mut page;
+    let body: &mut dyn Read = match &args.paginate {
+        Some(spec) => {
+            let mut text = String::new();
+            body.read_to_string(&mut text)
+                .map_err(|err| format!("Read error: {}", err))?;
+            let value = json::parse(&text)
+                .map_err(|err| format!("Response from {} is not JSON: {}", args.url, err))?;
+            paginate::record_page(&value, spec, args)?;
+            // Merged items are printed after the last page
+            if spec.items.is_some() {
+                return Ok(());
+            }
+            page = io::Cursor::new(text.into_bytes());
+            &mut page
+        }
+        None => body,
     };
 
     // If --head flag is used, only show headers
     if args.head_only {
-        if let Ok(headers) = std::str::from_utf8(&response[..header_end]) {
+        if let Ok(headers) = std::str::from_utf8(&headers[..header_end]) {
             print!("{}", headers);
         }
-        return;
+        return Ok(());
     }
 
-    // Handle response body
-    if let Some(output_path) = &args.output {
-        // Write to file
-        match File::create(output_path) {
-            Ok(mut file) => {
-                // If include_headers is set, write headers first
-                if args.include_headers {
-                    if let Err(err) = file.write_all(&response[..header_end]) {
-                        if !args.silent {
-                            eprintln!("Write error: {}", err);
-                        }
-                        std::process::exit(1);
-                    }
-                }
-                if let Err(err) = file.write_all(&body) {
-                    if !args.silent {
-                        eprintln!("Write error: {}", err);
-                    }
-                    std::process::exit(1);
+    // Check how the server answered -r; several ranges arrive as multipart/byteranges
+    let mut parts = None;
+    if args.range.is_some() {
+        match (
+            status.as_u16(),
+            range::multipart_boundary(&headers[..header_end]),
+        ) {
+            (206, Some(boundary)) => parts = Some(read_parts(body, &boundary, args)?),
+            (206, None) => {
+                if let Some(content_range) = find_header(headers, "content-range") {
+                    log::verbose(
+                        args,
+                        format_args!("Content-Range: {}", String::from_utf8_lossy(content_range)),
+                    );
                 }
+            }
+            _ => {
                 if !args.silent {
-                    println!("Response body saved to '{}'", output_path);
+                    eprintln!(
+                        "Warning: the server ignored the range and sent the whole body (HTTP {})",
+                        status.as_u16()
+                    );
                 }
             }
+        }
+    }
+
+    // Show a progress meter while the body streams; a resumed body continues the file
+    let offset = match (status.as_u16(), args.resume_from) {
+        (206, Some(offset)) => offset,
+        _ => 0,
+    };
+    let total = match &parts {
+        Some(parts) => Some(parts.get_ref().len() as u64),
+        None => get_content_length(headers).map(|length| length as u64 + offset),
+    };
+    let body: &mut dyn Read = match &mut parts {
+        Some(parts) => parts,
+        None => body,
+    };
+    let meter = Meter::new(
+        Direction::Download,
+        total,
+        offset,
+        progress::enabled(args, Direction::Download),
+    );
+    let body = &mut writeout::Counter(waterfall::Recorder(progress::Reader::new(body, meter)));
+
+    // Put the body on the clipboard instead of printing it
+    if args.copy {
+        let mut full_body = Vec::new();
+        body.take(clipboard::MAX_SIZE + 1)
+            .read_to_end(&mut full_body)
+            .map_err(|err| format!("Read error: {}", err))?;
+        if full_body.len() as u64 > clipboard::MAX_SIZE {
+            return Err("Response body is too large for the clipboard".to_string());
+        }
+        let command = clipboard::copy(&full_body)?;
+        if !args.silent {
+            eprintln!(
+                "Copied {} bytes to the clipboard ({})",
+                full_body.len(),
+                command
+            );
+        }
+        return Ok(());
+    }
+
+    // Browse the complete response instead of printing it
+    if args.tui && args.output.is_none() {
+        let mut full_body = Vec::new();
+        body.take(tui::MAX_BODY_SIZE)
+            .read_to_end(&mut full_body)
+            .map_err(|err| format!("Read error: {}", err))?;
+        return tui::run(&headers[..header_end], &full_body);
+    }
+
+    // Stream the body into a command, which decides the exit status
+    if let Some(command) = &args.pipe_to {
+        let code = pipe::run(command, body)?;
+        if code != 0 {
+            std::process::exit(code);
+        }
+        return Ok(());
+    }
+
+    // Handle response body
+    if let Some(output_path) = &args.output {
+        // A resumed download continues the file only if the server honoured the range
+        let append = args.resume_from.is_some() && status == 206;
+        if args.resume_from.is_some() && !append {
+            logger.log_err(
+                Level::Verbose,
+                format_args!("Server ignored the range request, downloading from the start"),
+            );
+        }
+
+        // Refuse a body that cannot fit before creating or truncating the file
+        if let Some(total) = total {
+            let mut needed = total - offset;
+            if args.include_headers && !append {
+                needed += header_end as u64;
+            }
+            disk::check(output_path, needed, append, args)?;
+        }
+
+        // Write to file
+        let file = if append {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(output_path)
+        } else {
+            File::create(output_path)
+        };
+        let mut file = match file {
+            Ok(file) => BufWriter::new(file),
             Err(err) => {
                 if !args.silent {
                     eprintln!("File error: {}", err);
                 }
                 std::process::exit(1);
             }
+        };
+
+        if args.hexdump {
+            write_hexdump(&headers[..header_end], body, &mut file, args)?;
+        } else {
+            // If include_headers is set, write headers first
+            if args.include_headers && !append {
+                file.write_all(&headers[..header_end])
+                    .map_err(|err| format!("Write error: {}", err))?;
+            }
+            if args.line_buffered {
+                copy_body(body, &mut FlushLines(&mut file))?;
+            } else {
+                copy_body(body, &mut file)?;
+            }
+        }
+        drop(file);
+
+        if args.resume_state.is_some() {
+            let size = fs::metadata(output_path)
+                .map_err(|err| format!("File error: {}", err))?
+                .len();
+            resume::complete(args, size)?;
+        }
+
+        if !args.silent {
+            println!("Response body saved to '{}'", output_path);
         }
     } else {
         // Print to stdout
+        let mut stdout = io::stdout().lock();
+
+        if args.hexdump {
+            return write_hexdump(&headers[..header_end], body, &mut stdout, args);
+        }
+
+        // One compact JSON document per line for --ndjson
+        if args.ndjson {
+            let mut text = String::new();
+            body.read_to_string(&mut text)
+                .map_err(|err| format!("Read error: {}", err))?;
+            let value = json::parse(&text)
+                .map_err(|err| format!("Response from {} is not JSON: {}", args.url, err))?;
+            return writeln!(stdout, "{}", value).map_err(|err| format!("Write error: {}", err));
+        }
+
         // If include_headers is set, print headers first
         if args.include_headers {
-            if let Ok(headers) = std::str::from_utf8(&response[..header_end]) {
-                print!("{}", headers);
+            stdout
+                .write_all(&headers[..header_end])
+                .map_err(|err| format!("Write error: {}", err))?;
+        }
+
+        // A byte order mark breaks tools such as jq reading the output
+        let mut start = Vec::new();
+        if !args.keep_bom {
+            let (rest, stripped) =
+                sniff::skip_bom(body).map_err(|err| format!("Read error: {}", err))?;
+            if stripped {
+                logger.log(
+                    Level::Verbose,
+                    format_args!("Removed the UTF-8 byte order mark from the body"),
+                );
             }
+            start = rest;
         }
-        let body_str = String::from_utf8_lossy(&body);
-        println!("{}", body_str);
+        let body = &mut io::Cursor::new(start).chain(body);
+        if args.line_buffered {
+            copy_body(body, &mut FlushLines(&mut stdout))?;
+        } else {
+            copy_body(body, &mut stdout)?;
+        }
+        writeln!(stdout).map_err(|err| format!("Write error: {}", err))?;
     }
+
+    Ok(())
 }
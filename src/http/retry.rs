@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::cookies;
+use crate::http::response;
+
+/// First delay of the exponential backoff
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for a single backoff delay
+const MAX_DELAY: Duration = Duration::from_secs(600);
+
+/// Check whether a response status is worth retrying: 429 Too Many Requests or any 5xx.
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// The delay before retry number `attempt` (0 for the first retry).
+///
+/// A `--retry-delay` is used as is; otherwise the delay starts at one second and
+/// doubles with every attempt, up to ten minutes.
+pub fn backoff(args: &Args, attempt: u32) -> Duration {
+    match args.retry_delay {
+        Some(delay) => delay,
+        None => INITIAL_DELAY
+            .checked_mul(1 << attempt.min(16))
+            .unwrap_or(MAX_DELAY)
+            .min(MAX_DELAY),
+    }
+}
+
+/// Parse a `Retry-After` value: delay seconds, or an HTTP date relative to `now`.
+///
+/// # Returns
+///
+/// * `Option<Duration>` - The time to wait, or None if the value cannot be parsed.
+pub fn parse_retry_after(value: &str, now: u64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = cookies::parse_http_date(value)?;
+    Some(Duration::from_secs(at.saturating_sub(now)))
+}
+
+/// Decide whether to retry after a failure, and prepare the next attempt.
+///
+/// # Arguments
+///
+/// * `args` - The arguments of the failed attempt.
+/// * `headers` - The response header block for a retryable status, or None for a connection error or timeout.
+///
+/// # Returns
+///
+/// * `Option<(Args, Duration)>` - The arguments for the next attempt and the delay to wait first, or None if the retries are used up or the next attempt would end after `--retry-max-time`.
+pub fn next(args: &Args, headers: Option<&[u8]>) -> Option<(Args, Duration)> {
+    if args.retry_attempt >= args.retry {
+        return None;
+    }
+
+    let requested = headers
+        .filter(|_| args.honor_retry_after)
+        .and_then(|headers| response::find_header(headers, "retry-after"))
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| parse_retry_after(value, args.clock.unix_time()));
+    let delay = requested.unwrap_or_else(|| backoff(args, args.retry_attempt));
+
+    if let Some(max_time) = args.retry_max_time {
+        if args.clock.monotonic().saturating_sub(args.started) + delay > max_time {
+            return None;
+        }
+    }
+
+    let mut retry = args.clone();
+    retry.retry_attempt += 1;
+    Some((retry, delay))
+}
+
+/// Tell the user about an upcoming retry, like `Warning: HTTP 503; retrying in 2s (2 retries left)`.
+pub fn announce(reason: &str, delay: Duration, retry: &Args) {
+    if retry.silent {
+        return;
+    }
+    let left = retry.retry - retry.retry_attempt;
+    eprintln!(
+        "Warning: {}; retrying in {:.1}s ({} {} left)",
+        reason,
+        delay.as_secs_f64(),
+        left,
+        if left == 1 { "retry" } else { "retries" }
+    );
+}
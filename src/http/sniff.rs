@@ -0,0 +1,149 @@
+use std::io::{self, ErrorKind, Read};
+
+/// Number of leading body bytes inspected for a signature
+pub const SNIFF_LEN: usize = 512;
+
+/// Magic byte signatures and the media type they identify.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x00asm", "application/wasm"),
+    (b"wOF2", "font/woff2"),
+    (b"wOFF", "font/woff"),
+];
+
+/// The UTF-8 byte order mark some servers put before a text body
+pub const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Aliases that servers commonly send for the sniffed types.
+const ALIASES: &[(&str, &str)] = &[
+    ("application/x-gzip", "application/gzip"),
+    ("image/jpg", "image/jpeg"),
+    ("application/x-zip-compressed", "application/zip"),
+    ("application/x-pdf", "application/pdf"),
+];
+
+/// Content-Encoding tokens under which a compressed body is expected.
+const ENCODINGS: &[(&str, &str)] = &[
+    ("gzip", "application/gzip"),
+    ("x-gzip", "application/gzip"),
+    ("zstd", "application/zstd"),
+];
+
+/// Identify a body from its leading magic bytes.
+///
+/// Only unambiguous binary signatures are recognized; text formats are never guessed.
+///
+/// # Arguments
+///
+/// * `body` - The first bytes of the response body.
+///
+/// # Returns
+///
+/// * `Option<&'static str>` - The sniffed media type, or None if no signature matched.
+pub fn sniff(body: &[u8]) -> Option<&'static str> {
+    if body.len() >= 12 && body.starts_with(b"RIFF") && &body[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| body.starts_with(magic))
+        .map(|(_, media_type)| *media_type)
+}
+
+/// Check whether a declared Content-Type contradicts the sniffed type.
+///
+/// Parameters and case are ignored, common aliases are accepted, and
+/// `application/octet-stream` never conflicts. A compressed body is expected
+/// when the matching Content-Encoding is declared.
+///
+/// # Arguments
+///
+/// * `content_type` - The Content-Type header value.
+/// * `content_encoding` - The Content-Encoding header value, if any.
+/// * `sniffed` - The type returned by `sniff`.
+///
+/// # Returns
+///
+/// * `bool` - Whether the declared type and the body disagree.
+pub fn is_mismatch(content_type: &str, content_encoding: Option<&str>, sniffed: &str) -> bool {
+    let declared = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let declared = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == declared)
+        .map_or(declared.as_str(), |(_, canonical)| canonical);
+
+    if declared.is_empty() || declared == "application/octet-stream" || declared == sniffed {
+        return false;
+    }
+
+    let encoded = content_encoding.is_some_and(|encoding| {
+        encoding.split(',').any(|token| {
+            let token = token.trim();
+            ENCODINGS.iter().any(|(name, media_type)| {
+                token.eq_ignore_ascii_case(name) && *media_type == sniffed
+            })
+        })
+    });
+    !encoded
+}
+
+/// Read up to `SNIFF_LEN` bytes from the start of `body` without losing them.
+///
+/// The caller chains the returned bytes in front of the remaining body.
+///
+/// # Returns
+///
+/// * `io::Result<Vec<u8>>` - The leading body bytes (fewer at end of body).
+pub fn peek<R: Read>(body: &mut R) -> io::Result<Vec<u8>> {
+    let mut prefix = vec![0u8; SNIFF_LEN];
+    let mut filled = 0;
+    // Signatures are short, so stop as soon as one read has delivered enough
+    while filled < 16 {
+        match body.read(&mut prefix[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    prefix.truncate(filled);
+    Ok(prefix)
+}
+
+/// Read the first bytes of a body and drop them if they are a UTF-8 byte order mark.
+///
+/// The caller chains the returned bytes in front of the remaining body.
+///
+/// # Returns
+///
+/// * `io::Result<(Vec<u8>, bool)>` - The leading body bytes without the mark, and whether it was there.
+pub fn skip_bom<R: Read>(body: &mut R) -> io::Result<(Vec<u8>, bool)> {
+    let mut prefix = vec![0u8; SNIFF_LEN];
+    let mut filled = 0;
+    // Read as much as one read delivers, so the body is not split into more pieces
+    while filled < UTF8_BOM.len() {
+        match body.read(&mut prefix[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    prefix.truncate(filled);
+    match prefix.strip_prefix(UTF8_BOM) {
+        Some(rest) => Ok((rest.to_vec(), true)),
+        None => Ok((prefix, false)),
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use crate::http::transport::Transport;
+
+/// Default limit for establishing the TCP connection
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Read timeout applied to the socket once connected
+pub const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Write timeout applied to the socket once connected
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A phase of setting up a request that can time out on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    not(any(feature = "tls-native", feature = "tls-rustls")),
+    allow(dead_code)
+)]
+pub enum Phase {
+    Dns,
+    Connect,
+    Tls,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Dns => write!(f, "DNS resolution"),
+            Phase::Connect => write!(f, "connect"),
+            Phase::Tls => write!(f, "TLS handshake"),
+        }
+    }
+}
+
+/// Build the error reported when `phase` exceeds `limit`, naming the phase so hangs can be attributed.
+pub fn error(phase: Phase, limit: Duration, target: &str) -> String {
+    format!(
+        "Timeout: {} did not complete within {:?} ({})",
+        phase, limit, target
+    )
+}
+
+/// Check whether an I/O error is a timeout (macOS reports EAGAIN for socket timeouts).
+pub fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+/// Apply the regular read and write timeouts to a connected socket.
+pub fn set_io_timeouts(stream: &dyn Transport) -> Result<(), String> {
+    stream
+        .set_timeouts(Some(READ_TIMEOUT), Some(WRITE_TIMEOUT))
+        .map_err(|err| format!("Failed to set socket timeout: {}", err))
+}
+
+/// Bound every socket operation by `limit`, e.g. for the duration of a TLS handshake.
+#[cfg_attr(
+    not(any(feature = "tls-native", feature = "tls-rustls")),
+    allow(dead_code)
+)]
+pub fn set_phase_timeout(stream: &dyn Transport, limit: Duration) -> Result<(), String> {
+    stream
+        .set_timeouts(Some(limit), Some(limit))
+        .map_err(|err| format!("Failed to set socket timeout: {}", err))
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::args::Args;
+use crate::output::{error, log};
+
+/// A connected byte stream to the server, which TLS and HTTP run on top of.
+///
+/// Usually a TCP connection; with `--unix-socket`, a Unix domain socket.
+pub trait Transport: Read + Write + Send + fmt::Debug {
+    /// Set the read and write timeouts of the underlying socket.
+    fn set_timeouts(&self, read: Option<Duration>, write: Option<Duration>) -> io::Result<()>;
+}
+
+impl Transport for TcpStream {
+    fn set_timeouts(&self, read: Option<Duration>, write: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(read)?;
+        self.set_write_timeout(write)
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixStream {
+    fn set_timeouts(&self, read: Option<Duration>, write: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(read)?;
+        self.set_write_timeout(write)
+    }
+}
+
+/// Connect to the Unix domain socket at `path`, as given with `--unix-socket`.
+///
+/// # Returns
+///
+/// * `Result<UnixStream, String>` - The connected socket if successful, or an error message if unsuccessful.
+#[cfg(unix)]
+pub fn connect_unix(path: &str, args: &Args) -> Result<UnixStream, String> {
+    log::verbose(args, format_args!("Connecting via Unix socket {}", path));
+    error::enter(error::Phase::Connect);
+    UnixStream::connect(path).map_err(|err| {
+        error::record_os_error(&err);
+        format!("Connection error: {} (Unix socket {})", err, path)
+    })
+}
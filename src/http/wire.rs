@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Write;
+
+use crate::history;
+use crate::http::hexdump;
+use crate::http::response;
+use crate::output::log::{Level, Logger};
+
+/// Which way bytes went over the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Send,
+    Recv,
+}
+
+thread_local! {
+    static TRACE: RefCell<Option<(File, Logger)>> = const { RefCell::new(None) };
+    static DUMP: RefCell<Option<File>> = const { RefCell::new(None) };
+}
+
+/// Print the head of an outgoing request for `-v`, each line prefixed with `> `.
+///
+/// Credentials in `Authorization`, `Cookie`, and similar headers are shown as `<redacted>`.
+pub fn print_request(logger: &Logger, request: &[u8]) {
+    if !logger.enabled(Level::Verbose) {
+        return;
+    }
+    let end = response::find_header_end(request).unwrap_or(request.len());
+    let head = String::from_utf8_lossy(&request[..end]);
+    let mut lines = head.lines();
+    if let Some(request_line) = lines.next() {
+        logger.log(Level::Verbose, format_args!("> {}", request_line));
+    }
+    for line in lines.filter(|line| !line.is_empty()) {
+        match line.split_once(':') {
+            Some((name, value)) => print_field(logger, name, value.trim()),
+            None => logger.log(Level::Verbose, format_args!("> {}", line)),
+        }
+    }
+    logger.log(Level::Verbose, format_args!(">"));
+}
+
+/// Print one outgoing header field, such as an HTTP/2 pseudo-header, for `-v`.
+pub fn print_field(logger: &Logger, name: &str, value: &str) {
+    logger.log(
+        Level::Verbose,
+        format_args!("> {}: {}", name, history::redact_header_value(name, value)),
+    );
+}
+
+/// Print a response head for `-v`, each line prefixed with `< `.
+pub fn print_response(logger: &Logger, head: &[u8]) {
+    if !logger.enabled(Level::Verbose) {
+        return;
+    }
+    let end = response::find_header_end(head).unwrap_or(head.len());
+    for line in String::from_utf8_lossy(&head[..end])
+        .lines()
+        .filter(|line| !line.is_empty())
+    {
+        logger.log(Level::Verbose, format_args!("< {}", line));
+    }
+    logger.log(Level::Verbose, format_args!("<"));
+}
+
+/// Start writing every byte sent and received to a new `--trace` file at `path`.
+///
+/// With `--trace-time`, each direction header in the file starts with the time of
+/// day; at the trace level it is also printed with the verbose output.
+pub fn start_trace(path: &str, logger: Logger) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|err| format!("Failed to create trace '{}': {}", path, err))?;
+    TRACE.with(|trace| *trace.borrow_mut() = Some((file, logger)));
+    Ok(())
+}
+
+/// Start writing every byte sent to a new `--request-dump` file at `path`.
+pub fn start_request_dump(path: &str) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|err| format!("Failed to create request dump '{}': {}", path, err))?;
+    DUMP.with(|dump| *dump.borrow_mut() = Some(file));
+    Ok(())
+}
+
+/// Append sent `bytes` to the `--request-dump` file as they are; a failed write stops the dump.
+fn dump(bytes: &[u8]) {
+    DUMP.with(|dump| {
+        let mut dump = dump.borrow_mut();
+        if let Some(file) = dump.as_mut() {
+            if file.write_all(bytes).is_err() {
+                *dump = None;
+            }
+        }
+    });
+}
+
+/// Append `bytes` to the `--trace` file as a hex dump headed by their direction.
+///
+/// The bytes are those of the HTTP exchange, after TLS decryption. Nothing is
+/// written unless `start_trace` was called; a failed write stops the trace.
+pub fn trace(direction: Direction, bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    if direction == Direction::Send {
+        dump(bytes);
+    }
+    TRACE.with(|trace| {
+        let mut trace = trace.borrow_mut();
+        let Some((file, logger)) = trace.as_mut() else {
+            return;
+        };
+        let header = match direction {
+            Direction::Send => format!("=> Send {} bytes", bytes.len()),
+            Direction::Recv => format!("<= Recv {} bytes", bytes.len()),
+        };
+        logger.log(Level::Trace, format_args!("{}", header));
+        let mut dump = logger.line(format_args!("{}", header));
+        dump.push('\n');
+        for (line, chunk) in bytes.chunks(16).enumerate() {
+            dump.push_str(&hexdump::format_line(line as u64 * 16, chunk));
+        }
+        if file.write_all(dump.as_bytes()).is_err() {
+            *trace = None;
+        }
+    });
+}
@@ -0,0 +1,660 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, ErrorKind, Read, Write};
+
+use super::{
+    create_http2_data_frames, create_http2_headers_frame, encode_frame, format_response_head,
+    frame_content, hpack, is_informational, parse_frame, request_headers, Frame,
+    DEFAULT_MAX_FRAME_SIZE, FLAG_ACK, FLAG_END_HEADERS, FLAG_END_STREAM, FRAME_CONTINUATION,
+    FRAME_DATA, FRAME_GOAWAY, FRAME_HEADERS, FRAME_PING, FRAME_PUSH_PROMISE, FRAME_RST_STREAM,
+    FRAME_SETTINGS, FRAME_WINDOW_UPDATE, PREFACE,
+};
+use crate::args::Args;
+use crate::cookies::CookieJar;
+use crate::http::request::{self, Body};
+use crate::http::wire;
+use crate::output::log::{Level, Logger};
+use crate::progress::{self, Direction, Meter};
+use crate::throttle;
+
+const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+
+const ERROR_NO_ERROR: u32 = 0x0;
+const ERROR_CANCEL: u32 = 0x8;
+
+/// Flow-control window every stream and the connection start with.
+const DEFAULT_WINDOW_SIZE: i64 = 65_535;
+
+/// Receive window advertised for each stream and the connection.
+const LOCAL_WINDOW_SIZE: i64 = 16 * 1024 * 1024;
+
+/// Largest legal flow-control window.
+const MAX_WINDOW_SIZE: i64 = (1 << 31) - 1;
+
+/// Most request body bytes read from the source per send.
+const MAX_SEND_CHUNK: usize = 256 * 1024;
+
+/// Client side of an HTTP/2 connection over an established (plain or TLS) stream.
+///
+/// Handles the preface and SETTINGS exchange, stream ID allocation,
+/// flow control in both directions, PING replies, and GOAWAY.
+///
+/// Connection-level frames can arrive while a body streams to stdout, so their
+/// verbose messages go to stderr, as those of `ResponseReader` do.
+pub struct Http2Connection<S: Read + Write> {
+    stream: S,
+    encoder: hpack::Encoder,
+    decoder: hpack::Decoder,
+    next_stream_id: u32,
+    recv_buf: Vec<u8>,
+    read_buf: Vec<u8>,
+    peer_max_frame_size: usize,
+    peer_initial_window: i64,
+    send_window: i64,
+    stream_send_windows: HashMap<u32, i64>,
+    recv_unacked: i64,
+    stream_recv_unacked: HashMap<u32, i64>,
+    stream_frames: HashMap<u32, Vec<Frame>>,
+    finished_streams: HashSet<u32>,
+    /// Streams whose HEADERS carried END_STREAM while their header block is still incomplete
+    ending_streams: HashSet<u32>,
+    goaway_last_stream: Option<u32>,
+    /// PING acknowledgements received from the server
+    pings_acked: u64,
+    logger: Logger,
+}
+
+impl<S: Read + Write> Http2Connection<S> {
+    /// Send the connection preface and our SETTINGS.
+    ///
+    /// The server's SETTINGS are applied and acknowledged as they arrive while
+    /// reading, so the handshake does not wait for a round trip.
+    pub fn handshake(mut stream: S, buffer_size: usize, logger: Logger) -> Result<Self, String> {
+        let mut settings = Vec::new();
+        for (id, value) in [
+            (SETTINGS_ENABLE_PUSH, 0),
+            (SETTINGS_INITIAL_WINDOW_SIZE, LOCAL_WINDOW_SIZE as u32),
+        ] {
+            settings.extend_from_slice(&id.to_be_bytes());
+            settings.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let mut bytes = PREFACE.to_vec();
+        bytes.extend_from_slice(&encode_frame(FRAME_SETTINGS, 0, 0, &settings));
+        let increment = (LOCAL_WINDOW_SIZE - DEFAULT_WINDOW_SIZE) as u32;
+        bytes.extend_from_slice(&encode_frame(
+            FRAME_WINDOW_UPDATE,
+            0,
+            0,
+            &increment.to_be_bytes(),
+        ));
+        stream
+            .write_all(&bytes)
+            .map_err(|err| format!("Write error: {}", err))?;
+
+        logger.log(
+            Level::Verbose,
+            format_args!("HTTP/2 connection preface and SETTINGS sent"),
+        );
+
+        Ok(Http2Connection {
+            stream,
+            encoder: hpack::Encoder::new(),
+            decoder: hpack::Decoder::new(),
+            next_stream_id: 1,
+            recv_buf: Vec::new(),
+            read_buf: vec![0u8; buffer_size.max(1)],
+            peer_max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            peer_initial_window: DEFAULT_WINDOW_SIZE,
+            send_window: DEFAULT_WINDOW_SIZE,
+            stream_send_windows: HashMap::new(),
+            recv_unacked: 0,
+            stream_recv_unacked: HashMap::new(),
+            stream_frames: HashMap::new(),
+            finished_streams: HashSet::new(),
+            ending_streams: HashSet::new(),
+            goaway_last_stream: None,
+            pings_acked: 0,
+            logger,
+        })
+    }
+
+    /// Open a new stream and send the request described by `args`, with cookies from `jar`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u32, String>` - The stream ID carrying the request if successful, or an error message if unsuccessful.
+    pub fn send_request(&mut self, args: &Args, jar: &CookieJar) -> Result<u32, String> {
+        if self.goaway_last_stream.is_some() {
+            return Err("HTTP/2 connection is shutting down (GOAWAY received)".to_string());
+        }
+        let stream_id = self.next_stream_id;
+        if stream_id > MAX_WINDOW_SIZE as u32 {
+            return Err("HTTP/2 stream IDs exhausted".to_string());
+        }
+        self.next_stream_id += 2;
+
+        let body = request::body(args)?;
+        let length = body.as_ref().map_or(Some(0), Body::length);
+        let reader: Box<dyn Read> = match &body {
+            Some(body @ (Body::File { .. } | Body::Stdin)) => {
+                let meter = Meter::new(
+                    Direction::Upload,
+                    length,
+                    0,
+                    progress::enabled(args, Direction::Upload),
+                );
+                Box::new(progress::Reader::new(body.reader()?, meter))
+            }
+            Some(body) => body.reader()?,
+            None => Box::new(std::io::empty()),
+        };
+        let mut reader = throttle::Reader::new(reader, args.limit_rate);
+        if self.logger.enabled(Level::Verbose) {
+            for (name, value) in request_headers(args, jar)? {
+                wire::print_field(&self.logger, &name, &value);
+            }
+            self.logger.log(Level::Verbose, format_args!(">"));
+        }
+        let headers =
+            create_http2_headers_frame(&mut self.encoder, args, jar, stream_id, length == Some(0))?;
+        self.write(&headers)?;
+        self.stream_send_windows
+            .insert(stream_id, self.peer_initial_window);
+
+        // The body is read in window-sized pieces so uploads are never held in memory whole;
+        // a body of unknown size ends with the first empty read
+        let mut chunk = Vec::new();
+        let mut remaining = length;
+        while remaining != Some(0) {
+            let stream_window = self.stream_send_windows[&stream_id];
+            let available = self.send_window.min(stream_window);
+            if available <= 0 {
+                // Wait for the server to open the window
+                if self.finished_streams.contains(&stream_id) {
+                    break;
+                }
+                self.process_next_frame()?;
+                continue;
+            }
+
+            let len = (available as u64)
+                .min(remaining.unwrap_or(u64::MAX))
+                .min(MAX_SEND_CHUNK as u64) as usize;
+            chunk.resize(len, 0);
+            let len = match remaining {
+                Some(_) => reader.read_exact(&mut chunk).map(|()| len),
+                None => reader.read(&mut chunk),
+            }
+            .map_err(|err| format!("Read error: {}", err))?;
+            chunk.truncate(len);
+            remaining = remaining.map(|remaining| remaining - len as u64);
+            let end_stream = remaining == Some(0) || len == 0;
+            let frames =
+                create_http2_data_frames(&chunk, stream_id, self.peer_max_frame_size, end_stream);
+            self.write(&frames)?;
+
+            self.send_window -= len as i64;
+            if let Some(window) = self.stream_send_windows.get_mut(&stream_id) {
+                *window -= len as i64;
+            }
+            if len == 0 {
+                break;
+            }
+        }
+
+        Ok(stream_id)
+    }
+
+    /// Read the response on `stream_id` as it arrives, converted to an HTTP/1.1-style
+    /// head followed by the body.
+    ///
+    /// Once the final head arrived, `skip_body` is asked whether the body is needed. If
+    /// not, the response ends after the head and a stream that is still open is reset
+    /// with CANCEL so the server stops sending. Otherwise the body is read from the
+    /// stream's DATA frames only as the returned reader is read, so it is never held
+    /// in memory as a whole.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream_id` - The stream carrying the response.
+    /// * `skip_body` - Decides from the response head whether to drop the body.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<S>, String>` - A reader over the response if its head arrived, or an error message if unsuccessful.
+    pub fn read_response(
+        mut self,
+        stream_id: u32,
+        skip_body: impl FnOnce(&[u8]) -> bool,
+    ) -> Result<Response<S>, String> {
+        let head = self.read_head(stream_id)?;
+        let done = skip_body(&head);
+        if done {
+            self.cancel(stream_id)?;
+            self.goaway();
+        }
+        Ok(Response {
+            connection: self,
+            stream_id,
+            pending: head,
+            pos: 0,
+            trailer_block: Vec::new(),
+            done,
+        })
+    }
+
+    /// Read frames until the final header block of `stream_id` arrived and return it
+    /// as a response head; the frames after it are left for the body.
+    fn read_head(&mut self, stream_id: u32) -> Result<Vec<u8>, String> {
+        let mut header_block = Vec::new();
+        let headers = 'head: loop {
+            // Header blocks must be decoded exactly once, in order, to keep the HPACK table in step
+            let mut frames = self
+                .stream_frames
+                .remove(&stream_id)
+                .unwrap_or_default()
+                .into_iter();
+            for frame in frames.by_ref() {
+                match frame.kind {
+                    FRAME_RST_STREAM => {
+                        return Err("HTTP/2 stream reset by server".to_string());
+                    }
+                    FRAME_HEADERS | FRAME_CONTINUATION => {
+                        header_block.extend_from_slice(frame_content(&frame)?);
+                        if frame.flags & FLAG_END_HEADERS != 0 {
+                            let decoded = self.decoder.decode(&header_block)?;
+                            header_block.clear();
+                            if !is_informational(&decoded) {
+                                self.stream_frames.insert(stream_id, frames.collect());
+                                break 'head decoded;
+                            }
+                            self.interim(&decoded);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if self.finished_streams.contains(&stream_id) {
+                return Err("No HTTP/2 response headers received".to_string());
+            }
+            self.check_refused(stream_id)?;
+            self.process_next_frame()?;
+        };
+        format_response_head(&headers)
+    }
+
+    /// Report an interim response in verbose mode, with the headers of Early Hints.
+    fn interim(&self, headers: &[(String, String)]) {
+        let logger = &self.logger;
+        let status = headers
+            .iter()
+            .find(|(name, _)| name == ":status")
+            .map_or("1xx", |(_, value)| value.as_str());
+        logger.log(Level::Verbose, format_args!("Interim response: {}", status));
+        if status == "103" {
+            for (name, value) in headers.iter().filter(|(name, _)| !name.starts_with(':')) {
+                logger.log(
+                    Level::Verbose,
+                    format_args!("Early Hints: {}: {}", name, value),
+                );
+            }
+        }
+    }
+
+    /// Reset `stream_id` with CANCEL if it is still open, and forget it.
+    fn cancel(&mut self, stream_id: u32) -> Result<(), String> {
+        if !self.finished_streams.contains(&stream_id) {
+            self.write(&encode_frame(
+                FRAME_RST_STREAM,
+                0,
+                stream_id,
+                &ERROR_CANCEL.to_be_bytes(),
+            ))?;
+            self.logger.log(
+                Level::Verbose,
+                format_args!(
+                    "HTTP/2 stream {} cancelled after the response head",
+                    stream_id
+                ),
+            );
+            self.finished_streams.insert(stream_id);
+        }
+        self.stream_frames.remove(&stream_id);
+        self.stream_send_windows.remove(&stream_id);
+        self.stream_recv_unacked.remove(&stream_id);
+        Ok(())
+    }
+
+    /// Fail if a GOAWAY from the server excluded `stream_id`.
+    fn check_refused(&self, stream_id: u32) -> Result<(), String> {
+        match self.goaway_last_stream {
+            Some(last) if stream_id > last => {
+                Err("HTTP/2 stream refused by server (GOAWAY)".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Send a PING and wait for the server to acknowledge it, handling any other
+    /// frames that arrive first.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - `Ok(())` once the PING was acknowledged, or an error message if the connection failed or is shutting down.
+    pub fn ping(&mut self) -> Result<(), String> {
+        let acked = self.pings_acked;
+        self.write(&encode_frame(FRAME_PING, 0, 0, &acked.to_be_bytes()))?;
+        while self.pings_acked == acked {
+            self.process_next_frame()?;
+            if self.goaway_last_stream.is_some() {
+                return Err("HTTP/2 connection is shutting down (GOAWAY received)".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Tell the server we are done with the connection.
+    fn goaway(&mut self) {
+        let mut payload = 0u32.to_be_bytes().to_vec();
+        payload.extend_from_slice(&ERROR_NO_ERROR.to_be_bytes());
+        let _ = self.write(&encode_frame(FRAME_GOAWAY, 0, 0, &payload));
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.stream
+            .write_all(bytes)
+            .map_err(|err| format!("Write error: {}", err))
+    }
+
+    /// Read the next complete frame from the connection.
+    fn read_frame(&mut self) -> Result<Frame, String> {
+        loop {
+            if let Some((frame, used)) = parse_frame(&self.recv_buf) {
+                self.recv_buf.drain(..used);
+                // We never advertise a larger SETTINGS_MAX_FRAME_SIZE
+                if frame.payload.len() > DEFAULT_MAX_FRAME_SIZE {
+                    return Err("HTTP/2 frame exceeds maximum frame size".to_string());
+                }
+                return Ok(frame);
+            }
+
+            match self.stream.read(&mut self.read_buf) {
+                Ok(0) => return Err("HTTP/2 connection closed by server".to_string()),
+                Ok(n) => self.recv_buf.extend_from_slice(&self.read_buf[..n]),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(format!("Read error: {}", err)),
+            }
+        }
+    }
+
+    /// Read one frame and update connection state accordingly.
+    fn process_next_frame(&mut self) -> Result<(), String> {
+        let frame = self.read_frame()?;
+
+        match frame.kind {
+            FRAME_SETTINGS => self.handle_settings(&frame)?,
+            FRAME_PING => {
+                if frame.stream_id != 0 || frame.payload.len() != 8 {
+                    return Err("Invalid HTTP/2 PING frame".to_string());
+                }
+                if frame.flags & FLAG_ACK == 0 {
+                    self.write(&encode_frame(FRAME_PING, FLAG_ACK, 0, &frame.payload))?;
+                    self.logger
+                        .log_err(Level::Verbose, format_args!("HTTP/2 PING answered"));
+                } else {
+                    self.pings_acked += 1;
+                }
+            }
+            FRAME_WINDOW_UPDATE => self.handle_window_update(&frame)?,
+            FRAME_GOAWAY => {
+                if frame.payload.len() < 8 {
+                    return Err("Invalid HTTP/2 GOAWAY frame".to_string());
+                }
+                let last = u32::from_be_bytes([
+                    frame.payload[0],
+                    frame.payload[1],
+                    frame.payload[2],
+                    frame.payload[3],
+                ]) & 0x7fff_ffff;
+                let code = u32::from_be_bytes([
+                    frame.payload[4],
+                    frame.payload[5],
+                    frame.payload[6],
+                    frame.payload[7],
+                ]);
+                if code != ERROR_NO_ERROR {
+                    return Err(format!("HTTP/2 GOAWAY received (error code {})", code));
+                }
+                self.goaway_last_stream = Some(last);
+            }
+            FRAME_PUSH_PROMISE => {
+                return Err("HTTP/2 server push received although disabled".to_string());
+            }
+            FRAME_RST_STREAM => {
+                self.ending_streams.remove(&frame.stream_id);
+                self.finished_streams.insert(frame.stream_id);
+                self.stream_frames
+                    .entry(frame.stream_id)
+                    .or_default()
+                    .push(frame);
+            }
+            FRAME_HEADERS | FRAME_CONTINUATION | FRAME_DATA => {
+                if frame.stream_id == 0 {
+                    return Err("HTTP/2 stream frame on stream 0".to_string());
+                }
+                if frame.kind == FRAME_DATA {
+                    self.consume_window(frame.stream_id, frame.payload.len() as i64)?;
+                }
+                // END_STREAM on HEADERS only takes effect once its CONTINUATION frames
+                // have completed the header block
+                let end_headers = frame.flags & FLAG_END_HEADERS != 0;
+                let end_stream = match frame.kind {
+                    FRAME_CONTINUATION => {
+                        end_headers && self.ending_streams.remove(&frame.stream_id)
+                    }
+                    _ => frame.flags & FLAG_END_STREAM != 0,
+                };
+                if frame.kind == FRAME_HEADERS && end_stream && !end_headers {
+                    self.ending_streams.insert(frame.stream_id);
+                } else if end_stream {
+                    self.finished_streams.insert(frame.stream_id);
+                }
+                self.stream_frames
+                    .entry(frame.stream_id)
+                    .or_default()
+                    .push(frame);
+            }
+            _ => {
+                // Unknown frame types must be ignored
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_settings(&mut self, frame: &Frame) -> Result<(), String> {
+        if frame.stream_id != 0 {
+            return Err("HTTP/2 SETTINGS on a stream".to_string());
+        }
+        if frame.flags & FLAG_ACK != 0 {
+            self.logger.log_err(
+                Level::Verbose,
+                format_args!("HTTP/2 SETTINGS acknowledged by server"),
+            );
+            return Ok(());
+        }
+        if !frame.payload.chunks_exact(6).remainder().is_empty() {
+            return Err("Invalid HTTP/2 SETTINGS frame".to_string());
+        }
+
+        for setting in frame.payload.chunks(6) {
+            let id = u16::from_be_bytes([setting[0], setting[1]]);
+            let value = u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+            match id {
+                SETTINGS_HEADER_TABLE_SIZE => self.encoder.set_max_table_size(value as usize),
+                SETTINGS_INITIAL_WINDOW_SIZE => {
+                    if value as i64 > MAX_WINDOW_SIZE {
+                        return Err("HTTP/2 flow control error in SETTINGS".to_string());
+                    }
+                    // Adjust open streams by the difference (RFC 9113, Section 6.9.2)
+                    let delta = value as i64 - self.peer_initial_window;
+                    for window in self.stream_send_windows.values_mut() {
+                        *window += delta;
+                    }
+                    self.peer_initial_window = value as i64;
+                }
+                SETTINGS_MAX_FRAME_SIZE => {
+                    if !(16_384..=16_777_215).contains(&value) {
+                        return Err("Invalid HTTP/2 SETTINGS_MAX_FRAME_SIZE".to_string());
+                    }
+                    self.peer_max_frame_size = value as usize;
+                }
+                _ => {}
+            }
+        }
+
+        self.write(&encode_frame(FRAME_SETTINGS, FLAG_ACK, 0, &[]))?;
+        self.logger.log_err(
+            Level::Verbose,
+            format_args!("HTTP/2 SETTINGS received and acknowledged"),
+        );
+        Ok(())
+    }
+
+    fn handle_window_update(&mut self, frame: &Frame) -> Result<(), String> {
+        if frame.payload.len() != 4 {
+            return Err("Invalid HTTP/2 WINDOW_UPDATE frame".to_string());
+        }
+        let increment = (u32::from_be_bytes([
+            frame.payload[0],
+            frame.payload[1],
+            frame.payload[2],
+            frame.payload[3],
+        ]) & 0x7fff_ffff) as i64;
+        if increment == 0 {
+            return Err("HTTP/2 WINDOW_UPDATE with zero increment".to_string());
+        }
+
+        let window = if frame.stream_id == 0 {
+            &mut self.send_window
+        } else {
+            match self.stream_send_windows.get_mut(&frame.stream_id) {
+                Some(window) => window,
+                // Updates for closed streams are allowed and ignored
+                None => return Ok(()),
+            }
+        };
+        *window += increment;
+        if *window > MAX_WINDOW_SIZE {
+            return Err("HTTP/2 flow control window overflow".to_string());
+        }
+        Ok(())
+    }
+
+    /// Account for received DATA and replenish the windows once half is used.
+    fn consume_window(&mut self, stream_id: u32, len: i64) -> Result<(), String> {
+        self.recv_unacked += len;
+        if self.recv_unacked >= LOCAL_WINDOW_SIZE / 2 {
+            let increment = self.recv_unacked as u32;
+            self.write(&encode_frame(
+                FRAME_WINDOW_UPDATE,
+                0,
+                0,
+                &increment.to_be_bytes(),
+            ))?;
+            self.recv_unacked = 0;
+        }
+
+        let unacked = self.stream_recv_unacked.entry(stream_id).or_insert(0);
+        *unacked += len;
+        if *unacked >= LOCAL_WINDOW_SIZE / 2 {
+            let increment = *unacked as u32;
+            *unacked = 0;
+            self.write(&encode_frame(
+                FRAME_WINDOW_UPDATE,
+                0,
+                stream_id,
+                &increment.to_be_bytes(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// An HTTP/2 response from `Http2Connection::read_response`, read as an HTTP/1.1-style
+/// head and then the body from the DATA frames of its stream.
+///
+/// Frames are only read from the connection when the body is, so a slow consumer
+/// (`--limit-rate`, `--pipe-to`) leaves the rest of the response with the server.
+/// The connection is closed with GOAWAY once the stream ends.
+pub struct Response<S: Read + Write> {
+    connection: Http2Connection<S>,
+    stream_id: u32,
+    pending: Vec<u8>,
+    pos: usize,
+    trailer_block: Vec<u8>,
+    done: bool,
+}
+
+impl<S: Read + Write> Response<S> {
+    /// Wait for the next DATA frame of the stream into `pending`, or mark the end.
+    fn next_data(&mut self) -> Result<(), String> {
+        let connection = &mut self.connection;
+        loop {
+            let frame = connection
+                .stream_frames
+                .get_mut(&self.stream_id)
+                .filter(|frames| !frames.is_empty())
+                .map(|frames| frames.remove(0));
+            let Some(frame) = frame else {
+                if connection.finished_streams.contains(&self.stream_id) {
+                    connection.stream_send_windows.remove(&self.stream_id);
+                    connection.stream_recv_unacked.remove(&self.stream_id);
+                    connection.goaway();
+                    self.done = true;
+                    return Ok(());
+                }
+                connection.check_refused(self.stream_id)?;
+                connection.process_next_frame()?;
+                continue;
+            };
+            match frame.kind {
+                FRAME_RST_STREAM => {
+                    return Err("HTTP/2 stream reset by server".to_string());
+                }
+                // Trailers are not shown, but decoding them keeps the HPACK table in step
+                FRAME_HEADERS | FRAME_CONTINUATION => {
+                    self.trailer_block.extend_from_slice(frame_content(&frame)?);
+                    if frame.flags & FLAG_END_HEADERS != 0 {
+                        connection.decoder.decode(&self.trailer_block)?;
+                        self.trailer_block.clear();
+                    }
+                }
+                FRAME_DATA => {
+                    self.pending = frame_content(&frame)?.to_vec();
+                    self.pos = 0;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<S: Read + Write> Read for Response<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.pending.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_data().map_err(io::Error::other)?;
+        }
+        let n = buf.len().min(self.pending.len() - self.pos);
+        buf[..n].copy_from_slice(&self.pending[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
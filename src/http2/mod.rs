@@ -0,0 +1,234 @@
+pub mod connection;
+
+pub use rurl::hpack;
+
+use std::io::{Read, Write};
+
+pub use connection::{Http2Connection, Response};
+
+use crate::args::Args;
+use crate::cookies::CookieJar;
+use crate::http::{request, response};
+use crate::output::log::Logger;
+
+/// The client connection preface (RFC 9113, Section 3.4).
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+pub const FRAME_DATA: u8 = 0x0;
+pub const FRAME_HEADERS: u8 = 0x1;
+pub const FRAME_RST_STREAM: u8 = 0x3;
+pub const FRAME_SETTINGS: u8 = 0x4;
+pub const FRAME_PUSH_PROMISE: u8 = 0x5;
+pub const FRAME_PING: u8 = 0x6;
+pub const FRAME_GOAWAY: u8 = 0x7;
+pub const FRAME_WINDOW_UPDATE: u8 = 0x8;
+pub const FRAME_CONTINUATION: u8 = 0x9;
+
+pub const FLAG_END_STREAM: u8 = 0x1;
+pub const FLAG_ACK: u8 = 0x1;
+pub const FLAG_END_HEADERS: u8 = 0x4;
+pub const FLAG_PADDED: u8 = 0x8;
+pub const FLAG_PRIORITY: u8 = 0x20;
+
+/// Default SETTINGS_MAX_FRAME_SIZE.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16_384;
+
+/// A single HTTP/2 frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub kind: u8,
+    pub flags: u8,
+    pub stream_id: u32,
+    pub payload: Vec<u8>,
+}
+
+/// Serialize a frame with its 9-byte header.
+pub fn encode_frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len();
+    let mut frame = Vec::with_capacity(9 + len);
+    frame.extend_from_slice(&[(len >> 16) as u8, (len >> 8) as u8, len as u8]);
+    frame.push(kind);
+    frame.push(flags);
+    frame.extend_from_slice(&(stream_id & 0x7fff_ffff).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Parse one frame from the start of `buf`.
+///
+/// # Returns
+///
+/// * `Option<(Frame, usize)>` - The frame and the number of bytes it occupied, or None if the frame is incomplete.
+pub fn parse_frame(buf: &[u8]) -> Option<(Frame, usize)> {
+    if buf.len() < 9 {
+        return None;
+    }
+    let len = ((buf[0] as usize) << 16) | ((buf[1] as usize) << 8) | buf[2] as usize;
+    if buf.len() < 9 + len {
+        return None;
+    }
+    let stream_id = u32::from_be_bytes([buf[5], buf[6], buf[7], buf[8]]) & 0x7fff_ffff;
+    let frame = Frame {
+        kind: buf[3],
+        flags: buf[4],
+        stream_id,
+        payload: buf[9..9 + len].to_vec(),
+    };
+    Some((frame, 9 + len))
+}
+
+/// Strip padding and priority fields from a HEADERS or DATA payload.
+pub fn frame_content(frame: &Frame) -> Result<&[u8], &'static str> {
+    let mut payload = &frame.payload[..];
+    let mut pad_len = 0;
+
+    if frame.flags & FLAG_PADDED != 0 {
+        pad_len = *payload.first().ok_or("Invalid padded frame")? as usize;
+        payload = &payload[1..];
+    }
+    if frame.kind == FRAME_HEADERS && frame.flags & FLAG_PRIORITY != 0 {
+        payload = payload.get(5..).ok_or("Invalid priority fields")?;
+    }
+    if pad_len > payload.len() {
+        return Err("Padding exceeds frame length");
+    }
+    Ok(&payload[..payload.len() - pad_len])
+}
+
+/// Build the request header list, pseudo-headers first.
+pub fn request_headers(
+    args: &Args,
+    jar: &CookieJar,
+) -> Result<Vec<(String, String)>, &'static str> {
+    Ok(request::prepare(args, jar)?.http2_headers())
+}
+
+/// Build HEADERS (plus any CONTINUATION) frames for a request on `stream_id`.
+///
+/// # Arguments
+///
+/// * `encoder` - The connection's HPACK encoder.
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar; cookies matching the URL are sent.
+/// * `stream_id` - The client-initiated (odd) stream identifier.
+/// * `end_stream` - Whether the request has no body.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, &'static str>` - The serialized frames if successful, or an error message if unsuccessful.
+pub fn create_http2_headers_frame(
+    encoder: &mut hpack::Encoder,
+    args: &Args,
+    jar: &CookieJar,
+    stream_id: u32,
+    end_stream: bool,
+) -> Result<Vec<u8>, &'static str> {
+    let block = encoder.encode(&request_headers(args, jar)?);
+
+    // An empty block still needs one HEADERS frame
+    let chunks: Vec<&[u8]> = if block.is_empty() {
+        vec![&[]]
+    } else {
+        block.chunks(DEFAULT_MAX_FRAME_SIZE).collect()
+    };
+
+    let mut frames = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let kind = if i == 0 {
+            FRAME_HEADERS
+        } else {
+            FRAME_CONTINUATION
+        };
+        let mut flags = if i == chunks.len() - 1 {
+            FLAG_END_HEADERS
+        } else {
+            0
+        };
+        if i == 0 && end_stream {
+            flags |= FLAG_END_STREAM;
+        }
+        frames.extend_from_slice(&encode_frame(kind, flags, stream_id, chunk));
+    }
+
+    Ok(frames)
+}
+
+/// Build DATA frames carrying `body` on `stream_id`, split at `max_frame_size`.
+///
+/// When `end_stream` is set the last frame carries END_STREAM.
+pub fn create_http2_data_frames(
+    body: &[u8],
+    stream_id: u32,
+    max_frame_size: usize,
+    end_stream: bool,
+) -> Vec<u8> {
+    // An empty body still needs a frame to end the stream
+    if body.is_empty() && end_stream {
+        return encode_frame(FRAME_DATA, FLAG_END_STREAM, stream_id, &[]);
+    }
+    let mut frames = Vec::new();
+    let mut chunks = body.chunks(max_frame_size.max(1)).peekable();
+    while let Some(chunk) = chunks.next() {
+        let flags = if end_stream && chunks.peek().is_none() {
+            FLAG_END_STREAM
+        } else {
+            0
+        };
+        frames.extend_from_slice(&encode_frame(FRAME_DATA, flags, stream_id, chunk));
+    }
+    frames
+}
+
+/// Whether a decoded header block is an informational (1xx) response.
+pub fn is_informational(headers: &[(String, String)]) -> bool {
+    headers
+        .iter()
+        .any(|(name, value)| name == ":status" && value.starts_with('1'))
+}
+
+/// Render a decoded response header block as an HTTP/1.1-style head ending in an empty line.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, String>` - The head if successful, or an error message if the block has no `:status`.
+pub fn format_response_head(headers: &[(String, String)]) -> Result<Vec<u8>, String> {
+    let status = headers
+        .iter()
+        .find(|(name, _)| name == ":status")
+        .map(|(_, value)| value.clone())
+        .ok_or("Missing :status in HTTP/2 response")?;
+
+    let mut response = format!("HTTP/2 {}\r\n", status);
+    for (name, value) in headers.iter().filter(|(name, _)| !name.starts_with(':')) {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    Ok(response.into_bytes())
+}
+
+/// Send a single request over a fresh HTTP/2 connection and read back its response.
+///
+/// When only the response head is used (`-I`, `--status`, `--expect-header`), or `-f`
+/// is given and the status is an error, the stream is cancelled once the head arrived
+/// and the response has no body.
+///
+/// # Arguments
+///
+/// * `stream` - The connected (plain or TLS) stream.
+/// * `args` - A reference to an `Args` struct containing the request parameters.
+/// * `jar` - The cookie jar; cookies matching the URL are sent.
+///
+/// # Returns
+///
+/// * `Result<Response<S>, String>` - A reader over the response as an HTTP/1.1-style head (`HTTP/2 <status>`) and the body as it arrives if successful, or an error message if unsuccessful.
+pub fn exchange<S: Read + Write>(
+    stream: S,
+    args: &Args,
+    jar: &CookieJar,
+) -> Result<Response<S>, String> {
+    let mut connection = Http2Connection::handshake(stream, args.buffer_size, Logger::new(args))?;
+    let stream_id = connection.send_request(args, jar)?;
+    connection.read_response(stream_id, |head| {
+        response::headers_only(args) || response::fails_without_body(head, args)
+    })
+}
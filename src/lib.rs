@@ -0,0 +1,24 @@
+//! Reusable parts of the rurl HTTP client.
+//!
+//! The protocol core is `no_std` and only needs `alloc`: it parses and serializes
+//! messages but does no I/O, so it can be driven by any transport. The `std`
+//! feature adds `client::Client`, which sends requests over `std::net`, and the
+//! `async` feature adds `r#async::Client`, which does the same on tokio.
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "async")]
+pub mod r#async;
+pub mod chunked;
+pub mod client;
+pub mod hpack;
+pub mod method;
+pub mod request;
+pub mod response;
+pub mod status;
+pub mod url;
+pub mod version;
@@ -2,9 +2,37 @@
 mod tests;
 
 mod args;
+mod clipboard;
+mod clock;
+mod config;
+mod cookies;
+mod disk;
+mod editor;
+mod history;
+mod hold;
 mod http;
+#[cfg(feature = "http2")]
+mod http2;
+mod json;
+mod netrc;
+mod output;
+mod parallel;
+mod pipe;
+mod progress;
+mod quota;
+mod resume;
+mod summary;
+mod throttle;
+mod tls;
+mod tui;
+mod warm;
+mod waterfall;
+mod writeout;
 
-use std::process;
+use std::{env, process};
+
+use output::log;
+use rurl::url::Url;
 
 /// A simple HTTP client that can send requests and receive responses.
 ///
@@ -15,7 +43,7 @@ use std::process;
 /// - Various HTTP methods (GET, POST, etc.)
 ///
 /// Usage:
-///     rurl [OPTIONS] <URL>
+///     rurl [OPTIONS] <URL>...
 ///
 /// Options:
 ///     -o, --output <FILE>     Save the response body to a file
@@ -28,12 +56,46 @@ use std::process;
 ///     rurl -m POST -H "Content-Type: application/json" -d '{"key":"value"}' https://api.example.com
 ///     rurl -o response.html https://example.com
 fn main() {
+    // Dispatch the cookie jar subcommand
+    if env::args().nth(1).as_deref() == Some("cookies") {
+        if let Err(err) = cookies::run_command(env::args().skip(2)) {
+            eprintln!("Error: {}", err);
+            eprintln!("Usage: rurl cookies list|import|export --jar <FILE> [OPTIONS]");
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    // Dispatch the connection hold probe
+    if env::args().nth(1).as_deref() == Some("hold") {
+        if let Err(err) = hold::run_command(env::args().skip(2)) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    // Dispatch the history subcommands
+    let history_command = match env::args().nth(1).as_deref() {
+        Some("history") => Some(history::run_history_command(env::args().skip(2))),
+        Some("rerun") => Some(history::run_rerun_command(env::args().skip(2))),
+        _ => None,
+    };
+    if let Some(result) = history_command {
+        if let Err(err) = result {
+            eprintln!("Error: {}", err);
+            eprintln!("Usage: rurl history [--limit N] [--clear] | rurl rerun <N>[-<M>] [OPTIONS]");
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
     // Parse arguments
     let args = match args::Args::parse() {
         Ok(args) => args,
         Err(err) => {
             eprintln!("Error: {}", err);
-            eprintln!("Usage: rurl [OPTIONS] <URL>");
+            eprintln!("Usage: rurl [OPTIONS] <URL>...");
             eprintln!("Try 'rurl --help' for more information.");
             process::exit(1);
         }
@@ -41,47 +103,223 @@ fn main() {
 
     // Display help if requested
     if args.help {
-        args::print_help();
+        if let Err(err) = args::print_help(args.help_topic.as_deref()) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+    if args.man {
+        args::print_man();
         process::exit(0);
     }
 
-    // Build HTTP request
-    let request_bytes = match http::request::build(&args) {
-        Ok(bytes) => bytes,
-        Err(err) => {
+    // The viewer needs a terminal; fail before sending the request
+    if args.tui {
+        if let Err(err) = tui::ensure_terminal() {
             eprintln!("Error: {}", err);
             process::exit(1);
         }
-    };
+    }
 
-    // Parse URL
-    let (host, port, _, is_https) = match http::url::parse(&args.url) {
-        Ok(parsed) => parsed,
+    // Load the [host "..."] profiles from ~/.rurlrc
+    let profiles = match config::load_profiles() {
+        Ok(profiles) => profiles,
         Err(err) => {
             eprintln!("Error: {}", err);
             process::exit(1);
         }
     };
 
-    // Setup TCP stream
-    let stream = match http::client::setup_tcp_stream(&host, port) {
-        Ok(stream) => stream,
-        Err(err) => {
-            eprintln!("{}", err);
+    // Load the cookie jar given with -b <file>; it is shared by all URLs
+    let mut jar = match &args.cookie_file {
+        Some(path) => match cookies::CookieJar::load(path) {
+            Ok(jar) => jar,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
+        },
+        None => cookies::CookieJar::default(),
+    };
+
+    // One trace file covers every transfer of the run
+    if let Some(path) = &args.trace {
+        if let Err(err) = http::wire::start_trace(path, log::Logger::new(&args)) {
+            eprintln!("Error: {}", err);
             process::exit(1);
         }
-    };
+    }
 
-    // Handle HTTP or HTTPS connection
-    let result = if is_https {
-        http::client::handle_https_connection(stream, &host, &request_bytes, &args)
-    } else {
-        http::client::handle_http_connection(stream, &host, &request_bytes, &args)
-    };
+    if let Some(path) = &args.request_dump {
+        if let Err(err) = http::wire::start_request_dump(path) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
 
-    // Handle any errors
-    if let Err(err) = result {
-        eprintln!("{}", err);
-        process::exit(1);
+    // Connect without sending requests, and report the setup times
+    if args.warm {
+        if let Err(err) = warm::run(&args) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    // Fetch the URLs concurrently, each into its own file
+    if args.parallel {
+        process::exit(parallel::run(&args, &profiles, &jar));
+    }
+
+    // Fetch the URLs in order; idle connections are reused between them
+    let summary_file = args.summary_file.clone();
+    for index in 0..args.urls.len() {
+        let args = args.for_url(index);
+        if let Some(path) = &summary_file {
+            if let Err(err) = summary::begin(path, &args.url) {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
+        }
+        let write_out = args.write_out.clone();
+        let error_json = args.error_json;
+        let max_total_bytes = args.max_total_bytes;
+        let waterfall_path = args.waterfall.clone();
+        let logger = log::Logger::new(&args);
+        let sparkline = logger.enabled(log::Level::Verbose);
+        writeout::start(&args.url);
+        output::error::start();
+        if waterfall_path.is_some() || sparkline {
+            waterfall::start(&args.url);
+        }
+        let result = if args.negotiate_report {
+            http::negotiate::report(&args, |variant| fetch(variant, &profiles, &mut jar))
+        } else if args.follow_link.is_some() || args.paginate.is_some() {
+            http::paginate::follow(&args, |page| fetch(page, &profiles, &mut jar))
+        } else {
+            fetch(args, &profiles, &mut jar)
+        };
+        if let Some(path) = &summary_file {
+            if let Err(err) = summary::finish(path, &result) {
+                eprintln!("Error: {}", err);
+                process::exit(1);
+            }
+        }
+        // Like curl, the summary is printed for failed transfers too
+        if let Some(template) = &write_out {
+            if let Err(err) = &result {
+                writeout::record_error(err);
+            }
+            writeout::print(template);
+        }
+        if sparkline {
+            let chunks = waterfall::current();
+            if let Some(last) = chunks.last() {
+                logger.log(
+                    log::Level::Verbose,
+                    format_args!(
+                        "Body arrived in {} chunk(s) by {:.3}s: {}",
+                        chunks.len(),
+                        last.time.as_secs_f64(),
+                        waterfall::sparkline(&chunks)
+                    ),
+                );
+            }
+        }
+        if let Some(path) = &waterfall_path {
+            if let Err(err) = waterfall::save(path) {
+                eprintln!("Error: {}", err);
+                exit_with_summary(&summary_file, 1);
+            }
+        }
+        if let Err(err) = result {
+            if error_json {
+                output::error::print(&err, None);
+            } else {
+                eprintln!("{}", err);
+            }
+            if let Some(limit) = max_total_bytes.filter(|_| quota::exceeded()) {
+                eprintln!("{}", quota::summary(limit));
+            }
+            exit_with_summary(&summary_file, 1);
+        }
+    }
+    exit_with_summary(&summary_file, 0);
+}
+
+/// Write the final `--summary-file`, if one was asked for, and exit with `code`.
+fn exit_with_summary(summary_file: &Option<String>, code: i32) -> ! {
+    if let Some(path) = summary_file {
+        if let Err(err) = summary::end(path, code) {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+    process::exit(code);
+}
+
+/// Fetch one URL: apply profiles and resume state, build the request, and send it.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if successful, or the message to print if the transfer failed.
+fn fetch(
+    mut args: args::Args,
+    profiles: &[config::Profile],
+    jar: &mut cookies::CookieJar,
+) -> Result<(), String> {
+    let error = |err: &str| format!("Error: {}", err);
+
+    // Fill in defaults from matching [host "..."] profiles
+    for pattern in config::apply(&mut args, profiles) {
+        log::verbose(&args, format_args!("Using profile for host '{}'", pattern));
+    }
+
+    // Skip or resume downloads recorded in the state file
+    if args.resume_state.is_some() {
+        match resume::prepare(&mut args).map_err(|err| error(&err))? {
+            resume::Plan::Skip => {
+                if !args.silent {
+                    println!("Skipping {} (already complete)", args.url);
+                }
+                return Ok(());
+            }
+            resume::Plan::Resume(offset) => {
+                log::verbose(
+                    &args,
+                    format_args!("Resuming {} at byte {}", args.url, offset),
+                );
+            }
+            resume::Plan::Fresh => {}
+        }
     }
+
+    // Continue a partial transfer with -C
+    if let Some(at) = args.continue_at {
+        resume::continue_at(&mut args, at).map_err(|err| error(&err))?;
+    }
+
+    // Let the user adjust the raw request; it replaces the one built from the options
+    if args.edit {
+        let request = http::request::edit(&args, jar).map_err(|err| error(&err))?;
+        args.method = request.method().clone();
+        args.url = request.url().to_string();
+        args.edited_request = Some(request);
+    }
+
+    // Build HTTP request
+    let request_bytes = http::request::build(&args, jar).map_err(error)?;
+
+    // Parse URL
+    let url = Url::parse(&args.url).map_err(error)?;
+    let (host, port, is_https) = (url.host_str(), url.port(), url.is_https());
+
+    // Apply the transport policy before connecting
+    http::guard::check(&args.url, is_https, &args).map_err(|err| error(&err))?;
+
+    // Connect and handle the HTTP or HTTPS exchange, retrying as configured
+    http::client::send(&host, port, is_https, &request_bytes, &args, jar)?;
+    quota::complete();
+    Ok(())
 }
@@ -0,0 +1,150 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The credentials of one `machine` entry, or of the `default` entry when `name` is None.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Machine {
+    pub name: Option<String>,
+    pub login: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Location of the user's netrc file, `~/.netrc` (`~/_netrc` on Windows).
+pub fn default_path() -> Option<PathBuf> {
+    let file = if cfg!(windows) { "_netrc" } else { ".netrc" };
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(file))
+}
+
+/// Split netrc contents into tokens: words separated by whitespace, or double-quoted
+/// strings in which a backslash escapes the next character.
+fn tokens(contents: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => token.extend(chars.next()),
+                    _ => token.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Parse the contents of a netrc file.
+///
+/// `machine NAME` and `default` start an entry; `login` and `password` fill it in and
+/// `account` is skipped. A `macdef` definition runs to the next blank line and is
+/// ignored. Lines starting with `#` are comments.
+///
+/// # Arguments
+///
+/// * `contents` - The netrc file contents.
+///
+/// # Returns
+///
+/// * `Result<Vec<Machine>, &'static str>` - The entries in file order if successful, or an error message if unsuccessful.
+pub fn parse(contents: &str) -> Result<Vec<Machine>, &'static str> {
+    // Drop comments and macro definitions before splitting into tokens
+    let mut kept = String::new();
+    let mut in_macro = false;
+    for line in contents.lines() {
+        if in_macro {
+            in_macro = !line.trim().is_empty();
+            continue;
+        }
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.split_whitespace().next() == Some("macdef") {
+            in_macro = true;
+            continue;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+    }
+
+    let mut machines: Vec<Machine> = Vec::new();
+    let mut tokens = tokens(&kept).into_iter();
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "machine" => machines.push(Machine {
+                name: Some(tokens.next().ok_or("Missing machine name in netrc file")?),
+                login: None,
+                password: None,
+            }),
+            "default" => machines.push(Machine {
+                name: None,
+                login: None,
+                password: None,
+            }),
+            "login" | "password" | "account" => {
+                let value = tokens.next().ok_or("Missing value in netrc file")?;
+                let machine = machines
+                    .last_mut()
+                    .ok_or("netrc file has a value before any machine")?;
+                match token.as_str() {
+                    "login" => machine.login = Some(value),
+                    "password" => machine.password = Some(value),
+                    _ => {}
+                }
+            }
+            _ => return Err("Unknown keyword in netrc file"),
+        }
+    }
+    Ok(machines)
+}
+
+/// Read and parse the netrc file at `path`.
+pub fn load(path: &str) -> Result<Vec<Machine>, &'static str> {
+    let contents = fs::read_to_string(path).map_err(|_| "Failed to read netrc file")?;
+    parse(&contents)
+}
+
+/// Find the credentials for `host`: the first `machine` entry naming it, or else the
+/// `default` entry. With a `login` from the URL, only entries for that login match.
+///
+/// # Arguments
+///
+/// * `machines` - The parsed netrc entries.
+/// * `host` - The host name of the request.
+/// * `login` - The user name given in the URL, if any.
+///
+/// # Returns
+///
+/// * `Option<&Machine>` - The matching entry, or None if there is none.
+pub fn find<'a>(machines: &'a [Machine], host: &str, login: Option<&str>) -> Option<&'a Machine> {
+    let login_matches = |machine: &&Machine| login.is_none() || machine.login.as_deref() == login;
+    machines
+        .iter()
+        .filter(login_matches)
+        .find(|machine| {
+            machine
+                .name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(host))
+        })
+        .or_else(|| {
+            machines
+                .iter()
+                .filter(login_matches)
+                .find(|machine| machine.name.is_none())
+        })
+}
@@ -0,0 +1,155 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+
+use crate::http::retry;
+use crate::json::Value;
+use crate::writeout;
+
+/// The step a transfer was in when it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading options, files, and building the request
+    Setup,
+    /// Resolving the host name
+    Dns,
+    /// Opening the TCP or Unix socket connection
+    Connect,
+    /// The TLS handshake
+    Tls,
+    /// Sending the request and waiting for the response head
+    Request,
+    /// Reading and writing the response body
+    Body,
+}
+
+impl Phase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Setup => "setup",
+            Phase::Dns => "dns",
+            Phase::Connect => "connect",
+            Phase::Tls => "tls",
+            Phase::Request => "request",
+            Phase::Body => "body",
+        }
+    }
+}
+
+thread_local! {
+    static PHASE: Cell<Phase> = const { Cell::new(Phase::Setup) };
+    static OS_ERROR: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+/// Start tracking a new transfer, forgetting the previous one.
+pub fn start() {
+    PHASE.with(|phase| phase.set(Phase::Setup));
+    OS_ERROR.with(|code| code.set(None));
+}
+
+/// Record that the transfer moved on to `phase`.
+pub fn enter(phase: Phase) {
+    PHASE.with(|current| current.set(phase));
+}
+
+/// Remember the operating system error code behind a failure, if it has one.
+pub fn record_os_error(err: &io::Error) {
+    if let Some(code) = err.raw_os_error() {
+        OS_ERROR.with(|current| current.set(Some(code)));
+    }
+}
+
+/// A failed transfer, as reported by `--error-format json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// `timeout`, `redirect`, `http`, or the phase the transfer failed in
+    pub category: &'static str,
+    pub message: String,
+    pub phase: Phase,
+    pub os_error: Option<i32>,
+    pub url: String,
+    /// Whether `--retry` would try again after this failure
+    pub retryable: bool,
+}
+
+impl Report {
+    /// Classify the failure `message` of a transfer of `url` that stopped in `phase`.
+    ///
+    /// `status` is the HTTP status when the server answered with an error.
+    pub fn new(
+        message: &str,
+        phase: Phase,
+        status: Option<u16>,
+        os_error: Option<i32>,
+        url: &str,
+    ) -> Report {
+        let message = message.strip_prefix("Error: ").unwrap_or(message);
+        let category = if status.is_some() {
+            "http"
+        } else if message.starts_with("Timeout") {
+            "timeout"
+        } else if message.starts_with("Redirect blocked") || message == "Too many redirects" {
+            "redirect"
+        } else {
+            phase.as_str()
+        };
+        // The phases that `--retry` covers, from DNS up to the response head
+        let retryable = match status {
+            Some(status) => retry::is_retryable_status(status),
+            None => {
+                category != "redirect"
+                    && matches!(
+                        phase,
+                        Phase::Dns | Phase::Connect | Phase::Tls | Phase::Request
+                    )
+            }
+        };
+        Report {
+            category,
+            message: message.to_string(),
+            phase,
+            os_error,
+            url: url.to_string(),
+            retryable,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Object(vec![
+            (
+                "category".to_string(),
+                Value::String(self.category.to_string()),
+            ),
+            ("message".to_string(), Value::String(self.message.clone())),
+            (
+                "phase".to_string(),
+                Value::String(self.phase.as_str().to_string()),
+            ),
+            (
+                "os_error".to_string(),
+                self.os_error
+                    .map_or(Value::Null, |code| Value::Number(f64::from(code))),
+            ),
+            ("url".to_string(), Value::String(self.url.clone())),
+            ("retryable".to_string(), Value::Bool(self.retryable)),
+        ])
+    }
+}
+
+/// Print the failure of the current transfer to stderr as one JSON object.
+///
+/// # Arguments
+///
+/// * `message` - The error message that would otherwise be printed.
+/// * `status` - The HTTP status, when the failure is an error response.
+pub fn print(message: &str, status: Option<u16>) {
+    let metrics = writeout::snapshot();
+    let report = Report::new(
+        message,
+        PHASE.with(Cell::get),
+        status,
+        OS_ERROR.with(Cell::get),
+        &metrics.url_effective,
+    );
+    let mut stderr = io::stderr().lock();
+    let _ = writeln!(stderr, "{}", report.to_json());
+}
@@ -0,0 +1,89 @@
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::http::response;
+use crate::json::Value;
+use crate::writeout;
+use rurl::request::base64_encode;
+
+/// Read the body and print the whole response as one JSON object, for `--json-output`.
+///
+/// The object holds `status`, `http_version`, `url` (the effective URL after
+/// redirects), `headers` as `{"name", "value"}` objects in order, `body` with its
+/// `body_encoding` (`utf-8`, or `base64` for binary bodies), `size`, and `timing` in
+/// seconds since the start of the transfer.
+///
+/// # Arguments
+///
+/// * `headers` - The response header block, including the status line.
+/// * `body` - The response body.
+/// * `args` - A reference to an `Args` struct describing the request.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` once the object was written, or an error message if the body cannot be read.
+pub fn write(headers: &[u8], body: &mut dyn Read, args: &Args) -> Result<(), String> {
+    let mut bytes = Vec::new();
+    writeout::Counter(body)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Read error: {}", err))?;
+    let value = serialize(headers, &bytes, &args.url, &writeout::snapshot());
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", value).map_err(|err| format!("Write error: {}", err))
+}
+
+/// Build the `--json-output` object for a response with header block `headers` and body `body`.
+pub fn serialize(headers: &[u8], body: &[u8], url: &str, metrics: &writeout::Metrics) -> Value {
+    let string = |text: &str| Value::String(text.to_string());
+    let lossy = |bytes: &[u8]| Value::String(String::from_utf8_lossy(bytes).into_owned());
+    let seconds = |time: Option<Duration>| match time {
+        Some(time) => Value::Number(time.as_secs_f64()),
+        None => Value::Null,
+    };
+
+    let version = headers
+        .strip_prefix(b"HTTP/")
+        .and_then(|rest| rest.split(|&byte| byte == b' ').next())
+        .unwrap_or_default();
+    let fields = response::header_fields(headers)
+        .map(|(name, value)| {
+            Value::Object(vec![
+                ("name".to_string(), lossy(name)),
+                ("value".to_string(), lossy(value)),
+            ])
+        })
+        .collect();
+    let size = body.len();
+    let (body, encoding) = match std::str::from_utf8(body) {
+        Ok(text) => (string(text), "utf-8"),
+        Err(_) => (Value::String(base64_encode(body)), "base64"),
+    };
+
+    Value::Object(vec![
+        (
+            "status".to_string(),
+            Value::Number(f64::from(response::parse_status_line(headers).unwrap_or(0))),
+        ),
+        ("http_version".to_string(), lossy(version)),
+        ("url".to_string(), string(url)),
+        ("headers".to_string(), Value::Array(fields)),
+        ("body".to_string(), body),
+        ("body_encoding".to_string(), string(encoding)),
+        ("size".to_string(), Value::Number(size as f64)),
+        (
+            "timing".to_string(),
+            Value::Object(vec![
+                ("namelookup".to_string(), seconds(metrics.time_namelookup)),
+                ("connect".to_string(), seconds(metrics.time_connect)),
+                ("appconnect".to_string(), seconds(metrics.time_appconnect)),
+                (
+                    "starttransfer".to_string(),
+                    seconds(metrics.time_starttransfer),
+                ),
+                ("total".to_string(), seconds(metrics.time_total)),
+            ]),
+        ),
+    ])
+}
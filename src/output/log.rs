@@ -0,0 +1,96 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::args::Args;
+
+/// How much rurl reports about a transfer, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// `-s`: nothing but the body and errors
+    Silent,
+    /// Progress and warnings
+    Normal,
+    /// `-v`: connection steps and the headers sent and received
+    Verbose,
+    /// `-v` with `--trace`: also each read and write, to line up with the dump
+    Trace,
+}
+
+/// Decides which messages are shown and stamps them with `--trace-time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Logger {
+    pub level: Level,
+    pub timestamps: bool,
+}
+
+impl Logger {
+    /// The logger for the options in `args`.
+    pub fn new(args: &Args) -> Logger {
+        let level = if args.silent {
+            Level::Silent
+        } else if args.verbose && args.trace.is_some() {
+            Level::Trace
+        } else if args.verbose {
+            Level::Verbose
+        } else {
+            Level::Normal
+        };
+        Logger {
+            level,
+            timestamps: args.trace_time,
+        }
+    }
+
+    /// Whether messages at `level` are shown.
+    pub fn enabled(&self, level: Level) -> bool {
+        level != Level::Silent && self.level >= level
+    }
+
+    /// `message` as it is printed: with the time of day first under `--trace-time`.
+    pub fn line(&self, message: fmt::Arguments) -> String {
+        if self.timestamps {
+            format!("{} {}", timestamp(now()), message)
+        } else {
+            message.to_string()
+        }
+    }
+
+    /// Print `message` to stdout if `level` is enabled.
+    pub fn log(&self, level: Level, message: fmt::Arguments) {
+        if self.enabled(level) {
+            println!("{}", self.line(message));
+        }
+    }
+
+    /// Print `message` to stderr if `level` is enabled: for warnings, and for messages
+    /// that can arrive while a body streams to stdout.
+    pub fn log_err(&self, level: Level, message: fmt::Arguments) {
+        if self.enabled(level) {
+            eprintln!("{}", self.line(message));
+        }
+    }
+}
+
+/// Print a `-v` message for the transfer described by `args`.
+pub fn verbose(args: &Args, message: fmt::Arguments) {
+    Logger::new(args).log(Level::Verbose, message);
+}
+
+/// Time since the Unix epoch.
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Format a time since the Unix epoch as the UTC time of day, `HH:MM:SS.ffffff`.
+pub fn timestamp(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:06}",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+        since_epoch.subsec_micros()
+    )
+}
@@ -0,0 +1,3 @@
+pub mod error;
+pub mod json;
+pub mod log;
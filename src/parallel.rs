@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::args::Args;
+use crate::config::Profile;
+use crate::cookies::CookieJar;
+use crate::http::parts;
+use crate::http::response::remote_file_name;
+use crate::output;
+use crate::progress;
+use crate::quota;
+use crate::writeout;
+use rurl::url::Url;
+
+/// Transfers running at once under `-Z` unless `--parallel-max` is given
+pub const DEFAULT_MAX: usize = 50;
+
+/// The file each URL is saved to under `-Z`.
+///
+/// A URL's `-o` (or `-O`) name is used if it has one, otherwise the `-O` name, since
+/// concurrent bodies cannot share stdout. A name that an earlier URL already took
+/// gets `-2`, `-3`, ... before its extension.
+pub fn outputs(args: &Args) -> Vec<String> {
+    let mut outputs: Vec<String> = Vec::with_capacity(args.urls.len());
+    for index in 0..args.urls.len() {
+        let name = args.for_url(index).output.unwrap_or_else(|| {
+            Url::parse(&args.urls[index])
+                .map(|url| remote_file_name(&url))
+                .unwrap_or_else(|_| format!("rurl-{}.out", index + 1))
+        });
+        outputs.push(parts::unique(name, &outputs));
+    }
+    outputs
+}
+
+/// Run `-Z/--parallel`: fetch every URL of `args` on up to `--parallel-max` worker
+/// threads, each into its own file from `outputs`.
+///
+/// Every transfer starts from the `-b` cookies and reuses connections only within its
+/// worker. Their progress is drawn as one line, and a failed transfer is reported with
+/// its URL without stopping the others, unless `--max-total-bytes` runs out.
+///
+/// # Returns
+///
+/// * `i32` - The exit code: 0 if every transfer succeeded, 1 otherwise.
+pub fn run(args: &Args, profiles: &[Profile], jar: &CookieJar) -> i32 {
+    let outputs = outputs(args);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let workers = args.parallel_max.min(args.urls.len());
+    progress::start_parallel(args.urls.len(), args);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut jar = jar.clone();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= args.urls.len() || quota::exceeded() {
+                        break;
+                    }
+                    let mut args = args.for_url(index);
+                    args.output = Some(outputs[index].clone());
+                    let url = args.url.clone();
+                    let write_out = args.write_out.clone();
+                    let error_json = args.error_json;
+                    writeout::start(&url);
+                    output::error::start();
+                    progress::transfer_started();
+                    let result = crate::fetch(args, profiles, &mut jar);
+                    progress::transfer_finished();
+                    if let Some(template) = &write_out {
+                        if let Err(err) = &result {
+                            writeout::record_error(err);
+                        }
+                        writeout::print(template);
+                    }
+                    if let Err(err) = result {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        if error_json {
+                            output::error::print(&err, None);
+                        } else {
+                            progress::print_error(&format!("{}: {}", url, err));
+                        }
+                    }
+                }
+            });
+        }
+    });
+    progress::end_parallel();
+    if let Some(limit) = args.max_total_bytes.filter(|_| quota::exceeded()) {
+        eprintln!("{}", quota::summary(limit));
+    }
+    if failed.load(Ordering::Relaxed) > 0 {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{ErrorKind, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::history::split_words;
+
+/// Stream a response body into the standard input of `command`, for `--pipe-to`.
+///
+/// The command line is split into words like a shell would (quotes and backslashes,
+/// but no expansion) and run directly, with its stdout and stderr inherited. Each
+/// chunk is handed over as it arrives. A command that exits before reading the whole
+/// body, like `head`, ends the transfer early without an error.
+///
+/// # Arguments
+///
+/// * `command` - The command line, e.g. `tar -xz -C out`.
+/// * `body` - The response body.
+///
+/// # Returns
+///
+/// * `Result<i32, String>` - The command's exit code, or an error message if it could not be run or the body could not be read.
+pub fn run(command: &str, body: &mut dyn Read) -> Result<i32, String> {
+    let words = split_words(command)?;
+    let Some((program, arguments)) = words.split_first() else {
+        return Err("Empty --pipe-to command".to_string());
+    };
+    let mut child = Command::new(program)
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Failed to run '{}': {}", program, err))?;
+
+    let mut stdin = child.stdin.take();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut read_error = None;
+    while let Some(pipe) = &mut stdin {
+        let n = match body.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => {
+                read_error = Some(format!("Read error: {}", err));
+                break;
+            }
+        };
+        match pipe.write_all(&buffer[..n]) {
+            Ok(()) => {}
+            // The command stopped reading; its exit status tells whether that is a failure
+            Err(err) if err.kind() == ErrorKind::BrokenPipe => stdin = None,
+            Err(err) => {
+                read_error = Some(format!("Failed to write to '{}': {}", program, err));
+                break;
+            }
+        }
+    }
+    // Closing stdin tells the command the body is complete
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .map_err(|err| format!("Failed to run '{}': {}", program, err))?;
+    if let Some(err) = read_error {
+        return Err(err);
+    }
+    // Killed by a signal: no exit code, so report a plain failure
+    Ok(status.code().unwrap_or(1))
+}
@@ -0,0 +1,339 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+
+/// Minimum time between two redraws of the meter
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Which way the bytes are flowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+/// Check whether a meter should be drawn: not with `-s`, and only on a terminal.
+///
+/// A download meter is also left out when the body itself goes to the terminal,
+/// where the two would interleave, unless it is piped into a `--pipe-to` command.
+pub fn enabled(args: &Args, direction: Direction) -> bool {
+    if args.silent || args.tui || !io::stderr().is_terminal() {
+        return false;
+    }
+    match direction {
+        Direction::Upload => true,
+        Direction::Download => {
+            args.output.is_some() || args.pipe_to.is_some() || !io::stdout().is_terminal()
+        }
+    }
+}
+
+/// The transfers of `-Z/--parallel`, drawn together on one line instead of a meter each.
+struct Board {
+    transfers: usize,
+    finished: usize,
+    active: usize,
+    bytes: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl Board {
+    fn draw(&mut self, now: Instant) {
+        self.last_draw = Some(now);
+        let line = format_board(
+            self.finished,
+            self.transfers,
+            self.active,
+            self.bytes,
+            self.started.elapsed(),
+        );
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r{}\x1b[K", line);
+        let _ = stderr.flush();
+    }
+}
+
+static BOARD: Mutex<Option<Board>> = Mutex::new(None);
+
+/// Run `f` on the shared line if `-Z/--parallel` started one.
+fn with_board<T>(f: impl FnOnce(&mut Board) -> T) -> Option<T> {
+    BOARD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_mut()
+        .map(f)
+}
+
+/// Report the next `transfers` transfers on one shared line, drawn on a terminal unless `-s`.
+pub fn start_parallel(transfers: usize, args: &Args) {
+    let enabled = !args.silent && io::stderr().is_terminal();
+    *BOARD.lock().unwrap_or_else(PoisonError::into_inner) = Some(Board {
+        transfers,
+        finished: 0,
+        active: 0,
+        bytes: 0,
+        started: Instant::now(),
+        last_draw: None,
+        enabled,
+    });
+}
+
+/// Count a transfer as running on the shared line.
+pub fn transfer_started() {
+    with_board(|board| board.active += 1);
+}
+
+/// Count a transfer as finished on the shared line.
+pub fn transfer_finished() {
+    with_board(|board| {
+        board.active -= 1;
+        board.finished += 1;
+    });
+}
+
+/// Print `message` on stderr, above the shared line if one is drawn.
+pub fn print_error(message: &str) {
+    let printed = with_board(|board| {
+        if !board.enabled || board.last_draw.is_none() {
+            return false;
+        }
+        eprint!("\r\x1b[K{}\n", message);
+        board.draw(Instant::now());
+        true
+    });
+    if printed != Some(true) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Draw the shared line a last time and stop using it.
+pub fn end_parallel() {
+    let board = BOARD.lock().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(mut board) = board.filter(|board| board.enabled) {
+        board.draw(Instant::now());
+        eprintln!();
+    }
+}
+
+/// A curl-style progress meter drawn on stderr.
+///
+/// Under `-Z/--parallel` the bytes are added to the shared line instead.
+///
+/// Updates are throttled to `REFRESH_INTERVAL`; the final state is drawn when the
+/// meter is dropped, so every exit path leaves a complete line behind.
+pub struct Meter {
+    direction: Direction,
+    total: Option<u64>,
+    done: u64,
+    /// Bytes that were already present before this transfer, e.g. when resuming
+    offset: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl Meter {
+    /// Create a meter for a transfer of `total` bytes, if known, that starts at `offset`.
+    pub fn new(direction: Direction, total: Option<u64>, offset: u64, enabled: bool) -> Self {
+        Meter {
+            direction,
+            total,
+            done: offset,
+            offset,
+            started: Instant::now(),
+            last_draw: None,
+            enabled,
+        }
+    }
+
+    /// Record `n` more bytes and redraw if the last update is old enough.
+    pub fn advance(&mut self, n: u64) {
+        self.done += n;
+        let shared = with_board(|board| {
+            board.bytes += n;
+            let now = Instant::now();
+            if board.enabled
+                && board
+                    .last_draw
+                    .is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL)
+            {
+                board.draw(now);
+            }
+        });
+        if shared.is_some() {
+            return;
+        }
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_draw
+            .is_none_or(|last| now.duration_since(last) >= REFRESH_INTERVAL)
+        {
+            self.last_draw = Some(now);
+            self.draw();
+        }
+    }
+
+    fn draw(&self) {
+        let line = format_line(
+            self.direction,
+            self.done,
+            self.total,
+            self.done - self.offset,
+            self.started.elapsed(),
+        );
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r{}\x1b[K", line);
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        if self.enabled && self.last_draw.is_some() {
+            self.draw();
+            eprintln!();
+        }
+    }
+}
+
+/// A reader that advances a `Meter` with every read.
+pub struct Reader<R> {
+    inner: R,
+    meter: Meter,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R, meter: Meter) -> Self {
+        Reader { inner, meter }
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.meter.advance(n as u64);
+        Ok(n)
+    }
+}
+
+/// Format one meter line.
+///
+/// With a known total: `↓  45%  1.2 MiB / 2.6 MiB  512.0 KiB/s  ETA 0:03`; without
+/// one, the percentage and ETA are replaced by the elapsed time.
+///
+/// # Arguments
+///
+/// * `direction` - Download or upload, shown as an arrow.
+/// * `done` - Bytes of the resource present so far, including any resumed offset.
+/// * `total` - The full size, if known.
+/// * `transferred` - Bytes moved by this transfer, used for the speed.
+/// * `elapsed` - Time since the transfer started.
+pub fn format_line(
+    direction: Direction,
+    done: u64,
+    total: Option<u64>,
+    transferred: u64,
+    elapsed: Duration,
+) -> String {
+    let arrow = match direction {
+        Direction::Download => '↓',
+        Direction::Upload => '↑',
+    };
+    let seconds = elapsed.as_secs_f64();
+    let speed = if seconds > 0.0 {
+        transferred as f64 / seconds
+    } else {
+        0.0
+    };
+    let rate = format!("{}/s", format_bytes(speed as u64));
+
+    match total {
+        Some(total) if total > 0 => {
+            let percent = (done.min(total) * 100) / total;
+            let eta = if done >= total {
+                format!("in {}", format_duration(elapsed))
+            } else if speed > 0.0 {
+                let remaining = (total - done) as f64 / speed;
+                format!(
+                    "ETA {}",
+                    format_duration(Duration::from_secs_f64(remaining))
+                )
+            } else {
+                "ETA --:--".to_string()
+            };
+            format!(
+                "{} {:>3}%  {} / {}  {}  {}",
+                arrow,
+                percent,
+                format_bytes(done),
+                format_bytes(total),
+                rate,
+                eta
+            )
+        }
+        _ => format!(
+            "{} {}  {}  {}",
+            arrow,
+            format_bytes(done),
+            rate,
+            format_duration(elapsed)
+        ),
+    }
+}
+
+/// Format the shared `-Z/--parallel` line, e.g. `↓ 3/10 done, 4 active  12.5 MiB  2.0 MiB/s  0:06`.
+pub fn format_board(
+    finished: usize,
+    transfers: usize,
+    active: usize,
+    bytes: u64,
+    elapsed: Duration,
+) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let speed = if seconds > 0.0 {
+        bytes as f64 / seconds
+    } else {
+        0.0
+    };
+    format!(
+        "↓ {}/{} done, {} active  {}  {}/s  {}",
+        finished,
+        transfers,
+        active,
+        format_bytes(bytes),
+        format_bytes(speed as u64),
+        format_duration(elapsed)
+    )
+}
+
+/// Format a byte count with binary units, e.g. `512 B` or `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format a duration as `M:SS`, or `H:MM:SS` from one hour.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3_600 {
+        format!("{}:{:02}:{:02}", secs / 3_600, secs % 3_600 / 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+// Shared by the worker threads of `-Z/--parallel`
+static TOTAL: AtomicU64 = AtomicU64::new(0);
+static EXCEEDED: AtomicBool = AtomicBool::new(false);
+static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+
+/// Body bytes downloaded so far by all transfers of this run.
+pub fn total() -> u64 {
+    TOTAL.load(Ordering::Relaxed)
+}
+
+/// Whether a transfer was stopped by `--max-total-bytes`.
+pub fn exceeded() -> bool {
+    EXCEEDED.load(Ordering::Relaxed)
+}
+
+/// Record that a transfer finished, for the summary printed when the budget runs out.
+pub fn complete() {
+    COMPLETED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Describe how far the run got before the budget of `limit` bytes ran out.
+pub fn summary(limit: u64) -> String {
+    format!(
+        "Stopped: downloaded {} bytes, all of the --max-total-bytes budget of {} bytes (transfers completed: {})",
+        total(),
+        limit,
+        COMPLETED.load(Ordering::Relaxed)
+    )
+}
+
+/// Counts the body bytes read through it against the budget shared by the whole run.
+///
+/// A read that would take the total past `limit` returns the bytes up to it, and the
+/// next read fails, so the transfer stops with an error once the budget is spent.
+/// Only the bytes returned are counted.
+pub struct Reader<R> {
+    inner: R,
+    limit: Option<u64>,
+    cut: bool,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R, limit: Option<u64>) -> Self {
+        Reader {
+            inner,
+            limit,
+            cut: false,
+        }
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let exceeded = |limit| {
+            EXCEEDED.store(true, Ordering::Relaxed);
+            io::Error::other(format!("download budget of {} bytes exceeded", limit))
+        };
+        if let Some(limit) = self.limit.filter(|_| self.cut) {
+            return Err(exceeded(limit));
+        }
+        let n = self.inner.read(buf)?;
+        let Some(limit) = self.limit else {
+            TOTAL.fetch_add(n as u64, Ordering::Relaxed);
+            return Ok(n);
+        };
+        // Take what is left of the budget, which other transfers may be spending too
+        let allowed = |total: u64| (n as u64).min(limit.saturating_sub(total));
+        let previous = TOTAL
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |total| {
+                Some(total + allowed(total))
+            })
+            .unwrap_or_else(|total| total);
+        let allowed = allowed(previous) as usize;
+        if allowed < n {
+            self.cut = true;
+            if allowed == 0 {
+                return Err(exceeded(limit));
+            }
+        }
+        Ok(allowed)
+    }
+}
@@ -0,0 +1,670 @@
+//! Requests and their HTTP/1.1 and HTTP/2 wire forms.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::chunked;
+use crate::method::Method;
+use crate::url::Url;
+use crate::version::Version;
+
+/// Request headers that are connection-specific and forbidden in HTTP/2.
+const CONNECTION_HEADERS: [&str; 6] = [
+    "connection",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// TLS settings for a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    /// Minimum protocol version: `1.0`, `1.1`, `1.2`, or `1.3`
+    pub min_version: Option<String>,
+    /// Use HTTP/2 (ALPN over TLS, prior knowledge otherwise)
+    pub http2: bool,
+}
+
+/// A complete HTTP request.
+///
+/// Build one with `Request::builder`, then serialize it with `to_http1` or
+/// `http2_headers`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    method: Method,
+    url: Url,
+    target: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    content_length: Option<u64>,
+    chunked: bool,
+    trailers: Vec<String>,
+    accept_trailers: bool,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    keep_alive: bool,
+    tls: TlsOptions,
+    allow_unsafe: bool,
+}
+
+impl Request {
+    /// Start building a request for `method` and `url`.
+    pub fn builder(method: &str, url: &str) -> RequestBuilder {
+        RequestBuilder::new(method, url)
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The header fields in the order they are sent, excluding `Host`.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Find a header value by name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The in-memory body, if any.
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+
+    /// The Content-Length sent with the request, if it has a body that is not chunked.
+    pub fn content_length(&self) -> Option<u64> {
+        if self.chunked {
+            return None;
+        }
+        self.content_length
+            .or_else(|| self.body.as_ref().map(|body| body.len() as u64))
+    }
+
+    /// Whether the body is sent with `Transfer-Encoding: chunked`.
+    pub fn chunked(&self) -> bool {
+        self.chunked
+    }
+
+    /// The names of the trailer fields declared to follow the body.
+    pub fn trailers(&self) -> &[String] {
+        &self.trailers
+    }
+
+    /// Whether `TE: trailers` tells the server that response trailers are accepted.
+    pub fn accept_trailers(&self) -> bool {
+        self.accept_trailers
+    }
+
+    /// The request target: the path and query unless overridden.
+    pub fn target(&self) -> String {
+        self.target
+            .clone()
+            .unwrap_or_else(|| self.url.request_target())
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Whether the connection is kept open for further requests.
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    pub fn tls(&self) -> &TlsOptions {
+        &self.tls
+    }
+
+    /// Whether the request skipped validation with `RequestBuilder::allow_unsafe`.
+    pub fn allows_unsafe(&self) -> bool {
+        self.allow_unsafe
+    }
+
+    /// Add a header field unless one with the same name is already set.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_default_header(&mut self, name: &str, value: &str) {
+        if self.header(name).is_none() {
+            self.headers.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    /// Send the absolute URL as the request target, as a forward proxy expects,
+    /// unless a target was set.
+    #[cfg(feature = "std")]
+    pub(crate) fn use_absolute_target(&mut self) {
+        if self.target.is_none() {
+            self.target = Some(format!(
+                "{}://{}{}",
+                self.url.scheme(),
+                self.url.authority(),
+                self.url.request_target()
+            ));
+        }
+    }
+
+    /// Parse a raw HTTP/1.1 request, such as the output of `to_http1`.
+    ///
+    /// The request line may use an origin-form target (`/path?query`), resolved
+    /// against the `Host` header with the scheme of `base`, or an absolute URL,
+    /// which is kept as the target. A `Host` without a port that names the host of
+    /// `base` keeps its port. `Host`, `Connection`, `TE`, and `Content-Length` are dropped because
+    /// `to_http1` derives them, keeping only whether keep-alive and trailers were asked for; everything after the blank line is the body. Lines
+    /// may end in CRLF or a bare LF.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The request text.
+    /// * `base` - The URL the request was composed for.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Request, &'static str>` - The request if successful, or an error message if unsuccessful.
+    pub fn parse_http1(raw: &str, base: &Url) -> Result<Request, &'static str> {
+        let (head, body) = match raw.find("\r\n\r\n") {
+            Some(end) if !raw[..end].contains("\n\n") => (&raw[..end], &raw[end + 4..]),
+            _ => match raw.find("\n\n") {
+                Some(end) => (&raw[..end], &raw[end + 2..]),
+                None => (raw.trim_end(), ""),
+            },
+        };
+        let mut lines = head.lines().map(|line| line.trim_end_matches('\r'));
+
+        let request_line = lines.next().ok_or("Missing request line")?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("Invalid request line");
+        };
+        Version::parse(version).map_err(|_| "Invalid request line")?;
+
+        let mut host = None;
+        let mut keep_alive = false;
+        let mut accept_trailers = false;
+        let mut headers = Vec::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once(':').ok_or("Invalid header format")?;
+            match name.trim().to_ascii_lowercase().as_str() {
+                "host" => host = Some(value.trim().to_string()),
+                "connection" => {
+                    keep_alive = value
+                        .split(',')
+                        .any(|token| token.trim().eq_ignore_ascii_case("keep-alive"))
+                }
+                "te" => accept_trailers = value.trim().eq_ignore_ascii_case("trailers"),
+                "content-length" => {}
+                _ => headers.push((name, value)),
+            }
+        }
+
+        let mut builder = if target.starts_with('/') {
+            let mut host = host.ok_or("Missing Host header")?;
+            if host == base.host().to_string() {
+                host = format!("{}:{}", host, base.port());
+            }
+            RequestBuilder::new(method, &format!("{}://{}{}", base.scheme(), host, target))
+        } else {
+            RequestBuilder::new(method, target).target(target)
+        };
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        builder = builder
+            .keep_alive(keep_alive)
+            .accept_trailers(accept_trailers);
+        if !body.is_empty() {
+            builder = builder.body(body);
+        }
+        builder.build()
+    }
+
+    /// Serialize the request as HTTP/1.1: request line, `Host`, headers, and body.
+    ///
+    /// `Host` carries the port only when it is not the scheme default, the same
+    /// authority HTTP/2 sends as `:authority`.
+    ///
+    /// A body declared with `RequestBuilder::content_length` is not included and
+    /// must be written after these bytes. A chunked body is written as a single
+    /// chunk, without the last chunk: further chunks may follow, and `end_chunked`
+    /// ends the body.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<u8>` - The request bytes.
+    pub fn to_http1(&self) -> Vec<u8> {
+        let mut head = format!(
+            "{} {} {}\r\nHost: {}\r\nConnection: {}{}\r\n",
+            self.method,
+            self.target(),
+            Version::Http11,
+            self.url.authority(),
+            if self.keep_alive {
+                "keep-alive"
+            } else {
+                "close"
+            },
+            // TE is hop-by-hop, so it must be listed in Connection
+            if self.accept_trailers { ", TE" } else { "" }
+        );
+        if self.accept_trailers {
+            head.push_str("TE: trailers\r\n");
+        }
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        // Unsafe requests that frame the body themselves get no framing added
+        let framed = self.allow_unsafe
+            && (self.header("content-length").is_some()
+                || self.header("transfer-encoding").is_some());
+        if let Some(length) = self.content_length().filter(|_| !framed) {
+            head.push_str(&format!("Content-Length: {}\r\n", length));
+        }
+        if self.chunked {
+            head.push_str("Transfer-Encoding: chunked\r\n");
+        }
+        if !self.trailers.is_empty() {
+            head.push_str(&format!("Trailer: {}\r\n", self.trailers.join(", ")));
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        if let Some(body) = &self.body {
+            if self.chunked {
+                bytes.extend(chunked::encode_chunk(body));
+            } else {
+                bytes.extend_from_slice(body);
+            }
+        }
+        bytes
+    }
+
+    /// The last chunk and trailer section that end a chunked body.
+    ///
+    /// Call it once the body has been sent, so trailer values such as checksums can be
+    /// computed from the data streamed. `value` is asked for each declared trailer in
+    /// order; fields it returns None for are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Computes the value of the trailer field with the given name.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<u8>` - The bytes ending the body.
+    pub fn end_chunked(&self, mut value: impl FnMut(&str) -> Option<String>) -> Vec<u8> {
+        let fields: Vec<(String, String)> = self
+            .trailers
+            .iter()
+            .filter_map(|name| Some((name.clone(), value(name)?)))
+            .collect();
+        chunked::encode_end(&fields)
+    }
+
+    /// The HTTP/2 header list: pseudo-headers first, then lowercased fields.
+    ///
+    /// Connection-specific fields, which HTTP/2 forbids, are dropped.
+    pub fn http2_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![
+            (":method".to_string(), self.method.to_string()),
+            (":scheme".to_string(), self.url.scheme().to_string()),
+            (":authority".to_string(), self.url.authority()),
+            (":path".to_string(), self.target()),
+        ];
+        // The only TE value HTTP/2 allows
+        if self.accept_trailers {
+            headers.push(("te".to_string(), "trailers".to_string()));
+        }
+        for (name, value) in &self.headers {
+            let name = name.to_lowercase();
+            if !CONNECTION_HEADERS.contains(&name.as_str()) {
+                headers.push((name, value.clone()));
+            }
+        }
+        if let Some(length) = self.content_length() {
+            headers.push(("content-length".to_string(), length.to_string()));
+        }
+        headers
+    }
+}
+
+/// Builder for a `Request`.
+///
+/// Errors, such as an invalid URL or header, are reported by `build`.
+///
+/// ```
+/// use rurl::request::Request;
+///
+/// let request = Request::builder("POST", "https://api.example.com/items")
+///     .header("Content-Type", "application/json")
+///     .basic_auth("alice", Some("secret"))
+///     .body(r#"{"name":"widget"}"#)
+///     .build()
+///     .unwrap();
+/// assert_eq!(request.content_length(), Some(17));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestBuilder {
+    method: Method,
+    url: Result<Url, &'static str>,
+    target: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    content_length: Option<u64>,
+    chunked: bool,
+    trailers: Vec<String>,
+    accept_trailers: bool,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    keep_alive: bool,
+    tls: TlsOptions,
+    allow_unsafe: bool,
+    raw_method: bool,
+    check_target: bool,
+    error: Option<&'static str>,
+}
+
+impl RequestBuilder {
+    /// Start a request for `method` (uppercased) and `url`.
+    pub fn new(method: &str, url: &str) -> Self {
+        RequestBuilder {
+            method: Method::from(method.to_uppercase().as_str()),
+            url: Url::parse(url),
+            target: None,
+            headers: Vec::new(),
+            body: None,
+            content_length: None,
+            chunked: false,
+            trailers: Vec::new(),
+            accept_trailers: false,
+            connect_timeout: None,
+            read_timeout: None,
+            keep_alive: false,
+            tls: TlsOptions::default(),
+            allow_unsafe: false,
+            raw_method: false,
+            check_target: false,
+            error: None,
+        }
+    }
+
+    /// Add a header field; repeated names are all sent.
+    ///
+    /// `build` checks that the name is a token and that the value holds no control
+    /// characters, and refuses `Content-Length` and `Transfer-Encoding`, which are
+    /// derived from the body, unless `allow_unsafe` is set.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a header given as a `Name: value` line.
+    pub fn header_line(mut self, line: &str) -> Self {
+        let Some((name, value)) = line.split_once(':') else {
+            self.error.get_or_insert("Invalid header format");
+            return self;
+        };
+        self.header(name, value)
+    }
+
+    /// Send Basic credentials in the `Authorization` header.
+    pub fn basic_auth(self, user: &str, password: Option<&str>) -> Self {
+        let credentials = match password {
+            Some(password) => format!("{}:{}", user, password),
+            None => user.to_string(),
+        };
+        let encoded = base64_encode(credentials.as_bytes());
+        self.header("Authorization", &format!("Basic {}", encoded))
+    }
+
+    /// Send an OAuth 2.0 bearer token in the `Authorization` header (RFC 6750).
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Set an in-memory body; `Content-Length` is derived from it.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self.content_length = None;
+        self
+    }
+
+    /// Declare a body of `length` bytes that the caller streams after the head.
+    pub fn content_length(mut self, length: u64) -> Self {
+        self.body = None;
+        self.content_length = Some(length);
+        self.chunked = false;
+        self
+    }
+
+    /// Send the body with `Transfer-Encoding: chunked`, for bodies whose length is
+    /// not known up front. An in-memory body becomes the first chunk.
+    pub fn chunked(mut self) -> Self {
+        self.content_length = None;
+        self.chunked = true;
+        self
+    }
+
+    /// Declare a trailer field sent after a chunked body; its value is given to
+    /// `Request::end_chunked`. Implies `chunked`.
+    pub fn trailer(mut self, name: &str) -> Self {
+        let name = name.trim();
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_graphic() && b != b':') {
+            self.error.get_or_insert("Invalid trailer name");
+            return self;
+        }
+        self.trailers.push(name.to_string());
+        self.chunked()
+    }
+
+    /// Tell the server that trailer fields in the response are understood (`TE: trailers`).
+    pub fn accept_trailers(mut self, enabled: bool) -> Self {
+        self.accept_trailers = enabled;
+        self
+    }
+
+    /// Override the request target, e.g. with the absolute form used towards a proxy.
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Send `target` as the request target, checked against the method when building.
+    ///
+    /// Besides the origin form (`/path?query`) and the absolute form
+    /// (`http://host/path`), this allows the asterisk form `*` with OPTIONS and requires
+    /// the authority form `host:port` with CONNECT (RFC 9112, Section 3.2).
+    pub fn request_target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self.check_target = true;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Ask the server to keep the connection open (`Connection: keep-alive`) instead
+    /// of closing it after the response, which is the default.
+    pub fn keep_alive(mut self, enabled: bool) -> Self {
+        self.keep_alive = enabled;
+        self
+    }
+
+    /// Require at least this TLS version (`1.0`, `1.1`, `1.2`, or `1.3`).
+    pub fn tls_version(mut self, version: &str) -> Self {
+        if matches!(version, "1.0" | "1.1" | "1.2" | "1.3") {
+            self.tls.min_version = Some(version.to_string());
+        } else {
+            self.error.get_or_insert("Invalid TLS version");
+        }
+        self
+    }
+
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.tls.http2 = enabled;
+        self
+    }
+
+    /// Send the headers exactly as given, for deliberately malformed test requests
+    /// such as request smuggling probes.
+    ///
+    /// Header names are kept untrimmed and values may contain anything, including line
+    /// breaks. When the headers include `Content-Length` or `Transfer-Encoding`, no
+    /// framing header is added for the body.
+    pub fn allow_unsafe(mut self, enabled: bool) -> Self {
+        self.allow_unsafe = enabled;
+        self
+    }
+
+    /// Use `method` verbatim, without uppercasing or checking it.
+    pub fn raw_method(mut self, method: &str) -> Self {
+        self.method = Method::Extension(method.to_string());
+        self.raw_method = true;
+        self
+    }
+
+    /// Finish the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Request, &'static str>` - The request if successful, or the first error recorded while building.
+    pub fn build(self) -> Result<Request, &'static str> {
+        let url = self.url?;
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        if !self.raw_method && !is_token(self.method.as_str()) {
+            return Err("Invalid HTTP method");
+        }
+        if !self.trailers.is_empty() && !self.chunked {
+            return Err("Trailers require a chunked body");
+        }
+        if let Some(target) = self.target.as_deref().filter(|_| self.check_target) {
+            check_target(&self.method, target)?;
+        }
+
+        let mut headers = Vec::with_capacity(self.headers.len());
+        for (name, value) in self.headers {
+            if self.allow_unsafe {
+                headers.push((name, value.trim_start().to_string()));
+                continue;
+            }
+            let (name, value) = (name.trim(), value.trim());
+            if !is_token(name) {
+                return Err("Invalid header name");
+            }
+            if value.bytes().any(|b| (b < 0x20 && b != b'\t') || b == 0x7f) {
+                return Err("Invalid header value");
+            }
+            if name.eq_ignore_ascii_case("content-length")
+                || name.eq_ignore_ascii_case("transfer-encoding")
+            {
+                return Err("Content-Length and Transfer-Encoding are set from the body");
+            }
+            headers.push((name.to_string(), value.to_string()));
+        }
+
+        Ok(Request {
+            method: self.method,
+            url,
+            target: self.target,
+            headers,
+            body: self.body,
+            content_length: self.content_length,
+            chunked: self.chunked,
+            trailers: self.trailers,
+            accept_trailers: self.accept_trailers,
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            keep_alive: self.keep_alive,
+            tls: self.tls,
+            allow_unsafe: self.allow_unsafe,
+        })
+    }
+}
+
+/// Check that `target` has a request-target form allowed for `method`.
+fn check_target(method: &Method, target: &str) -> Result<(), &'static str> {
+    if target.is_empty() || target.bytes().any(|b| b <= b' ' || b == 0x7f) {
+        return Err("Invalid request target");
+    }
+    if *method == Method::Connect {
+        return match target.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(()),
+            _ => Err("CONNECT requires a host:port request target"),
+        };
+    }
+    if target == "*" {
+        return if *method == Method::Options {
+            Ok(())
+        } else {
+            Err("The * request target is only allowed with OPTIONS")
+        };
+    }
+    if target.starts_with('/') || target.contains("://") {
+        return Ok(());
+    }
+    Err("A host:port request target is only allowed with CONNECT")
+}
+
+/// Check that `s` is an HTTP token, as used for methods and header names.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Base64 encode a byte slice
+pub fn base64_encode(data: &[u8]) -> String {
+    const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        let b1 = data[i];
+        let b2 = if i + 1 < data.len() { data[i + 1] } else { 0 };
+        let b3 = if i + 2 < data.len() { data[i + 2] } else { 0 };
+
+        result.push(BASE64_CHARS[(b1 >> 2) as usize] as char);
+        result.push(BASE64_CHARS[(((b1 & 0x03) << 4) | (b2 >> 4)) as usize] as char);
+
+        if i + 1 < data.len() {
+            result.push(BASE64_CHARS[(((b2 & 0x0f) << 2) | (b3 >> 6)) as usize] as char);
+        } else {
+            result.push('=');
+        }
+
+        if i + 2 < data.len() {
+            result.push(BASE64_CHARS[(b3 & 0x3f) as usize] as char);
+        } else {
+            result.push('=');
+        }
+
+        i += 3;
+    }
+
+    result
+}
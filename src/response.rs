@@ -0,0 +1,254 @@
+//! Parsing of HTTP/1.x response heads.
+//!
+//! These helpers work on the raw header block, from the status line up to and
+//! including the blank line, and never allocate except to return owned values.
+//! `Response` holds a parsed head together with the URL it came from.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::status::StatusCode;
+use crate::url::Url;
+use crate::version::Version;
+
+/// A parsed response head and the URL that answered it.
+///
+/// After redirects were followed, `url` is the effective URL, the last one in the
+/// chain, and `redirects` tells how many hops led there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    status: StatusCode,
+    version: Version,
+    headers: Vec<(String, String)>,
+    url: Url,
+    redirects: usize,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Parse the head of the response received from `url` after `redirects` redirects.
+    ///
+    /// # Arguments
+    ///
+    /// * `head` - The header block, from the status line up to and including the blank line.
+    /// * `url` - The URL the response came from.
+    /// * `redirects` - The number of redirects followed before it, 0 for a direct answer.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response, &'static str>` - The response if successful, or an error message if the status line is invalid.
+    pub fn parse(head: &[u8], url: Url, redirects: usize) -> Result<Response, &'static str> {
+        let headers = header_fields(&head[..find_header_end(head).unwrap_or(head.len())])
+            .map(|(name, value)| {
+                (
+                    String::from_utf8_lossy(name).into_owned(),
+                    String::from_utf8_lossy(value).into_owned(),
+                )
+            })
+            .collect();
+        Ok(Response {
+            status: parse_status(head)?,
+            version: parse_version(head)?,
+            headers,
+            url,
+            redirects,
+            body: Vec::new(),
+        })
+    }
+
+    /// The response with `body` as its body.
+    pub fn with_body(mut self, body: Vec<u8>) -> Response {
+        self.body = body;
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// All header fields in order, with values trimmed.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// The value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The effective URL: where the response came from after any redirects.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// How many redirects were followed to reach `url`.
+    pub fn redirects(&self) -> usize {
+        self.redirects
+    }
+
+    /// The body, empty unless it was read with the head, as by `client::Client`.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// Find the end of the header block.
+///
+/// # Arguments
+///
+/// * `response` - A slice of bytes representing an HTTP response.
+///
+/// # Returns
+///
+/// * `Option<usize>` - The index just past the blank line ending the headers, or None if it has not arrived yet.
+pub fn find_header_end(response: &[u8]) -> Option<usize> {
+    response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+/// Iterate over the header fields of a response as (name, value) byte slices.
+///
+/// The status line is skipped and values are trimmed. No allocation is performed;
+/// the returned slices point into `headers`.
+pub fn header_fields(headers: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    headers.split(|&b| b == b'\n').skip(1).filter_map(|line| {
+        let colon = line.iter().position(|&b| b == b':')?;
+        Some((trim_ascii(&line[..colon]), trim_ascii(&line[colon + 1..])))
+    })
+}
+
+/// Find the value of the first header named `name`, compared case-insensitively.
+pub fn find_header<'a>(headers: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    header_fields(headers)
+        .find(|(field, _)| field.eq_ignore_ascii_case(name.as_bytes()))
+        .map(|(_, value)| value)
+}
+
+/// Check whether `haystack` contains `needle`, ignoring ASCII case.
+pub fn contains_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window.eq_ignore_ascii_case(needle))
+}
+
+/// Trim ASCII whitespace (including the trailing CR of a header line) from both ends.
+fn trim_ascii(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |pos| pos + 1);
+    &bytes[start..end]
+}
+
+/// Extract the Content-Length header value from an HTTP response.
+///
+/// # Arguments
+///
+/// * `response` - A slice of bytes representing an HTTP response.
+///
+/// # Returns
+///
+/// * `Option<usize>` - The Content-Length value if found, otherwise None.
+pub fn get_content_length(response: &[u8]) -> Option<usize> {
+    let headers = &response[..find_header_end(response).unwrap_or(response.len())];
+    let value = find_header(headers, "content-length")?;
+    core::str::from_utf8(value).ok()?.parse::<usize>().ok()
+}
+
+/// Check if the response is using chunked transfer encoding.
+///
+/// # Arguments
+///
+/// * `response` - A slice of bytes representing an HTTP response.
+///
+/// # Returns
+///
+/// * `bool` - Whether the response is using chunked transfer encoding.
+pub fn is_chunked_transfer(response: &[u8]) -> bool {
+    let headers = &response[..find_header_end(response).unwrap_or(response.len())];
+    find_header(headers, "transfer-encoding")
+        .is_some_and(|value| contains_ignore_ascii_case(value, b"chunked"))
+}
+
+/// Parse the status line of an HTTP response.
+///
+/// This function takes a slice of bytes representing an HTTP response and parses the status line.
+///
+/// # Arguments
+///
+/// * `response` - A slice of bytes representing an HTTP response.
+///
+/// # Returns
+///
+/// * `Result<u16, &'static str>` - The status code of the response if successful, or an error message if unsuccessful.
+pub fn parse_status_line(response: &[u8]) -> Result<u16, &'static str> {
+    let status_line = match response.split(|&b| b == b'\r').next() {
+        Some(line) => line,
+        None => return Err("Invalid response format"),
+    };
+
+    let status_line = match core::str::from_utf8(status_line) {
+        Ok(line) => line,
+        Err(_) => return Err("Invalid UTF-8 in status line"),
+    };
+
+    let status_code = status_line
+        .split_whitespace()
+        .nth(1)
+        .ok_or("Missing status code")?
+        .parse::<u16>()
+        .map_err(|_| "Invalid status code")?;
+
+    Ok(status_code)
+}
+
+/// Parse the status code of an HTTP response.
+///
+/// # Returns
+///
+/// * `Result<StatusCode, &'static str>` - The status code if successful, or an error message if the status line is invalid.
+pub fn parse_status(response: &[u8]) -> Result<StatusCode, &'static str> {
+    StatusCode::from_u16(parse_status_line(response)?)
+}
+
+/// Parse the protocol version of an HTTP response from its status line.
+///
+/// # Returns
+///
+/// * `Result<Version, &'static str>` - The version if successful, or an error message if it is missing or unsupported.
+pub fn parse_version(response: &[u8]) -> Result<Version, &'static str> {
+    let version = response
+        .split(|&b| b == b' ')
+        .next()
+        .ok_or("Invalid response format")?;
+    Version::parse(core::str::from_utf8(version).map_err(|_| "Invalid UTF-8 in status line")?)
+}
+
+/// Extract the Location header from an HTTP response.
+///
+/// # Arguments
+///
+/// * `response` - A slice of bytes representing an HTTP response.
+///
+/// # Returns
+///
+/// * `Option<String>` - The Location header value if found, otherwise None.
+pub fn get_location(response: &[u8]) -> Option<String> {
+    let headers = &response[..find_header_end(response).unwrap_or(response.len())];
+    let value = find_header(headers, "location")?;
+    Some(String::from_utf8_lossy(value).into_owned())
+}
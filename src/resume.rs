@@ -0,0 +1,260 @@
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::args::Args;
+use crate::http::response;
+use crate::json::{self, Value};
+
+/// Progress of one download recorded in a `--resume-state` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub url: String,
+    pub output: String,
+    pub complete: bool,
+    pub bytes: u64,
+}
+
+/// The set of downloads recorded in a `--resume-state` file, keyed by output path.
+#[derive(Debug, Default)]
+pub struct ResumeState {
+    pub entries: Vec<Entry>,
+}
+
+impl ResumeState {
+    /// Parse a state file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - The recorded downloads if successful, or an error message if the file is malformed.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let root = json::parse(contents)?;
+        let entries = root
+            .get("entries")
+            .and_then(Value::as_array)
+            .ok_or("Invalid resume state: missing entries")?;
+
+        let mut state = ResumeState::default();
+        for entry in entries {
+            let field = |name: &str| {
+                entry
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .ok_or(format!("Invalid resume state: entry without {}", name))
+            };
+            state.entries.push(Entry {
+                url: field("url")?,
+                output: field("output")?,
+                complete: field("status")? == "complete",
+                bytes: entry.get("bytes").and_then(Value::as_u64).unwrap_or(0),
+            });
+        }
+        Ok(state)
+    }
+
+    /// Load a state file, starting empty if it does not exist yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(ResumeState::default()),
+            Err(err) => Err(format!("Failed to read resume state '{}': {}", path, err)),
+        }
+    }
+
+    /// Write the state file, one entry per line so it diffs and greps well.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let entries: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let status = if entry.complete {
+                    "complete"
+                } else {
+                    "partial"
+                };
+                Value::Object(vec![
+                    ("url".to_string(), Value::String(entry.url.clone())),
+                    ("output".to_string(), Value::String(entry.output.clone())),
+                    ("status".to_string(), Value::String(status.to_string())),
+                    ("bytes".to_string(), Value::Number(entry.bytes as f64)),
+                ])
+                .to_string()
+            })
+            .collect();
+        let contents = format!(
+            "{{\n  \"version\": 1,\n  \"entries\": [\n    {}\n  ]\n}}\n",
+            entries.join(",\n    ")
+        );
+        fs::write(path, contents)
+            .map_err(|err| format!("Failed to write resume state '{}': {}", path, err))
+    }
+
+    /// Find the entry for an output file.
+    pub fn find(&self, output: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.output == output)
+    }
+
+    /// Record the progress of a download, replacing any earlier entry for the same output.
+    pub fn record(&mut self, entry: Entry) {
+        match self.entries.iter_mut().find(|e| e.output == entry.output) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+}
+
+/// What to do with a download before it starts.
+#[derive(Debug, PartialEq)]
+pub enum Plan {
+    /// The output is already complete
+    Skip,
+    /// Download from the start
+    Fresh,
+    /// Request the rest of the body, starting at this offset
+    Resume(u64),
+}
+
+/// Decide how to fetch `args.url` into `args.output` from the state file.
+///
+/// A finished download is skipped when its output still has the recorded size; a
+/// partial one resumes from the current size of its output file via a Range
+/// request. The download is recorded as partial before it starts, so an
+/// interrupted run leaves a resumable entry behind.
+///
+/// # Arguments
+///
+/// * `args` - The arguments; for a resumed download, `resume_from` and a Range header are set.
+///
+/// # Returns
+///
+/// * `Result<Plan, String>` - The plan if successful, or an error message if the state file could not be used.
+pub fn prepare(args: &mut Args) -> Result<Plan, String> {
+    let (Some(path), Some(output)) = (args.resume_state.clone(), args.output.clone()) else {
+        return Err("--resume-state requires -o/--output".to_string());
+    };
+
+    let mut state = ResumeState::load(&path)?;
+    let on_disk = fs::metadata(&output).map(|meta| meta.len()).ok();
+
+    let plan = match (state.find(&output), on_disk) {
+        (Some(entry), Some(size)) if entry.url == args.url && entry.complete => {
+            if size == entry.bytes {
+                Plan::Skip
+            } else {
+                Plan::Fresh
+            }
+        }
+        (Some(entry), Some(size)) if entry.url == args.url && size > 0 => Plan::Resume(size),
+        _ => Plan::Fresh,
+    };
+
+    if plan == Plan::Skip {
+        return Ok(plan);
+    }
+    if let Plan::Resume(offset) = plan {
+        args.resume_from = Some(offset);
+        args.headers.push(format!("Range: bytes={}-", offset));
+    }
+
+    state.record(Entry {
+        url: args.url.clone(),
+        output,
+        complete: false,
+        bytes: on_disk.unwrap_or(0),
+    });
+    state.save(&path)?;
+    Ok(plan)
+}
+
+/// Mark the download into `args.output` as complete with its final size.
+pub fn complete(args: &Args, bytes: u64) -> Result<(), String> {
+    let (Some(path), Some(output)) = (&args.resume_state, &args.output) else {
+        return Ok(());
+    };
+    let mut state = ResumeState::load(path)?;
+    let url = state
+        .find(output)
+        .map_or_else(|| args.url.clone(), |entry| entry.url.clone());
+    state.record(Entry {
+        url,
+        output: output.clone(),
+        complete: true,
+        bytes,
+    });
+    state.save(path)
+}
+
+/// Where `-C/--continue-at` resumes a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContinueAt {
+    /// `-C -`: from the current size of the output file
+    Auto,
+    /// `-C <offset>`: from this byte
+    Offset(u64),
+}
+
+impl ContinueAt {
+    /// Parse a `-C` value: `-` or a byte offset.
+    pub fn parse(value: &str) -> Result<Self, &'static str> {
+        match value.trim() {
+            "-" => Ok(ContinueAt::Auto),
+            offset => offset
+                .parse()
+                .map(ContinueAt::Offset)
+                .map_err(|_| "Invalid -C offset"),
+        }
+    }
+}
+
+/// Ask for the rest of the body with a Range request, as given by `-C/--continue-at`.
+///
+/// `-C -` continues from the size of the output file; a missing file starts from
+/// the beginning. Offset 0 sends no Range header.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if successful, or an error message if `-C -` has no output file.
+pub fn continue_at(args: &mut Args, at: ContinueAt) -> Result<(), String> {
+    let offset = match at {
+        ContinueAt::Offset(offset) => offset,
+        ContinueAt::Auto => {
+            let output = args
+                .output
+                .as_ref()
+                .ok_or("-C - requires -o/--output or -O")?;
+            fs::metadata(output).map(|meta| meta.len()).unwrap_or(0)
+        }
+    };
+    if offset > 0 {
+        args.resume_from = Some(offset);
+        args.headers.push(format!("Range: bytes={}-", offset));
+    }
+    Ok(())
+}
+
+/// Check that a response continues a `-C` transfer at `offset`: 206 with a matching `Content-Range`.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if the server resumed at `offset`, or an error message otherwise.
+pub fn check_continued(status: u16, headers: &[u8], offset: u64) -> Result<(), String> {
+    if status != 206 {
+        return Err(format!(
+            "Server does not support byte ranges (HTTP {}); cannot resume at byte {}",
+            status, offset
+        ));
+    }
+    let start = response::find_header(headers, "content-range")
+        .and_then(|value| std::str::from_utf8(value).ok())
+        .and_then(|value| value.trim().strip_prefix("bytes "))
+        .and_then(|range| range.split('-').next())
+        .and_then(|start| start.trim().parse::<u64>().ok());
+    match start {
+        Some(start) if start == offset => Ok(()),
+        Some(start) => Err(format!(
+            "Server resumed at byte {} instead of {}",
+            start, offset
+        )),
+        None => Err("Invalid Content-Range in the partial response".to_string()),
+    }
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::fs;
+
+use crate::http::negotiate::{self, Mismatch};
+use crate::json::Value;
+use crate::writeout;
+
+/// The outcome of one URL, as far as it got.
+struct Entry {
+    url: String,
+    /// None while the transfer is running, then the error message if it failed
+    result: Option<Result<(), String>>,
+    http_code: Option<u16>,
+    bytes: u64,
+    seconds: f64,
+    /// Accept-Language or Accept-Charset headers the response did not honour
+    mismatches: Vec<Mismatch>,
+}
+
+thread_local! {
+    static ENTRIES: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Render the summary: every URL started so far, and the exit code once the run is over.
+fn to_json(exit_code: Option<i32>) -> Value {
+    let transfers = ENTRIES.with(|entries| {
+        entries
+            .borrow()
+            .iter()
+            .map(|entry| {
+                let (result, error) = match &entry.result {
+                    None => ("unfinished", Value::Null),
+                    Some(Ok(())) => ("ok", Value::Null),
+                    Some(Err(err)) => ("error", Value::String(err.clone())),
+                };
+                Value::Object(vec![
+                    ("url".to_string(), Value::String(entry.url.clone())),
+                    ("result".to_string(), Value::String(result.to_string())),
+                    (
+                        "http_code".to_string(),
+                        entry
+                            .http_code
+                            .map_or(Value::Null, |code| Value::Number(code as f64)),
+                    ),
+                    ("bytes".to_string(), Value::Number(entry.bytes as f64)),
+                    ("time_total".to_string(), Value::Number(entry.seconds)),
+                    ("error".to_string(), error),
+                    (
+                        "negotiation_mismatches".to_string(),
+                        Value::Array(entry.mismatches.iter().map(mismatch_to_json).collect()),
+                    ),
+                ])
+            })
+            .collect()
+    });
+    Value::Object(vec![
+        ("complete".to_string(), Value::Bool(exit_code.is_some())),
+        (
+            "exit_code".to_string(),
+            exit_code.map_or(Value::Null, |code| Value::Number(code as f64)),
+        ),
+        ("transfers".to_string(), Value::Array(transfers)),
+    ])
+}
+
+fn mismatch_to_json(mismatch: &Mismatch) -> Value {
+    Value::Object(vec![
+        (
+            "header".to_string(),
+            Value::String(mismatch.header.to_string()),
+        ),
+        (
+            "requested".to_string(),
+            Value::String(mismatch.requested.clone()),
+        ),
+        (
+            "received".to_string(),
+            mismatch.received.clone().map_or(Value::Null, Value::String),
+        ),
+    ])
+}
+
+/// Replace the summary at `path`, writing a temporary file first so a reader never
+/// sees half of it.
+fn write(path: &str, exit_code: Option<i32>) -> Result<(), String> {
+    let error = |err: std::io::Error| format!("Failed to write summary '{}': {}", path, err);
+    let partial = format!("{}.part", path);
+    fs::write(&partial, format!("{}\n", to_json(exit_code))).map_err(error)?;
+    fs::rename(&partial, path).map_err(error)
+}
+
+/// Record that the transfer of `url` is starting and write the summary.
+///
+/// The file always reflects the run so far, so if rurl is killed, the interrupted
+/// URL is left as `unfinished` and `complete` stays false.
+pub fn begin(path: &str, url: &str) -> Result<(), String> {
+    ENTRIES.with(|entries| {
+        entries.borrow_mut().push(Entry {
+            url: url.to_string(),
+            result: None,
+            http_code: None,
+            bytes: 0,
+            seconds: 0.0,
+            mismatches: Vec::new(),
+        })
+    });
+    write(path, None)
+}
+
+/// Record the outcome of the current transfer from its measurements and write the summary.
+pub fn finish(path: &str, result: &Result<(), String>) -> Result<(), String> {
+    let metrics = writeout::snapshot();
+    let mismatches = negotiate::take_mismatches();
+    ENTRIES.with(|entries| {
+        if let Some(entry) = entries.borrow_mut().last_mut() {
+            entry.result = Some(
+                result
+                    .clone()
+                    .map_err(|err| match err.strip_prefix("Error: ") {
+                        Some(message) => message.to_string(),
+                        None => err,
+                    }),
+            );
+            entry.http_code = metrics.http_code.map(|code| code.as_u16());
+            entry.bytes = metrics.size_download;
+            entry.seconds = metrics.time_total.unwrap_or_default().as_secs_f64();
+            entry.mismatches = mismatches;
+        }
+    });
+    write(path, None)
+}
+
+/// Write the final summary with the exit code rurl is about to return.
+pub fn end(path: &str, exit_code: i32) -> Result<(), String> {
+    write(path, Some(exit_code))
+}
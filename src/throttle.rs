@@ -0,0 +1,117 @@
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Share of a second of traffic that may pass at once after an idle period
+const BURST: f64 = 0.1;
+
+/// Token bucket pacing a transfer to `--limit-rate` bytes per second.
+///
+/// Tokens accrue at the rate up to a burst of a tenth of a second; bytes that pass
+/// without tokens put the bucket in debt, which is paid off by sleeping. Transfers
+/// therefore average the rate without stalling for longer than one read.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    /// A full bucket for `rate` bytes per second.
+    pub fn new(rate: u64) -> Self {
+        let rate = rate.max(1) as f64;
+        Bucket {
+            rate,
+            tokens: rate * BURST,
+            last: Instant::now(),
+        }
+    }
+
+    /// Largest piece a single read or write should move, so the pace stays even.
+    pub fn chunk(&self) -> usize {
+        ((self.rate * BURST) as usize).max(1)
+    }
+
+    /// Account for `n` bytes that passed at `now`.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - How long to wait before more bytes may pass.
+    pub fn take(&mut self, n: usize, now: Instant) -> Duration {
+        let refill = now.saturating_duration_since(self.last).as_secs_f64() * self.rate;
+        self.last = now;
+        self.tokens = (self.tokens + refill).min(self.rate * BURST) - n as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Account for `n` bytes and sleep until they are paid for.
+    fn pace(&mut self, n: usize) {
+        let wait = self.take(n, Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// A reader limited to `--limit-rate`; without a rate it passes reads through.
+pub struct Reader<R> {
+    inner: R,
+    bucket: Option<Bucket>,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R, rate: Option<u64>) -> Self {
+        Reader {
+            inner,
+            bucket: rate.map(Bucket::new),
+        }
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(bucket) = &mut self.bucket else {
+            return self.inner.read(buf);
+        };
+        let len = buf.len().min(bucket.chunk());
+        let n = self.inner.read(&mut buf[..len])?;
+        bucket.pace(n);
+        Ok(n)
+    }
+}
+
+/// A writer limited to `--limit-rate`; without a rate it passes writes through.
+pub struct Writer<W> {
+    inner: W,
+    bucket: Option<Bucket>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W, rate: Option<u64>) -> Self {
+        Writer {
+            inner,
+            bucket: rate.map(Bucket::new),
+        }
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(bucket) = &mut self.bucket else {
+            return self.inner.write(buf);
+        };
+        let len = buf.len().min(bucket.chunk());
+        let n = self.inner.write(&buf[..len])?;
+        bucket.pace(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
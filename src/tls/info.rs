@@ -0,0 +1,306 @@
+use std::fmt;
+
+use crate::output::log::{Level, Logger};
+
+/// DER tag of a SEQUENCE
+const SEQUENCE: u8 = 0x30;
+/// DER tag of an OBJECT IDENTIFIER
+const OID: u8 = 0x06;
+/// DER tag of the explicit `[0]` version field of a certificate
+const VERSION: u8 = 0xa0;
+/// DER tag of the explicit `[3]` extensions field of a certificate
+const EXTENSIONS: u8 = 0xa3;
+/// DER tag of UTCTime
+const UTC_TIME: u8 = 0x17;
+/// DER tag of GeneralizedTime
+const GENERALIZED_TIME: u8 = 0x18;
+
+/// OID 2.5.29.17, the subject alternative name extension
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Short names of the common name attributes, keyed by OID
+const ATTRIBUTES: [(&[u8], &str); 8] = [
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x06], "C"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (&[0x55, 0x04, 0x05], "serialNumber"),
+    (
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01],
+        "emailAddress",
+    ),
+];
+
+/// What `-v` shows about a server certificate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Certificate {
+    /// The subject name, e.g. `C=US, O=Example, CN=example.com`
+    pub subject: String,
+    /// The issuer name, in the same form
+    pub issuer: String,
+    /// Subject alternative names such as `DNS:example.com` and `IP:192.0.2.1`
+    pub alt_names: Vec<String>,
+    /// Start of the validity period, as `YYYY-MM-DD HH:MM:SS UTC`
+    pub not_before: String,
+    /// End of the validity period, in the same form
+    pub not_after: String,
+}
+
+impl Certificate {
+    /// Read the fields shown by `-v` from a DER-encoded X.509 certificate.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Certificate, &'static str>` - The certificate details if successful, or an error message if the encoding is not understood.
+    pub fn parse(der: &[u8]) -> Result<Certificate, &'static str> {
+        const INVALID: &str = "Invalid certificate encoding";
+
+        let (certificate, _) = expect(der, SEQUENCE).ok_or(INVALID)?;
+        let (tbs, _) = expect(certificate, SEQUENCE).ok_or(INVALID)?;
+
+        // version (optional), serialNumber, signature algorithm
+        let (tag, _, mut rest) = read(tbs).ok_or(INVALID)?;
+        if tag == VERSION {
+            rest = read(rest).ok_or(INVALID)?.2;
+        }
+        rest = read(rest).ok_or(INVALID)?.2;
+
+        let (issuer, rest) = expect(rest, SEQUENCE).ok_or(INVALID)?;
+        let (validity, rest) = expect(rest, SEQUENCE).ok_or(INVALID)?;
+        let (subject, mut rest) = expect(rest, SEQUENCE).ok_or(INVALID)?;
+        let (not_before, validity) = time(validity).ok_or(INVALID)?;
+        let (not_after, _) = time(validity).ok_or(INVALID)?;
+
+        // subjectPublicKeyInfo, then the optional unique IDs and extensions
+        let mut alt_names = Vec::new();
+        rest = read(rest).ok_or(INVALID)?.2;
+        while let Some((tag, content, next)) = read(rest) {
+            if tag == EXTENSIONS {
+                alt_names = subject_alt_names(content).ok_or(INVALID)?;
+            }
+            rest = next;
+        }
+
+        Ok(Certificate {
+            subject: name(subject).ok_or(INVALID)?,
+            issuer: name(issuer).ok_or(INVALID)?,
+            alt_names,
+            not_before,
+            not_after,
+        })
+    }
+}
+
+/// Print the verbose summary of a finished handshake, similar to `curl -v`.
+///
+/// # Arguments
+///
+/// * `logger` - Prints the summary at the verbose level.
+/// * `version` - The negotiated protocol version, e.g. `TLSv1.3`, if the backend reports it.
+/// * `cipher` - The negotiated cipher suite, if the backend reports it.
+/// * `certificate` - The DER-encoded certificate the server presented, if any.
+pub fn print(
+    logger: &Logger,
+    version: Option<&str>,
+    cipher: Option<&str>,
+    certificate: Option<&[u8]>,
+) {
+    let line = |message: fmt::Arguments| logger.log(Level::Verbose, message);
+    match (version, cipher) {
+        (Some(version), Some(cipher)) => {
+            line(format_args!("TLS connection: {} / {}", version, cipher))
+        }
+        (Some(version), None) => line(format_args!("TLS connection: {}", version)),
+        _ => line(format_args!(
+            "TLS connection: version and cipher suite not reported by the TLS backend"
+        )),
+    }
+
+    let Some(der) = certificate else {
+        line(format_args!("Server certificate: none presented"));
+        return;
+    };
+    match Certificate::parse(der) {
+        Ok(certificate) => {
+            line(format_args!("Server certificate:"));
+            line(format_args!("  subject: {}", certificate.subject));
+            line(format_args!("  issuer: {}", certificate.issuer));
+            if !certificate.alt_names.is_empty() {
+                line(format_args!(
+                    "  subject alt names: {}",
+                    certificate.alt_names.join(", ")
+                ));
+            }
+            line(format_args!("  start date: {}", certificate.not_before));
+            line(format_args!("  expire date: {}", certificate.not_after));
+        }
+        Err(err) => line(format_args!("Server certificate: {}", err)),
+    }
+}
+
+/// Read one DER element from the start of `input`.
+///
+/// # Returns
+///
+/// * `Option<(u8, &[u8], &[u8])>` - The tag, the content, and the bytes after the element, or None if the element is truncated.
+fn read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let length = if first < 0x80 {
+        first as usize
+    } else {
+        // Long form: the low bits count the length bytes that follow
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let (bytes, after) = rest.split_at(count);
+        rest = after;
+        bytes
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | byte as usize)
+    };
+    if rest.len() < length {
+        return None;
+    }
+    let (content, after) = rest.split_at(length);
+    Some((tag, content, after))
+}
+
+/// Read one DER element that must have tag `tag`, returning its content and what follows.
+fn expect(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    match read(input)? {
+        (found, content, rest) if found == tag => Some((content, rest)),
+        _ => None,
+    }
+}
+
+/// Render the RDN sequence of a Name as `TYPE=value` pairs joined by `, `.
+fn name(mut rdns: &[u8]) -> Option<String> {
+    let mut parts = Vec::new();
+    while !rdns.is_empty() {
+        let (_, set, rest) = read(rdns)?;
+        let mut attributes = set;
+        while !attributes.is_empty() {
+            let (attribute, after) = expect(attributes, SEQUENCE)?;
+            let (oid, value) = expect(attribute, OID)?;
+            let (_, value, _) = read(value)?;
+            let label = ATTRIBUTES
+                .iter()
+                .find(|(known, _)| *known == oid)
+                .map(|(_, label)| label.to_string())
+                .unwrap_or_else(|| dotted_oid(oid));
+            parts.push(format!("{}={}", label, String::from_utf8_lossy(value)));
+            attributes = after;
+        }
+        rdns = rest;
+    }
+    Some(parts.join(", "))
+}
+
+/// Render an OID in dotted form, e.g. `2.5.4.97`.
+fn dotted_oid(oid: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut value = 0u64;
+    for &byte in oid {
+        value = (value << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                // The first byte packs the first two arcs
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    arcs.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Read a UTCTime or GeneralizedTime and format it as `YYYY-MM-DD HH:MM:SS UTC`.
+fn time(input: &[u8]) -> Option<(String, &[u8])> {
+    let (tag, content, rest) = read(input)?;
+    let text = std::str::from_utf8(content).ok()?;
+    let digits = text.strip_suffix('Z')?;
+    let (year, digits) = match tag {
+        // Two-digit years 50-99 are 1950-1999 (RFC 5280, Section 4.1.2.5.1)
+        UTC_TIME => {
+            let year: u32 = digits.get(..2)?.parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &digits[2..],
+            )
+        }
+        GENERALIZED_TIME => (digits.get(..4)?.parse().ok()?, &digits[4..]),
+        _ => return None,
+    };
+    if digits.len() != 10 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let formatted = format!(
+        "{:04}-{}-{} {}:{}:{} UTC",
+        year,
+        &digits[0..2],
+        &digits[2..4],
+        &digits[4..6],
+        &digits[6..8],
+        &digits[8..10]
+    );
+    Some((formatted, rest))
+}
+
+/// Find the subject alternative names in the `[3]` extensions field.
+fn subject_alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let (mut extensions, _) = expect(extensions, SEQUENCE)?;
+    while !extensions.is_empty() {
+        let (extension, rest) = expect(extensions, SEQUENCE)?;
+        extensions = rest;
+        let (oid, mut fields) = expect(extension, OID)?;
+        if oid != OID_SUBJECT_ALT_NAME {
+            continue;
+        }
+
+        // Skip the critical flag; the value is the last field
+        let mut value = &[][..];
+        while let Some((_, content, rest)) = read(fields) {
+            value = content;
+            fields = rest;
+        }
+        let (mut names, _) = expect(value, SEQUENCE)?;
+        let mut rendered = Vec::new();
+        while !names.is_empty() {
+            let (tag, content, rest) = read(names)?;
+            // GeneralName choices are context-specific and implicitly tagged
+            match tag {
+                0x81 => rendered.push(format!("email:{}", String::from_utf8_lossy(content))),
+                0x82 => rendered.push(format!("DNS:{}", String::from_utf8_lossy(content))),
+                0x86 => rendered.push(format!("URI:{}", String::from_utf8_lossy(content))),
+                0x87 => rendered.push(format!("IP:{}", ip_address(content))),
+                _ => {}
+            }
+            names = rest;
+        }
+        return Some(rendered);
+    }
+    Some(Vec::new())
+}
+
+/// Render the octets of an iPAddress name.
+fn ip_address(octets: &[u8]) -> String {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    if let Ok(octets) = <[u8; 4]>::try_from(octets) {
+        Ipv4Addr::from(octets).to_string()
+    } else if let Ok(octets) = <[u8; 16]>::try_from(octets) {
+        Ipv6Addr::from(octets).to_string()
+    } else {
+        format!("{:02x?}", octets)
+    }
+}
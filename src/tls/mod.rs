@@ -0,0 +1,213 @@
+// The trust settings are only read by a TLS backend
+#![cfg_attr(
+    not(any(feature = "tls-native", feature = "tls-rustls")),
+    allow(dead_code)
+)]
+
+pub mod info;
+#[cfg(all(feature = "tls-native", not(feature = "tls-rustls")))]
+pub mod native_backend;
+#[cfg(feature = "tls-rustls")]
+pub mod rustls_backend;
+
+use std::fs;
+
+use crate::args::Args;
+use crate::http::proxy;
+use crate::output::log::{Level, Logger};
+
+// rustls wins when both backends are enabled
+#[cfg(all(feature = "tls-native", not(feature = "tls-rustls")))]
+pub use native_backend::{connect, transport, TlsStream};
+#[cfg(feature = "tls-rustls")]
+pub use rustls_backend::{connect, transport, TlsStream};
+
+#[cfg(not(any(feature = "tls-native", feature = "tls-rustls")))]
+pub use disabled::{connect, transport, TlsStream};
+
+/// Stand-ins for builds without a TLS backend: `https://` URLs fail with a clear error.
+#[cfg(not(any(feature = "tls-native", feature = "tls-rustls")))]
+mod disabled {
+    use crate::args::Args;
+    use crate::http::transport::Transport;
+    use std::io::{self, Read, Write};
+
+    /// No TLS stream can exist without a backend
+    pub enum TlsStream {}
+
+    impl Read for TlsStream {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            match *self {}
+        }
+    }
+
+    impl Write for TlsStream {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            match *self {}
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            match *self {}
+        }
+    }
+
+    /// Unreachable: no TLS stream can exist.
+    pub fn transport(stream: &TlsStream) -> &dyn Transport {
+        match *stream {}
+    }
+
+    /// Always fails: this build has no TLS backend.
+    pub fn connect(
+        _stream: Box<dyn Transport>,
+        _host: &str,
+        _args: &Args,
+    ) -> Result<(TlsStream, bool), String> {
+        Err("HTTPS is not available: rurl was built without TLS (enable the tls-native or tls-rustls feature)".to_string())
+    }
+}
+
+/// A client certificate for mutual TLS, from `--cert` and `--key`.
+#[derive(Debug)]
+pub enum ClientIdentity {
+    /// PEM certificate chain and PEM private key; both may come from the same file
+    Pem { certs: Vec<u8>, key: Vec<u8> },
+    /// A PKCS#12 archive holding both, and its password; rustls cannot read it
+    #[cfg_attr(feature = "tls-rustls", allow(dead_code))]
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+/// Extra trust and identity settings for a TLS handshake.
+#[derive(Debug, Default)]
+pub struct Trust {
+    /// PEM certificate blocks trusted in addition to the default roots
+    pub roots: Vec<String>,
+    /// The certificate presented when the server asks for one
+    pub identity: Option<ClientIdentity>,
+    /// Accept any certificate and host name
+    pub insecure: bool,
+    /// Verify the certificate chain but accept any host name
+    pub insecure_hostname: bool,
+}
+
+/// The trust settings for the handshake with the origin.
+///
+/// `--cacert`, `--cert`, `-k/--insecure`, and `--insecure-hostname` apply to every
+/// handshake. Through a proxy, `--proxy-cacert` and `--proxy-insecure` also apply: a
+/// TLS-inspecting proxy answers the handshake inside its CONNECT tunnel with
+/// certificates signed by its own CA.
+///
+/// # Returns
+///
+/// * `Result<Trust, String>` - The trust settings if successful, or an error message if a CA or certificate file cannot be read or holds no certificates.
+pub fn trust(args: &Args) -> Result<Trust, String> {
+    let logger = Logger::new(args);
+    if args.insecure {
+        logger.log_err(Level::Verbose, format_args!("WARNING: -k/--insecure is set: TLS certificates and host names are NOT verified, so anyone on the path can read and alter this connection"));
+    } else if args.insecure_hostname {
+        logger.log_err(
+            Level::Verbose,
+            format_args!(
+                "WARNING: --insecure-hostname is set: the certificate may belong to any host"
+            ),
+        );
+    }
+    let mut roots = Vec::new();
+    if let Some(path) = &args.cacert {
+        roots.extend(load_ca_file(path, "CA file")?);
+    }
+    let identity = match &args.cert {
+        Some(cert) => Some(client_identity(cert, args.key.as_deref())?),
+        None => None,
+    };
+    if proxy::for_url(args).is_none() {
+        return Ok(Trust {
+            roots,
+            identity,
+            insecure: args.insecure,
+            insecure_hostname: args.insecure_hostname,
+        });
+    }
+
+    if let Some(path) = &args.proxy_cacert {
+        roots.extend(load_ca_file(path, "proxy CA file")?);
+    }
+    if args.proxy_insecure {
+        logger.log(
+            Level::Verbose,
+            format_args!("Not verifying TLS certificates received through the proxy"),
+        );
+    }
+
+    Ok(Trust {
+        roots,
+        identity,
+        insecure: args.insecure || args.proxy_insecure,
+        insecure_hostname: args.insecure_hostname,
+    })
+}
+
+/// Read the PEM certificates of a CA file; `what` names the file in errors.
+fn load_ca_file(path: &str, what: &str) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {} '{}': {}", what, path, err))?;
+    let roots = pem_certificates(&text);
+    if roots.is_empty() {
+        return Err(format!("No certificates found in {} '{}'", what, path));
+    }
+    Ok(roots)
+}
+
+/// Load the client certificate given with `--cert`, and its key from `--key`.
+///
+/// Without `--key`, a PEM file must hold the key as well; any other file is read as
+/// PKCS#12. Like curl, `--cert FILE:PASSWORD` gives the PKCS#12 password.
+fn client_identity(cert: &str, key: Option<&str>) -> Result<ClientIdentity, String> {
+    let (path, password) = match cert.rsplit_once(':') {
+        Some((path, password)) if !fs::exists(cert).unwrap_or(false) => (path, password),
+        _ => (cert, ""),
+    };
+    let read = |path: &str, what: &str| {
+        fs::read(path).map_err(|err| format!("Failed to read {} '{}': {}", what, path, err))
+    };
+
+    let certs = read(path, "certificate file")?;
+    if let Some(key) = key {
+        return Ok(ClientIdentity::Pem {
+            certs,
+            key: read(key, "key file")?,
+        });
+    }
+
+    let contains = |needle: &[u8]| certs.windows(needle.len()).any(|window| window == needle);
+    if !contains(b"-----BEGIN ") {
+        return Ok(ClientIdentity::Pkcs12 {
+            der: certs,
+            password: password.to_string(),
+        });
+    }
+    if !contains(b"PRIVATE KEY-----") {
+        return Err(format!("No private key in '{}'; give it with --key", path));
+    }
+    Ok(ClientIdentity::Pem {
+        key: certs.clone(),
+        certs,
+    })
+}
+
+/// Split PEM text into its `CERTIFICATE` blocks, dropping anything between them.
+pub fn pem_certificates(text: &str) -> Vec<String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(BEGIN) {
+        let Some(length) = rest[start..].find(END) else {
+            break;
+        };
+        let end = start + length + END.len();
+        blocks.push(format!("{}\n", &rest[start..end]));
+        rest = &rest[end..];
+    }
+    blocks
+}
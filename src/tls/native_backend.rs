@@ -0,0 +1,165 @@
+use std::time::Instant;
+
+use native_tls::TlsConnector;
+
+use crate::args::Args;
+use crate::http::timeout::{self, Phase};
+use crate::http::transport::Transport;
+use crate::output::log::{Level, Logger};
+use crate::tls::{self, info, ClientIdentity};
+
+/// A TLS stream driven by the platform's TLS library
+pub type TlsStream = native_tls::TlsStream<Box<dyn Transport>>;
+
+/// The transport the TLS stream runs on, e.g. to change its timeouts.
+pub fn transport(stream: &TlsStream) -> &dyn Transport {
+    stream.get_ref().as_ref()
+}
+
+/// Perform a TLS handshake with native-tls.
+///
+/// native-tls offers no ALPN, so `--http2` is used with prior knowledge.
+///
+/// # Arguments
+///
+/// * `stream` - The connected transport.
+/// * `host` - The server name to verify the certificate against.
+/// * `args` - A reference to an `Args` struct containing the TLS options.
+///
+/// # Returns
+///
+/// * `Result<(TlsStream, bool), String>` - The TLS stream and whether HTTP/2 is spoken if successful, or an error message if unsuccessful.
+pub fn connect(
+    stream: Box<dyn Transport>,
+    host: &str,
+    args: &Args,
+) -> Result<(TlsStream, bool), String> {
+    // Determine which TLS version to use
+    let tls_version = args
+        .tls_version
+        .as_deref()
+        .and_then(get_tls_protocol_version)
+        .or_else(get_default_tls_protocol);
+
+    let mut builder = TlsConnector::builder();
+
+    // Set minimum protocol version if specified
+    if let Some(version) = tls_version {
+        builder.min_protocol_version(Some(version));
+    }
+
+    // Trust --cacert and, through a TLS-inspecting proxy, its CA
+    let trust = tls::trust(args)?;
+    for pem in &trust.roots {
+        let certificate = native_tls::Certificate::from_pem(pem.as_bytes())
+            .map_err(|err| format!("TLS error: invalid CA certificate: {}", err))?;
+        builder.add_root_certificate(certificate);
+    }
+
+    // Present a client certificate for mutual TLS
+    if let Some(identity) = &trust.identity {
+        let identity = match identity {
+            ClientIdentity::Pem { certs, key } => native_tls::Identity::from_pkcs8(certs, key),
+            ClientIdentity::Pkcs12 { der, password } => {
+                native_tls::Identity::from_pkcs12(der, password)
+            }
+        }
+        .map_err(|err| format!("TLS error: invalid client certificate: {}", err))?;
+        builder.identity(identity);
+    }
+
+    // Complete the connector configuration
+    let connector = match builder
+        .danger_accept_invalid_certs(trust.insecure)
+        .danger_accept_invalid_hostnames(trust.insecure || trust.insecure_hostname)
+        .build()
+    {
+        Ok(connector) => connector,
+        Err(err) => {
+            return Err(format!("TLS error: {}", err));
+        }
+    };
+
+    // Bound the handshake separately from regular reads and writes
+    if let Some(limit) = args.tls_timeout {
+        timeout::set_phase_timeout(&*stream, limit)?;
+    }
+    let started = Instant::now();
+
+    let tls_stream = match connector.connect(host, stream) {
+        Ok(stream) => stream,
+        Err(_)
+            if args
+                .tls_timeout
+                .is_some_and(|limit| started.elapsed() >= limit) =>
+        {
+            let limit = args.tls_timeout.unwrap_or_default();
+            return Err(timeout::error(Phase::Tls, limit, host));
+        }
+        Err(err) => {
+            return Err(format!("TLS handshake error: {}", err));
+        }
+    };
+
+    if args.tls_timeout.is_some() {
+        timeout::set_io_timeouts(&**tls_stream.get_ref())?;
+    }
+
+    let logger = Logger::new(args);
+    if logger.enabled(Level::Verbose) {
+        // native-tls does not expose the negotiated version or cipher suite
+        let certificate = tls_stream
+            .peer_certificate()
+            .ok()
+            .flatten()
+            .and_then(|certificate| certificate.to_der().ok());
+        info::print(&logger, None, None, certificate.as_deref());
+    }
+
+    if args.http2 && !args.silent {
+        eprintln!("Warning: native-tls cannot negotiate ALPN; sending HTTP/2 with prior knowledge");
+    }
+
+    Ok((tls_stream, args.http2))
+}
+
+/// Get the TLS protocol version from the specified string
+fn get_tls_protocol_version(version: &str) -> Option<native_tls::Protocol> {
+    match version.trim() {
+        "1.0" => Some(native_tls::Protocol::Tlsv10),
+        "1.1" => Some(native_tls::Protocol::Tlsv11),
+        "1.2" => Some(native_tls::Protocol::Tlsv12),
+        // TLS 1.3 is not explicitly supported in native-tls yet, but we can try to leave it to the system
+        "1.3" => None,
+        _ => None,
+    }
+}
+
+/// Get the default minimum TLS protocol version for the current OS
+fn get_default_tls_protocol() -> Option<native_tls::Protocol> {
+    // Different OS versions have different defaults/support for TLS versions
+    // Here we're making conservative choices
+    #[cfg(target_os = "macos")]
+    {
+        // macOS typically has good support for recent TLS versions
+        Some(native_tls::Protocol::Tlsv12)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Windows support depends a lot on the version, default to 1.2 for security
+        Some(native_tls::Protocol::Tlsv12)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Linux typically supports recent versions
+        Some(native_tls::Protocol::Tlsv12)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        // For other platforms, use TLS 1.2 as a safe default
+        Some(native_tls::Protocol::Tlsv12)
+    }
+}
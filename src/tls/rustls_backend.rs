@@ -0,0 +1,277 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::{self, PemObject};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore,
+    StreamOwned,
+};
+
+use crate::args::Args;
+use crate::http::timeout::{self, Phase};
+use crate::http::transport::Transport;
+use crate::output::log::{Level, Logger};
+use crate::tls::{self, info, ClientIdentity};
+
+/// A TLS stream driven by rustls
+pub type TlsStream = StreamOwned<ClientConnection, Box<dyn Transport>>;
+
+/// The transport the TLS stream runs on, e.g. to change its timeouts.
+pub fn transport(stream: &TlsStream) -> &dyn Transport {
+    stream.get_ref().as_ref()
+}
+
+/// ALPN protocol identifier for HTTP/2
+const ALPN_H2: &[u8] = b"h2";
+
+/// ALPN protocol identifier for HTTP/1.1
+const ALPN_HTTP11: &[u8] = b"http/1.1";
+
+/// Perform a TLS handshake with rustls, offering ALPN.
+///
+/// `h2` is offered alongside `http/1.1` when `--http2` is set; the server's choice decides
+/// which protocol is spoken afterwards.
+///
+/// # Arguments
+///
+/// * `stream` - The connected transport.
+/// * `host` - The server name to verify the certificate against.
+/// * `args` - A reference to an `Args` struct containing the TLS options.
+///
+/// # Returns
+///
+/// * `Result<(TlsStream, bool), String>` - The TLS stream and whether HTTP/2 was negotiated if successful, or an error message if unsuccessful.
+pub fn connect(
+    mut stream: Box<dyn Transport>,
+    host: &str,
+    args: &Args,
+) -> Result<(TlsStream, bool), String> {
+    let versions: &[&rustls::SupportedProtocolVersion] =
+        match args.tls_version.as_deref().map(str::trim) {
+            Some("1.3") => &[&rustls::version::TLS13],
+            Some("1.0") | Some("1.1") => {
+                return Err("TLS error: rustls supports TLS 1.2 and 1.3 only".to_string());
+            }
+            _ => &[&rustls::version::TLS13, &rustls::version::TLS12],
+        };
+
+    let trust = tls::trust(args)?;
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    for pem in &trust.roots {
+        let certificate = CertificateDer::from_pem_slice(pem.as_bytes())
+            .map_err(|err| format!("TLS error: invalid CA certificate: {}", err))?;
+        roots
+            .add(certificate)
+            .map_err(|err| format!("TLS error: invalid CA certificate: {}", err))?;
+    }
+
+    let roots = Arc::new(roots);
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(versions)
+        .map_err(|err| format!("TLS error: {}", err))?
+        .with_root_certificates(roots.clone());
+
+    // Present a client certificate for mutual TLS
+    let mut config = match &trust.identity {
+        None => builder.with_no_client_auth(),
+        Some(ClientIdentity::Pem { certs, key }) => {
+            let invalid =
+                |err: pem::Error| format!("TLS error: invalid client certificate: {}", err);
+            let chain = CertificateDer::pem_slice_iter(certs)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(invalid)?;
+            let key = PrivateKeyDer::from_pem_slice(key).map_err(invalid)?;
+            builder
+                .with_client_auth_cert(chain, key)
+                .map_err(|err| format!("TLS error: invalid client certificate: {}", err))?
+        }
+        Some(ClientIdentity::Pkcs12 { .. }) => {
+            return Err("TLS error: PKCS#12 client certificates need the tls-native backend; convert them to PEM for --cert and --key".to_string());
+        }
+    };
+    if trust.insecure {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)));
+    } else if trust.insecure_hostname {
+        let verifier = WebPkiServerVerifier::builder_with_provider(roots, provider)
+            .build()
+            .map_err(|err| format!("TLS error: {}", err))?;
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyHostname(verifier)));
+    }
+    config.alpn_protocols = if args.http2 {
+        vec![ALPN_H2.to_vec(), ALPN_HTTP11.to_vec()]
+    } else {
+        vec![ALPN_HTTP11.to_vec()]
+    };
+
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|err| format!("TLS error: invalid server name: {}", err))?;
+    let mut conn = ClientConnection::new(Arc::new(config), server_name)
+        .map_err(|err| format!("TLS error: {}", err))?;
+
+    // Bound the handshake as a whole, shrinking the socket timeout as the deadline nears
+    let deadline = args
+        .tls_timeout
+        .map(|limit| (limit, Instant::now() + limit));
+    while conn.is_handshaking() {
+        if let Some((limit, deadline)) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(timeout::error(Phase::Tls, limit, host));
+            }
+            timeout::set_phase_timeout(&*stream, remaining)?;
+        }
+        match conn.complete_io(&mut stream) {
+            Ok(_) => {}
+            Err(err) if timeout::is_timeout(&err) && deadline.is_some() => {
+                let limit = args.tls_timeout.unwrap_or_default();
+                return Err(timeout::error(Phase::Tls, limit, host));
+            }
+            Err(err) => return Err(format!("TLS handshake error: {}", err)),
+        }
+    }
+    if deadline.is_some() {
+        timeout::set_io_timeouts(&*stream)?;
+    }
+
+    let negotiated_h2 = conn.alpn_protocol() == Some(ALPN_H2);
+    let logger = Logger::new(args);
+    if logger.enabled(Level::Verbose) {
+        let version = conn
+            .protocol_version()
+            .and_then(|version| version.as_str())
+            .map(|version| version.replace('_', "."));
+        let cipher = conn
+            .negotiated_cipher_suite()
+            .and_then(|suite| suite.suite().as_str());
+        let certificate = conn
+            .peer_certificates()
+            .and_then(|chain| chain.first())
+            .map(|certificate| certificate.as_ref());
+        info::print(&logger, version.as_deref(), cipher, certificate);
+        match conn.alpn_protocol() {
+            Some(protocol) => logger.log(
+                Level::Verbose,
+                format_args!(
+                    "ALPN: server accepted {}",
+                    String::from_utf8_lossy(protocol)
+                ),
+            ),
+            None => logger.log(
+                Level::Verbose,
+                format_args!("ALPN: server did not select a protocol"),
+            ),
+        }
+    }
+
+    Ok((StreamOwned::new(conn, stream), negotiated_h2))
+}
+
+/// Certificate verifier for `--insecure-hostname`: verifies the chain as usual but
+/// accepts a certificate issued for another name.
+#[derive(Debug)]
+struct AcceptAnyHostname(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for AcceptAnyHostname {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // The name is checked after the chain, so a name mismatch means the chain is valid
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// Certificate verifier for `-k/--insecure` and `--proxy-insecure`: accepts any certificate, but still
+/// checks the handshake signatures so the session keys match the certificate.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,456 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::json::{self, Value};
+
+/// Upper bound for a response body loaded into the viewer
+pub const MAX_BODY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The pane receiving scroll and search keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Headers,
+    Body,
+}
+
+/// A key press decoded from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Tab,
+    Enter,
+    Backspace,
+    Escape,
+}
+
+/// Render a JSON value as indented lines, collapsing containers nested deeper than `fold_depth`.
+///
+/// # Arguments
+///
+/// * `value` - The parsed JSON document.
+/// * `fold_depth` - Depth from which objects and arrays are shown as a one-line summary, or None to expand everything.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The rendered lines.
+pub fn render_json(value: &Value, fold_depth: Option<usize>) -> Vec<String> {
+    let mut lines = Vec::new();
+    render_value(value, 0, fold_depth, String::new(), "", &mut lines);
+    lines
+}
+
+/// Render `value` at `depth`, starting its first line with `prefix` and ending its last with `suffix`.
+fn render_value(
+    value: &Value,
+    depth: usize,
+    fold_depth: Option<usize>,
+    prefix: String,
+    suffix: &str,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(depth);
+    let folded = fold_depth.is_some_and(|limit| depth >= limit);
+
+    match value {
+        Value::Object(fields) if !fields.is_empty() => {
+            if folded {
+                lines.push(format!(
+                    "{}{}{{…}} ({} keys){}",
+                    indent,
+                    prefix,
+                    fields.len(),
+                    suffix
+                ));
+                return;
+            }
+            lines.push(format!("{}{}{{", indent, prefix));
+            for (i, (key, field)) in fields.iter().enumerate() {
+                let comma = if i + 1 < fields.len() { "," } else { "" };
+                let key = format!("{}: ", json::quote(key));
+                render_value(field, depth + 1, fold_depth, key, comma, lines);
+            }
+            lines.push(format!("{}}}{}", indent, suffix));
+        }
+        Value::Array(items) if !items.is_empty() => {
+            if folded {
+                lines.push(format!(
+                    "{}{}[…] ({} items){}",
+                    indent,
+                    prefix,
+                    items.len(),
+                    suffix
+                ));
+                return;
+            }
+            lines.push(format!("{}{}[", indent, prefix));
+            for (i, item) in items.iter().enumerate() {
+                let comma = if i + 1 < items.len() { "," } else { "" };
+                render_value(item, depth + 1, fold_depth, String::new(), comma, lines);
+            }
+            lines.push(format!("{}]{}", indent, suffix));
+        }
+        scalar => lines.push(format!("{}{}{}{}", indent, prefix, scalar, suffix)),
+    }
+}
+
+/// Find the next line at or after `from` containing `query`, ignoring case and wrapping around.
+pub fn find_next(lines: &[String], query: &str, from: usize) -> Option<usize> {
+    if query.is_empty() || lines.is_empty() {
+        return None;
+    }
+    let query = query.to_lowercase();
+    (0..lines.len())
+        .map(|offset| (from + offset) % lines.len())
+        .find(|&i| lines[i].to_lowercase().contains(&query))
+}
+
+/// Full-screen viewer state.
+struct Viewer {
+    status: String,
+    headers: Vec<String>,
+    raw_body: Vec<String>,
+    json: Option<Value>,
+    fold_depth: Option<usize>,
+    body: Vec<String>,
+    focus: Pane,
+    header_scroll: usize,
+    body_scroll: usize,
+    search: Option<String>,
+    message: String,
+}
+
+impl Viewer {
+    fn new(head: &[u8], body: &[u8]) -> Self {
+        let head = String::from_utf8_lossy(head);
+        let mut lines = head.lines().filter(|line| !line.is_empty());
+        let status = lines.next().unwrap_or("").to_string();
+        let headers = lines.map(str::to_string).collect();
+
+        let text = String::from_utf8_lossy(body);
+        let raw_body: Vec<String> = text
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+        let json = json::parse(&text).ok();
+
+        let mut viewer = Viewer {
+            status,
+            headers,
+            raw_body,
+            json,
+            fold_depth: None,
+            body: Vec::new(),
+            focus: Pane::Body,
+            header_scroll: 0,
+            body_scroll: 0,
+            search: None,
+            message: String::new(),
+        };
+        viewer.refresh_body();
+        viewer
+    }
+
+    /// Re-render the body pane after the folding level changed.
+    fn refresh_body(&mut self) {
+        self.body = match &self.json {
+            Some(value) => render_json(value, self.fold_depth),
+            None => self.raw_body.clone(),
+        };
+        self.body_scroll = self.body_scroll.min(self.body.len().saturating_sub(1));
+    }
+
+    fn focused(&self) -> (&[String], usize) {
+        match self.focus {
+            Pane::Headers => (&self.headers, self.header_scroll),
+            Pane::Body => (&self.body, self.body_scroll),
+        }
+    }
+
+    fn set_scroll(&mut self, line: usize) {
+        let (lines, _) = self.focused();
+        let line = line.min(lines.len().saturating_sub(1));
+        match self.focus {
+            Pane::Headers => self.header_scroll = line,
+            Pane::Body => self.body_scroll = line,
+        }
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let (_, current) = self.focused();
+        self.set_scroll(current.saturating_add_signed(delta));
+    }
+
+    fn search_from(&mut self, from: usize) {
+        let Some(query) = self.search.clone() else {
+            return;
+        };
+        let (lines, _) = self.focused();
+        match find_next(lines, &query, from) {
+            Some(line) => {
+                self.set_scroll(line);
+                self.message.clear();
+            }
+            None => self.message = format!("Pattern not found: {}", query),
+        }
+    }
+
+    /// Height of the header pane for a terminal of `rows` rows.
+    fn header_rows(&self, rows: usize) -> usize {
+        self.headers.len().min(rows.saturating_sub(4) / 3).max(1)
+    }
+
+    /// Lay out the screen as `rows` lines of at most `cols` characters.
+    fn frame(&self, rows: usize, cols: usize) -> Vec<String> {
+        let header_rows = self.header_rows(rows);
+        let body_rows = rows.saturating_sub(header_rows + 4);
+        let clip = |line: &str| line.chars().take(cols).collect::<String>();
+        let rule = |title: &str, focused: bool| {
+            let marker = if focused { "▶" } else { "─" };
+            let title = format!("{}─ {} ", marker, title);
+            let fill = cols.saturating_sub(title.chars().count());
+            clip(&format!("{}{}", title, "─".repeat(fill)))
+        };
+
+        let mut frame = vec![format!("\x1b[1m{}\x1b[0m", clip(&self.status))];
+        frame.push(rule(
+            &format!("Headers ({})", self.headers.len()),
+            self.focus == Pane::Headers,
+        ));
+        for i in 0..header_rows {
+            let line = self.headers.get(self.header_scroll + i).map_or("", |l| l);
+            frame.push(self.highlight(&clip(line)));
+        }
+
+        let fold = match (&self.json, self.fold_depth) {
+            (None, _) => String::new(),
+            (Some(_), None) => ", JSON".to_string(),
+            (Some(_), Some(depth)) => format!(", JSON folded at depth {}", depth),
+        };
+        frame.push(rule(
+            &format!(
+                "Body ({} lines{}) {}/{}",
+                self.body.len(),
+                fold,
+                (self.body_scroll + 1).min(self.body.len()),
+                self.body.len()
+            ),
+            self.focus == Pane::Body,
+        ));
+        for i in 0..body_rows {
+            let line = self.body.get(self.body_scroll + i).map_or("", |l| l);
+            frame.push(self.highlight(&clip(line)));
+        }
+
+        let help = if self.message.is_empty() {
+            "q quit  Tab pane  j/k ↑/↓ scroll  PgUp/PgDn  g/G  / search  n next  f fold  +/- depth"
+                .to_string()
+        } else {
+            self.message.clone()
+        };
+        frame.push(format!("\x1b[7m{}\x1b[0m", clip(&help)));
+        frame
+    }
+
+    /// Show occurrences of the search query in reverse video.
+    fn highlight(&self, line: &str) -> String {
+        let Some(query) = self.search.as_deref().filter(|q| !q.is_empty()) else {
+            return line.to_string();
+        };
+        let lower = line.to_lowercase();
+        let query = query.to_lowercase();
+        // Only highlight when lowercasing kept byte offsets aligned
+        if lower.len() != line.len() {
+            return line.to_string();
+        }
+        let mut out = String::new();
+        let mut last = 0;
+        for (start, matched) in lower.match_indices(&query) {
+            out.push_str(&line[last..start]);
+            out.push_str("\x1b[7m");
+            out.push_str(&line[start..start + matched.len()]);
+            out.push_str("\x1b[0m");
+            last = start + matched.len();
+        }
+        out.push_str(&line[last..]);
+        out
+    }
+}
+
+/// Run a command against the controlling terminal, returning its trimmed output.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The terminal size as (rows, columns), defaulting to 24x80.
+fn terminal_size() -> (usize, usize) {
+    stty(&["size"])
+        .and_then(|size| {
+            let (rows, cols) = size.split_once(' ')?;
+            Some((rows.parse().ok()?, cols.parse().ok()?))
+        })
+        .filter(|&(rows, cols)| rows > 0 && cols > 0)
+        .unwrap_or((24, 80))
+}
+
+/// Read one key press from stdin in raw mode.
+fn read_key(stdin: &mut impl Read) -> io::Result<Key> {
+    let mut byte = [0u8; 1];
+    stdin.read_exact(&mut byte)?;
+    Ok(match byte[0] {
+        b'\t' => Key::Tab,
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => {
+            let mut seq = [0u8; 2];
+            if stdin.read_exact(&mut seq).is_err() || seq[0] != b'[' {
+                return Ok(Key::Escape);
+            }
+            match seq[1] {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'H' => Key::Home,
+                b'F' => Key::End,
+                b'5' | b'6' => {
+                    let mut tilde = [0u8; 1];
+                    stdin.read_exact(&mut tilde)?;
+                    if seq[1] == b'5' {
+                        Key::PageUp
+                    } else {
+                        Key::PageDown
+                    }
+                }
+                _ => Key::Escape,
+            }
+        }
+        b => Key::Char(b as char),
+    })
+}
+
+/// Check that stdin and stdout are a terminal the viewer can take over.
+pub fn ensure_terminal() -> Result<(), String> {
+    if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        Ok(())
+    } else {
+        Err("--tui requires an interactive terminal".to_string())
+    }
+}
+
+/// Show the response in a full-screen viewer until the user quits.
+///
+/// The terminal is switched to raw mode and the alternate screen, and restored on exit.
+///
+/// # Arguments
+///
+/// * `head` - The response header block, including the status line.
+/// * `body` - The complete response body.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` when the user quits, or an error message if the terminal cannot be used.
+pub fn run(head: &[u8], body: &[u8]) -> Result<(), String> {
+    ensure_terminal()?;
+    let saved = stty(&["-g"]).ok_or("Failed to read terminal settings")?;
+    stty(&["raw", "-echo"]).ok_or("Failed to switch the terminal to raw mode")?;
+
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "\x1b[?1049h\x1b[?25l");
+    let result = event_loop(&mut Viewer::new(head, body), &mut stdout);
+    let _ = write!(stdout, "\x1b[?25h\x1b[?1049l");
+    let _ = stdout.flush();
+    stty(&[&saved]);
+
+    result.map_err(|err| format!("Terminal error: {}", err))
+}
+
+fn event_loop(viewer: &mut Viewer, stdout: &mut impl Write) -> io::Result<()> {
+    let mut stdin = io::stdin().lock();
+    let mut prompt: Option<String> = None;
+
+    loop {
+        let (rows, cols) = terminal_size();
+        let mut frame = viewer.frame(rows, cols);
+        if let Some(input) = &prompt {
+            if let Some(last) = frame.last_mut() {
+                *last = format!("/{}", input);
+            }
+        }
+        write!(stdout, "\x1b[H\x1b[2J{}", frame.join("\r\n"))?;
+        stdout.flush()?;
+
+        let key = read_key(&mut stdin)?;
+        let page = rows.saturating_sub(viewer.header_rows(rows) + 4).max(1) as isize;
+
+        // Typing a search query
+        if let Some(input) = prompt.as_mut() {
+            match key {
+                Key::Enter => {
+                    viewer.search = Some(input.clone());
+                    prompt = None;
+                    let (_, current) = viewer.focused();
+                    viewer.search_from(current);
+                }
+                Key::Escape => prompt = None,
+                Key::Backspace => {
+                    input.pop();
+                }
+                Key::Char(c) if !c.is_control() => input.push(c),
+                _ => {}
+            }
+            continue;
+        }
+
+        viewer.message.clear();
+        match key {
+            Key::Char('q') | Key::Escape => return Ok(()),
+            Key::Tab => {
+                viewer.focus = match viewer.focus {
+                    Pane::Headers => Pane::Body,
+                    Pane::Body => Pane::Headers,
+                }
+            }
+            Key::Char('j') | Key::Down => viewer.scroll_by(1),
+            Key::Char('k') | Key::Up => viewer.scroll_by(-1),
+            Key::Char(' ') | Key::PageDown => viewer.scroll_by(page),
+            Key::Char('b') | Key::PageUp => viewer.scroll_by(-page),
+            Key::Char('g') | Key::Home => viewer.set_scroll(0),
+            Key::Char('G') | Key::End => viewer.set_scroll(usize::MAX),
+            Key::Char('/') => prompt = Some(String::new()),
+            Key::Char('n') => {
+                let (_, current) = viewer.focused();
+                viewer.search_from(current + 1);
+            }
+            Key::Char('f') if viewer.json.is_some() => {
+                viewer.fold_depth = match viewer.fold_depth {
+                    Some(_) => None,
+                    None => Some(1),
+                };
+                viewer.refresh_body();
+            }
+            Key::Char('+') if viewer.json.is_some() => {
+                viewer.fold_depth = viewer.fold_depth.map(|depth| depth + 1);
+                viewer.refresh_body();
+            }
+            Key::Char('-') if viewer.json.is_some() => {
+                viewer.fold_depth =
+                    Some(viewer.fold_depth.map_or(1, |d| d.saturating_sub(1).max(1)));
+                viewer.refresh_body();
+            }
+            _ => {}
+        }
+    }
+}
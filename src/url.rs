@@ -0,0 +1,462 @@
+//! Typed HTTP(S) URLs.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+/// The host part of a URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Host {
+    /// A registered name, lowercased
+    Domain(String),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+}
+
+impl fmt::Display for Host {
+    /// Format the host as it appears in a URL, with brackets around IPv6 literals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Domain(name) => write!(f, "{}", name),
+            Host::Ipv4(addr) => write!(f, "{}", addr),
+            Host::Ipv6(addr) => write!(f, "[{}]", addr),
+        }
+    }
+}
+
+/// A parsed `http://` or `https://` URL.
+///
+/// The scheme and host are normalized to lowercase and a missing port is filled
+/// in from the scheme, so `Display` reserializes a URL canonically. Characters
+/// that may not appear in a path, query, or fragment, such as spaces and non-ASCII
+/// text, are percent-encoded; existing escapes are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    scheme: String,
+    /// `user[:password]`, still percent-encoded
+    userinfo: Option<String>,
+    host: Host,
+    port: u16,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Url {
+    /// Parse an absolute URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - A string slice representing the URL to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Url, &'static str>` - The parsed URL if successful, or an error message if unsuccessful.
+    pub fn parse(input: &str) -> Result<Url, &'static str> {
+        let input = input.trim();
+        let (scheme, rest) = input
+            .split_once("://")
+            .ok_or("URL must start with http:// or https://")?;
+        let scheme = scheme.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return Err("URL must start with http:// or https://");
+        }
+
+        // Split off the fragment, then the query, then the path
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query.to_string())),
+            None => (rest, None),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        let (userinfo, authority) = match authority.rsplit_once('@') {
+            Some((userinfo, authority)) => (Some(userinfo), authority),
+            None => (None, authority),
+        };
+
+        let (host, port) = parse_authority(authority)?;
+        let port = match port {
+            Some(port) => port,
+            None => default_port(&scheme),
+        };
+
+        Ok(Url {
+            scheme,
+            userinfo: userinfo
+                .filter(|userinfo| !userinfo.is_empty())
+                .map(encode_invalid),
+            host,
+            port,
+            path: encode_invalid(path),
+            query: query.as_deref().map(encode_invalid),
+            fragment: fragment.as_deref().map(encode_invalid),
+        })
+    }
+
+    /// The scheme, `http` or `https`.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Whether the scheme is `https`.
+    pub fn is_https(&self) -> bool {
+        self.scheme == "https"
+    }
+
+    /// The user name given before `@`, percent-decoded.
+    pub fn username(&self) -> Option<String> {
+        let userinfo = self.userinfo.as_deref()?;
+        let user = userinfo.split_once(':').map_or(userinfo, |(user, _)| user);
+        Some(percent_decode(user))
+    }
+
+    /// The password given after the user name, percent-decoded.
+    pub fn password(&self) -> Option<String> {
+        let (_, password) = self.userinfo.as_deref()?.split_once(':')?;
+        Some(percent_decode(password))
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    /// The host as used for name resolution and TLS, without IPv6 brackets.
+    pub fn host_str(&self) -> String {
+        match &self.host {
+            Host::Domain(name) => name.clone(),
+            Host::Ipv4(addr) => addr.to_string(),
+            Host::Ipv6(addr) => addr.to_string(),
+        }
+    }
+
+    /// The port, explicit or the scheme default.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The host, followed by the port when it is not the scheme default.
+    ///
+    /// This is what `Host` and `:authority` carry, so the userinfo is left out.
+    pub fn authority(&self) -> String {
+        if self.port == default_port(&self.scheme) {
+            self.host.to_string()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// The path, always starting with `/`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The path split at `/`, without the leading slash and still percent-encoded.
+    ///
+    /// `/a/b/` yields `a`, `b`, and an empty last segment; `/` yields one empty segment.
+    pub fn path_segments(&self) -> impl Iterator<Item = &str> {
+        self.path[1..].split('/')
+    }
+
+    /// The raw query string, without the `?`.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// The query split into decoded name/value pairs.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query
+            .as_deref()
+            .unwrap_or("")
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode_query_component(name), decode_query_component(value))
+            })
+            .collect()
+    }
+
+    /// Set the query parameter `name` to `value`.
+    ///
+    /// The first parameter of that name is replaced and any later ones are dropped;
+    /// without one, the parameter is appended. Other parameters keep their encoding.
+    pub fn set_query_pair(&mut self, name: &str, value: &str) {
+        let pair = format!(
+            "{}={}",
+            encode_query_component(name),
+            encode_query_component(value)
+        );
+        let mut pairs = Vec::new();
+        let mut replaced = false;
+        for existing in self.query.as_deref().unwrap_or("").split('&') {
+            let existing_name = existing.split_once('=').map_or(existing, |(name, _)| name);
+            if existing.is_empty() {
+                continue;
+            }
+            if decode_query_component(existing_name) != name {
+                pairs.push(existing.to_string());
+            } else if !replaced {
+                pairs.push(pair.clone());
+                replaced = true;
+            }
+        }
+        if !replaced {
+            pairs.push(pair);
+        }
+        self.query = Some(pairs.join("&"));
+    }
+
+    /// Append `pair`, which must already be percent-encoded, to the query.
+    ///
+    /// Existing parameters are kept as they are, even ones of the same name.
+    pub fn append_query(&mut self, pair: &str) {
+        match &mut self.query {
+            Some(query) if !query.is_empty() => {
+                query.push('&');
+                query.push_str(pair);
+            }
+            _ => self.query = Some(pair.to_string()),
+        }
+    }
+
+    /// The fragment, without the `#`; it is never sent to the server.
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment.as_deref()
+    }
+
+    /// The origin-form request target: the path plus the query.
+    pub fn request_target(&self) -> String {
+        match &self.query {
+            Some(query) => format!("{}?{}", self.path, query),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Resolve a reference, such as a redirect `Location`, against this URL (RFC 3986, Section 5.2).
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - An absolute URL, or a scheme-relative, absolute-path, relative-path, query, or fragment reference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Url, &'static str>` - The resolved URL if successful, or an error message if unsuccessful.
+    pub fn join(&self, reference: &str) -> Result<Url, &'static str> {
+        let reference = reference.trim();
+
+        if has_scheme(reference) {
+            return Url::parse(reference);
+        }
+        if reference.starts_with("//") {
+            return Url::parse(&format!("{}:{}", self.scheme, reference));
+        }
+
+        let (reference, fragment) = match reference.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(encode_invalid(fragment))),
+            None => (reference, None),
+        };
+        let (path, query) = match reference.split_once('?') {
+            Some((path, query)) => (path, Some(encode_invalid(query))),
+            None => (reference, None),
+        };
+        let path = &encode_invalid(path);
+
+        let mut joined = self.clone();
+        joined.fragment = fragment;
+        if path.is_empty() {
+            // Same document: keep the path, and the query unless a new one is given
+            if query.is_some() {
+                joined.query = query;
+            }
+            return Ok(joined);
+        }
+
+        joined.query = query;
+        joined.path = if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            // Merge with everything up to the last '/' of the base path
+            let base = &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)];
+            remove_dot_segments(&format!("{}{}", base, path))
+        };
+        Ok(joined)
+    }
+}
+
+impl fmt::Display for Url {
+    /// Serialize canonically: lowercase scheme and host, default port omitted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://", self.scheme)?;
+        if let Some(userinfo) = &self.userinfo {
+            write!(f, "{}@", userinfo)?;
+        }
+        write!(f, "{}{}", self.authority(), self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+/// The port implied by a scheme.
+fn default_port(scheme: &str) -> u16 {
+    if scheme == "https" {
+        443
+    } else {
+        80
+    }
+}
+
+/// Check whether a reference starts with a scheme (`ALPHA *( ALPHA / DIGIT / "+" / "-" / "." ) ":"`).
+fn has_scheme(reference: &str) -> bool {
+    match reference.find(':') {
+        Some(colon) => {
+            let scheme = &reference[..colon];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Parse `host[:port]`, where the host may be a bracketed IPv6 literal.
+fn parse_authority(authority: &str) -> Result<(Host, Option<u16>), &'static str> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (literal, after) = rest.split_once(']').ok_or("Invalid IPv6 host")?;
+        let addr = literal
+            .parse::<Ipv6Addr>()
+            .map_err(|_| "Invalid IPv6 host")?;
+        let port = match after {
+            "" => None,
+            _ => Some(after.strip_prefix(':').ok_or("Invalid port")?),
+        };
+        (Host::Ipv6(addr), port)
+    } else {
+        let (host, port) = match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        };
+        if host.is_empty() {
+            return Err("Invalid host");
+        }
+        let host = match host.parse::<Ipv4Addr>() {
+            Ok(addr) => Host::Ipv4(addr),
+            Err(_) => Host::Domain(host.to_ascii_lowercase()),
+        };
+        (host, port)
+    };
+
+    let port = match port {
+        Some(port) => Some(port.parse::<u16>().map_err(|_| "Invalid port")?),
+        None => None,
+    };
+    Ok((host, port))
+}
+
+/// Remove `.` and `..` segments from a path (RFC 3986, Section 5.2.4).
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i == segments.len() - 1;
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    output.pop();
+                }
+                // A trailing dot segment still denotes a directory
+                if last {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+
+    format!("/{}", output.join("/"))
+}
+
+/// Decode `+` and percent escapes in a query name or value.
+fn decode_query_component(component: &str) -> String {
+    percent_decode(&component.replace('+', " "))
+}
+
+/// Decode percent escapes; malformed ones are kept as they are.
+fn percent_decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                decoded.push(hex_value(bytes[i + 1]) << 4 | hex_value(bytes[i + 2]));
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Percent-encode the bytes that are never valid in a URL component as is: controls,
+/// space, non-ASCII, and `"`, `<`, `>`, `^`, `` ` ``, `{`, `|`, `}`. Everything else,
+/// `%` included, is kept.
+fn encode_invalid(component: &str) -> String {
+    let mut encoded = String::with_capacity(component.len());
+    for byte in component.bytes() {
+        if byte <= b' ' || byte >= 0x7f || b"\"<>^`{|}".contains(&byte) {
+            encoded.push_str(&format!("%{:02X}", byte));
+        } else {
+            encoded.push(byte as char);
+        }
+    }
+    encoded
+}
+
+/// Percent-encode a query component, keeping only unreserved characters as is.
+fn encode_query_component(component: &str) -> String {
+    percent_encode(component.as_bytes())
+}
+
+/// Percent-encode bytes for a query or an `application/x-www-form-urlencoded` body.
+///
+/// Only unreserved characters (`A-Z a-z 0-9 - . _ ~`) are kept as is; everything
+/// else, spaces included, becomes `%XX`.
+pub fn percent_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// The value of an ASCII hex digit.
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
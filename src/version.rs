@@ -0,0 +1,47 @@
+//! Protocol versions.
+
+use core::fmt;
+
+/// An HTTP protocol version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    Http10,
+    Http11,
+    Http2,
+}
+
+impl Version {
+    /// Parse the version of a request or status line, such as `HTTP/1.1`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Version, &'static str>` - The version if supported, or an error message if not.
+    pub fn parse(version: &str) -> Result<Version, &'static str> {
+        match version {
+            "HTTP/1.0" => Ok(Version::Http10),
+            "HTTP/1.1" => Ok(Version::Http11),
+            "HTTP/2" | "HTTP/2.0" => Ok(Version::Http2),
+            _ => Err("Unsupported HTTP version"),
+        }
+    }
+
+    /// The version as written in a request or status line.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Version::Http10 => "HTTP/1.0",
+            Version::Http11 => "HTTP/1.1",
+            Version::Http2 => "HTTP/2",
+        }
+    }
+
+    /// The version number alone, as `--write-out` reports it: `1.0`, `1.1`, or `2`.
+    pub fn number(self) -> &'static str {
+        &self.as_str()[5..]
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
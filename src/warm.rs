@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::http::client;
+use crate::tls;
+use crate::writeout;
+use rurl::url::Url;
+
+/// Connection setup times of one host, each phase measured on its own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timings {
+    pub dns: Duration,
+    pub tcp: Duration,
+    /// None for plain HTTP
+    pub tls: Option<Duration>,
+    pub total: Duration,
+}
+
+/// Run `--warm`: resolve and connect to the host of every URL, with the TLS handshake
+/// for `https`, but send no request.
+///
+/// A line per host reports how long each phase took, so the cost of connection setup
+/// can be measured apart from the server's response time, and resolver caches along
+/// the way are filled before a batch of requests.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - `Ok(())` if every host was reached, or how many were not.
+pub fn run(args: &Args) -> Result<(), String> {
+    let mut failed = 0;
+    for index in 0..args.urls.len() {
+        let args = args.for_url(index);
+        let target = match Url::parse(&args.url) {
+            Ok(url) => format!("{}:{}", url.host_str(), url.port()),
+            Err(_) => args.url.clone(),
+        };
+        match warm(&args) {
+            Ok((timings, negotiated)) => println!("{}", report(&target, &timings, negotiated)),
+            Err(err) => {
+                failed += 1;
+                println!("{:<28} failed: {}", target, err);
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!(
+            "{} of {} host(s) could not be reached",
+            failed,
+            args.urls.len()
+        )),
+    }
+}
+
+/// Open a connection for `args.url` and measure it, then close it.
+///
+/// # Returns
+///
+/// * `Result<(Timings, Option<&'static str>), String>` - The timings and, for HTTPS, the protocol ALPN selected, or why the host could not be reached.
+fn warm(args: &Args) -> Result<(Timings, Option<&'static str>), String> {
+    let url = Url::parse(&args.url)?;
+    let (host, port, is_https) = (url.host_str(), url.port(), url.is_https());
+    writeout::start(&args.url);
+    let stream = client::open_connection(&host, port, is_https, args)?;
+    let negotiated = if is_https {
+        let (_stream, use_http2) = tls::connect(stream, &host, args)?;
+        writeout::mark(writeout::Event::AppConnect);
+        Some(if use_http2 { "h2" } else { "http/1.1" })
+    } else {
+        None
+    };
+    let metrics = writeout::snapshot();
+    let dns = metrics.time_namelookup.unwrap_or_default();
+    let connect = metrics.time_connect.unwrap_or(dns);
+    let timings = Timings {
+        dns,
+        tcp: connect.saturating_sub(dns),
+        tls: metrics
+            .time_appconnect
+            .filter(|_| is_https)
+            .map(|appconnect| appconnect.saturating_sub(connect)),
+        total: metrics.time_total.unwrap_or_default(),
+    };
+    Ok((timings, negotiated))
+}
+
+/// The report line for `target`, such as
+/// `example.com:443   dns 12.1 ms  tcp 20.4 ms  tls 41.0 ms  total 73.5 ms  h2`.
+pub fn report(target: &str, timings: &Timings, negotiated: Option<&str>) -> String {
+    let ms = |duration: Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+    let mut line = format!(
+        "{:<28} dns {:>9}  tcp {:>9}",
+        target,
+        ms(timings.dns),
+        ms(timings.tcp)
+    );
+    if let Some(tls) = timings.tls {
+        line.push_str(&format!("  tls {:>9}", ms(tls)));
+    }
+    line.push_str(&format!("  total {:>9}", ms(timings.total)));
+    if let Some(protocol) = negotiated {
+        line.push_str(&format!("  {}", protocol));
+    }
+    line
+}
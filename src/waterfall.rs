@@ -0,0 +1,194 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use crate::json::Value;
+
+/// Characters of the verbose sparkline, from least to most bytes
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Columns of the verbose sparkline
+const WIDTH: usize = 40;
+
+/// One piece of a response body, as it was read from the connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chunk {
+    /// Time since the transfer started
+    pub time: Duration,
+    pub bytes: usize,
+}
+
+/// The chunks of one transfer.
+#[derive(Debug, Clone, Default)]
+pub struct Transfer {
+    pub url: String,
+    pub chunks: Vec<Chunk>,
+}
+
+struct State {
+    started: Instant,
+    current: Transfer,
+    finished: Vec<Transfer>,
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+/// Start recording the body chunks of `url`, keeping the transfers before it.
+pub fn start(url: &str) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let finished = match state.take() {
+            Some(previous) => {
+                let mut finished = previous.finished;
+                finished.push(previous.current);
+                finished
+            }
+            None => Vec::new(),
+        };
+        *state = Some(State {
+            started: Instant::now(),
+            current: Transfer {
+                url: url.to_string(),
+                chunks: Vec::new(),
+            },
+            finished,
+        });
+    });
+}
+
+/// Record that `bytes` of the body arrived now; nothing is recorded before `start`.
+fn record(bytes: usize) {
+    STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            let time = state.started.elapsed();
+            state.current.chunks.push(Chunk { time, bytes });
+        }
+    });
+}
+
+/// Records each non-empty read of the body as a chunk.
+pub struct Recorder<R>(pub R);
+
+impl<R: Read> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        if n > 0 {
+            record(n);
+        }
+        Ok(n)
+    }
+}
+
+/// All transfers recorded so far, the current one last.
+pub fn transfers() -> Vec<Transfer> {
+    STATE.with(|state| match state.borrow().as_ref() {
+        Some(state) => {
+            let mut transfers = state.finished.clone();
+            transfers.push(state.current.clone());
+            transfers
+        }
+        None => Vec::new(),
+    })
+}
+
+/// The chunks of the current transfer.
+pub fn current() -> Vec<Chunk> {
+    STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .map(|state| state.current.chunks.clone())
+            .unwrap_or_default()
+    })
+}
+
+/// Render `transfers` as CSV: one `url,time,bytes,total` row per chunk, with the
+/// time in seconds since the transfer started and the running total of its bytes.
+pub fn to_csv(transfers: &[Transfer]) -> String {
+    let mut csv = String::from("url,time,bytes,total\n");
+    for transfer in transfers {
+        let mut total = 0;
+        for chunk in &transfer.chunks {
+            total += chunk.bytes;
+            csv.push_str(&format!(
+                "{},{:.6},{},{}\n",
+                transfer.url,
+                chunk.time.as_secs_f64(),
+                chunk.bytes,
+                total
+            ));
+        }
+    }
+    csv
+}
+
+/// Render `transfers` as a JSON array of `{"url", "chunks": [{"time", "bytes"}]}` objects.
+pub fn to_json(transfers: &[Transfer]) -> Value {
+    Value::Array(
+        transfers
+            .iter()
+            .map(|transfer| {
+                let chunks = transfer
+                    .chunks
+                    .iter()
+                    .map(|chunk| {
+                        Value::Object(vec![
+                            ("time".to_string(), Value::Number(chunk.time.as_secs_f64())),
+                            ("bytes".to_string(), Value::Number(chunk.bytes as f64)),
+                        ])
+                    })
+                    .collect();
+                Value::Object(vec![
+                    ("url".to_string(), Value::String(transfer.url.clone())),
+                    ("chunks".to_string(), Value::Array(chunks)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Write every transfer so far to `path`, as JSON if it ends in `.json` and CSV otherwise.
+pub fn save(path: &str) -> Result<(), String> {
+    let transfers = transfers();
+    let contents = if path.ends_with(".json") {
+        format!("{}\n", to_json(&transfers))
+    } else {
+        to_csv(&transfers)
+    };
+    fs::write(path, contents)
+        .map_err(|err| format!("Failed to write waterfall '{}': {}", path, err))
+}
+
+/// Draw the bytes received over time as a one-line sparkline.
+///
+/// The time from the first to the last chunk is split into equal columns, each
+/// showing the bytes that arrived in it relative to the busiest column. Columns
+/// in which nothing arrived are blank, so stalls stand out.
+pub fn sparkline(chunks: &[Chunk]) -> String {
+    let (Some(first), Some(last)) = (chunks.first(), chunks.last()) else {
+        return String::new();
+    };
+    let span = (last.time - first.time).as_secs_f64();
+    let mut columns = [0usize; WIDTH];
+    for chunk in chunks {
+        let offset = (chunk.time - first.time).as_secs_f64();
+        let column = if span > 0.0 {
+            ((offset / span) * (WIDTH - 1) as f64).round() as usize
+        } else {
+            0
+        };
+        columns[column.min(WIDTH - 1)] += chunk.bytes;
+    }
+    let used = if span > 0.0 { WIDTH } else { 1 };
+    let busiest = columns.iter().copied().max().unwrap_or(0).max(1);
+    columns[..used]
+        .iter()
+        .map(|&bytes| match bytes {
+            0 => ' ',
+            _ => LEVELS[((bytes * LEVELS.len() - 1) / busiest).min(LEVELS.len() - 1)],
+        })
+        .collect()
+}
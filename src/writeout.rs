@@ -0,0 +1,279 @@
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use crate::http::response;
+use rurl::status::StatusCode;
+use rurl::version::Version;
+
+/// Variables understood by `-w/--write-out`
+const VARIABLES: [&str; 21] = [
+    "content_type",
+    "content_type_sniffed",
+    "errormsg",
+    "http_code",
+    "http_version",
+    "num_redirects",
+    "remote_ip",
+    "remote_port",
+    "response_code",
+    "size_download",
+    "size_header",
+    "size_request",
+    "speed_download",
+    "time_appconnect",
+    "time_connect",
+    "time_namelookup",
+    "time_pretransfer",
+    "time_starttransfer",
+    "time_total",
+    "url",
+    "url_effective",
+];
+
+/// Points in a transfer whose time since the start is reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// The host name was resolved
+    NameLookup,
+    /// The TCP connection was established
+    Connect,
+    /// The TLS handshake finished
+    AppConnect,
+    /// The request is about to be sent
+    PreTransfer,
+    /// The response head arrived
+    StartTransfer,
+}
+
+/// What was measured while fetching one URL.
+///
+/// Timings are from the start of the transfer and describe the last hop when
+/// redirects were followed.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    started: Option<Instant>,
+    pub url: String,
+    pub url_effective: String,
+    pub http_code: Option<StatusCode>,
+    pub http_version: Option<Version>,
+    pub content_type: Option<String>,
+    /// The media type the body's magic bytes point to, if any matched
+    pub content_type_sniffed: Option<&'static str>,
+    /// Why the transfer failed, such as the phase that timed out
+    pub errormsg: Option<String>,
+    pub num_redirects: usize,
+    pub remote: Option<SocketAddr>,
+    pub size_download: u64,
+    pub size_header: u64,
+    pub size_request: u64,
+    pub time_namelookup: Option<Duration>,
+    pub time_connect: Option<Duration>,
+    pub time_appconnect: Option<Duration>,
+    pub time_pretransfer: Option<Duration>,
+    pub time_starttransfer: Option<Duration>,
+    pub time_total: Option<Duration>,
+}
+
+thread_local! {
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+}
+
+/// Start measuring the transfer of `url`, forgetting the previous one.
+pub fn start(url: &str) {
+    METRICS.with(|metrics| {
+        *metrics.borrow_mut() = Metrics {
+            started: Some(Instant::now()),
+            url: url.to_string(),
+            url_effective: url.to_string(),
+            ..Metrics::default()
+        }
+    });
+}
+
+/// Record that `event` happened now.
+pub fn mark(event: Event) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let Some(started) = metrics.started else {
+            return;
+        };
+        let elapsed = Some(started.elapsed());
+        match event {
+            Event::NameLookup => metrics.time_namelookup = elapsed,
+            Event::Connect => metrics.time_connect = elapsed,
+            Event::AppConnect => metrics.time_appconnect = elapsed,
+            Event::PreTransfer => metrics.time_pretransfer = elapsed,
+            Event::StartTransfer => metrics.time_starttransfer = elapsed,
+        }
+    });
+}
+
+/// Record the address the connection was made to.
+pub fn record_remote(addr: SocketAddr) {
+    METRICS.with(|metrics| metrics.borrow_mut().remote = Some(addr));
+}
+
+/// Record the size of a request sent.
+pub fn record_request(length: usize) {
+    METRICS.with(|metrics| metrics.borrow_mut().size_request += length as u64);
+}
+
+/// Record the final response: its head, the URL it came from, and the redirects before it.
+pub fn record_response(headers: &[u8], url: &str, redirects: usize) {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.http_code = response::parse_status(headers).ok();
+        metrics.http_version = response::parse_version(headers).ok();
+        metrics.content_type = response::find_header(headers, "content-type")
+            .map(|value| String::from_utf8_lossy(value).into_owned());
+        metrics.size_header = headers.len() as u64;
+        metrics.url_effective = url.to_string();
+        metrics.num_redirects = redirects;
+    });
+}
+
+/// Record the media type sniffed from the start of the body.
+pub fn record_sniffed(media_type: &'static str) {
+    METRICS.with(|metrics| metrics.borrow_mut().content_type_sniffed = Some(media_type));
+}
+
+/// Record the error that ended the transfer.
+pub fn record_error(message: &str) {
+    METRICS.with(|metrics| metrics.borrow_mut().errormsg = Some(message.to_string()));
+}
+
+/// Whether `template` uses the variable `name`, so it is worth the work to measure.
+pub fn uses(template: &str, name: &str) -> bool {
+    template.contains(&format!("%{{{}}}", name))
+}
+
+/// Counts the body bytes read through it as downloaded.
+pub struct Counter<R>(pub R);
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        METRICS.with(|metrics| metrics.borrow_mut().size_download += n as u64);
+        Ok(n)
+    }
+}
+
+/// Check that `template` only uses known variables.
+///
+/// # Returns
+///
+/// * `Result<(), &'static str>` - `Ok(())` if the template is valid, or an error message if it is not.
+pub fn validate(template: &str) -> Result<(), &'static str> {
+    let mut rest = template;
+    while let Some(start) = rest.find("%{") {
+        let end = rest[start..]
+            .find('}')
+            .ok_or("Unterminated %{ in --write-out")?;
+        if !VARIABLES.contains(&&rest[start + 2..start + end]) {
+            return Err("Unknown --write-out variable");
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+/// Fill in `template` with the measurements of the last transfer.
+///
+/// `%{name}` is replaced by the variable's value, `%%` by `%`, and `\n`, `\r`, `\t`
+/// by a newline, carriage return, and tab.
+pub fn render(template: &str, metrics: &Metrics) -> String {
+    let seconds = |time: Option<Duration>| format!("{:.6}", time.unwrap_or_default().as_secs_f64());
+    let mut output = String::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match (c, chars.peek().map(|&(_, next)| next)) {
+            ('%', Some('%')) => {
+                chars.next();
+                output.push('%');
+            }
+            ('%', Some('{')) => {
+                let Some(length) = template[index..].find('}') else {
+                    output.push_str(&template[index..]);
+                    break;
+                };
+                let name = &template[index + 2..index + length];
+                output.push_str(&match name {
+                    "content_type" => metrics.content_type.clone().unwrap_or_default(),
+                    "content_type_sniffed" => {
+                        metrics.content_type_sniffed.unwrap_or_default().to_string()
+                    }
+                    "errormsg" => metrics.errormsg.clone().unwrap_or_default(),
+                    "http_code" | "response_code" => {
+                        format!("{:03}", metrics.http_code.map_or(0, StatusCode::as_u16))
+                    }
+                    "http_version" => metrics
+                        .http_version
+                        .map(Version::number)
+                        .unwrap_or_default()
+                        .to_string(),
+                    "num_redirects" => metrics.num_redirects.to_string(),
+                    "remote_ip" => metrics
+                        .remote
+                        .map(|addr| addr.ip().to_string())
+                        .unwrap_or_default(),
+                    "remote_port" => metrics
+                        .remote
+                        .map(|addr| addr.port().to_string())
+                        .unwrap_or_default(),
+                    "size_download" => metrics.size_download.to_string(),
+                    "size_header" => metrics.size_header.to_string(),
+                    "size_request" => metrics.size_request.to_string(),
+                    "speed_download" => {
+                        let total = metrics.time_total.unwrap_or_default().as_secs_f64();
+                        let speed = if total > 0.0 {
+                            metrics.size_download as f64 / total
+                        } else {
+                            0.0
+                        };
+                        format!("{:.0}", speed)
+                    }
+                    "time_appconnect" => seconds(metrics.time_appconnect),
+                    "time_connect" => seconds(metrics.time_connect),
+                    "time_namelookup" => seconds(metrics.time_namelookup),
+                    "time_pretransfer" => seconds(metrics.time_pretransfer),
+                    "time_starttransfer" => seconds(metrics.time_starttransfer),
+                    "time_total" => seconds(metrics.time_total),
+                    "url" => metrics.url.clone(),
+                    "url_effective" => metrics.url_effective.clone(),
+                    _ => String::new(),
+                });
+                while chars.next_if(|&(at, _)| at <= index + length).is_some() {}
+            }
+            ('\\', Some(escaped @ ('n' | 'r' | 't' | '\\'))) => {
+                chars.next();
+                output.push(match escaped {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    _ => '\\',
+                });
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+/// The measurements so far, with the total time up to now.
+pub fn snapshot() -> Metrics {
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.time_total = metrics.started.map(|started| started.elapsed());
+        metrics.clone()
+    })
+}
+
+/// Finish measuring and print `template` to stdout.
+pub fn print(template: &str) {
+    let metrics = snapshot();
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(render(template, &metrics).as_bytes());
+    let _ = stdout.flush();
+}